@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use aoclib::solution::Part;
+
+// The answers file has one line per known answer: day, part, input name, and answer, separated
+// by tabs. Blank lines and lines starting with '#' are ignored.
+const HEADER : &str = "# day\tpart\tinput\tanswer\n";
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct AnswerKey {
+    pub day : u32,
+    pub part : Part,
+    pub input : String,
+}
+
+pub struct Answers {
+    answers : BTreeMap<AnswerKey, String>,
+}
+
+impl AnswerKey {
+    pub fn new(day : u32, part : Part, input : &str) -> AnswerKey {
+        AnswerKey {
+            day,
+            part,
+            input : String::from(input),
+        }
+    }
+}
+
+impl Answers {
+    pub fn parse(input : &str) -> Result<Answers, String> {
+        let mut answers = BTreeMap::new();
+
+        for (line_number, line) in input.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields = line.splitn(4, '\t').collect::<Vec<&str>>();
+            if fields.len() != 4 {
+                return Err(format!("line {}: expected day, part, input, and answer separated by tabs", line_number));
+            }
+
+            let day = fields[0].parse::<u32>().map_err(|_| {
+                format!("line {}: invalid day {}", line_number, fields[0])
+            })?;
+
+            let part = Part::parse(fields[1]).ok_or_else(|| {
+                format!("line {}: invalid part {}", line_number, fields[1])
+            })?;
+
+            let key = AnswerKey::new(day, part, fields[2]);
+            if answers.insert(key, String::from(fields[3])).is_some() {
+                return Err(format!("line {}: duplicate answer for day {} part {} [{}]", line_number, day, part, fields[2]));
+            }
+        }
+
+        Ok(Answers {
+            answers,
+        })
+    }
+
+    // A missing file just means nothing has been recorded yet.
+    pub fn load(path : &Path) -> Result<Answers, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Answers::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Answers::parse(""),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn get(&self, key : &AnswerKey) -> Option<&str> {
+        self.answers.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key : AnswerKey, answer : &str) {
+        self.answers.insert(key, String::from(answer));
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut output = String::from(HEADER);
+        for (key, answer) in self.answers.iter() {
+            output += &format!("{:02}\t{}\t{}\t{}\n", key.day, key.part, key.input, answer);
+        }
+        output
+    }
+
+    pub fn save(&self, path : &Path) -> io::Result<()> {
+        fs::write(path, self.serialize())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = "# comment\n\n02\tb\tinput\t275\n01\ta\texample_a\t3\n";
+        let answers = Answers::parse(input).unwrap();
+
+        assert_eq!(answers.get(&AnswerKey::new(1, Part::A, "example_a")), Some("3"));
+        assert_eq!(answers.get(&AnswerKey::new(1, Part::B, "example_a")), None);
+        assert_eq!(answers.serialize(), "# day\tpart\tinput\tanswer\n01\ta\texample_a\t3\n02\tb\tinput\t275\n");
+    }
+
+    #[test]
+    fn answer_with_spaces() {
+        let answers = Answers::parse("07\ta\tinput\ta b c").unwrap();
+        assert_eq!(answers.get(&AnswerKey::new(7, Part::A, "input")), Some("a b c"));
+    }
+
+    #[test]
+    fn bad_lines() {
+        assert!(Answers::parse("01\ta\tinput").is_err());
+        assert!(Answers::parse("xx\ta\tinput\t3").is_err());
+        assert!(Answers::parse("01\tc\tinput\t3").is_err());
+        assert!(Answers::parse("01\ta\tinput\t3\n01\ta\tinput\t4").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use aoclib::solution::{AnySolution, Part, SolveError};
+
+// The report is tab-separated with one line per day, part, and input, so reports from two commits
+// can be diffed or loaded into a spreadsheet. Times are in milliseconds.
+const HEADER : &str = "# day\tpart\tinput\titerations\tmin_ms\tmedian_ms\tmax_ms\n";
+
+pub struct Timing {
+    pub day : u32,
+    pub part : Part,
+    pub input : String,
+    samples : Vec<Duration>,
+}
+
+pub struct Report {
+    timings : Vec<Timing>,
+}
+
+pub fn duration_ms(duration : Duration) -> f64 {
+    (duration.as_secs() as f64 * 1000.0) + (duration.subsec_nanos() as f64 / 1000000.0)
+}
+
+impl Timing {
+    // Stops at the first iteration that fails, since the rest would fail the same way.
+    pub fn measure(solution : &dyn AnySolution, part : Part, input_name : &str, input : &str, iterations : u32) -> Result<Timing, SolveError> {
+        let mut samples = vec![];
+
+        for _ in 0 .. iterations {
+            let start = Instant::now();
+            solution.solve(part, input)?;
+            samples.push(start.elapsed());
+        }
+
+        samples.sort();
+
+        Ok(Timing {
+            day : solution.day(),
+            part,
+            input : String::from(input_name),
+            samples,
+        })
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn min_ms(&self) -> f64 {
+        duration_ms(self.samples[0])
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        duration_ms(self.samples[self.samples.len() - 1])
+    }
+
+    // With an even number of samples, this is the mean of the middle two.
+    pub fn median_ms(&self) -> f64 {
+        let middle = self.samples.len() / 2;
+        if self.samples.len().is_multiple_of(2) {
+            (duration_ms(self.samples[middle - 1]) + duration_ms(self.samples[middle])) / 2.0
+        } else {
+            duration_ms(self.samples[middle])
+        }
+    }
+}
+
+impl Report {
+    pub fn new() -> Report {
+        Report {
+            timings : vec![],
+        }
+    }
+
+    pub fn add(&mut self, timing : Timing) {
+        self.timings.push(timing);
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut output = String::from(HEADER);
+        for timing in self.timings.iter() {
+            output += &format!("{:02}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{:.3}\n", timing.day, timing.part, timing.input, timing.iterations(), timing.min_ms(), timing.median_ms(), timing.max_ms());
+        }
+        output
+    }
+}
+
+// Only the medians of an earlier report are needed to compare against.
+pub struct Baseline {
+    medians : BTreeMap<(u32, Part, String), f64>,
+}
+
+impl Baseline {
+    pub fn parse(input : &str) -> Result<Baseline, String> {
+        let mut medians = BTreeMap::new();
+
+        for (line_number, line) in input.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields = line.split('\t').collect::<Vec<&str>>();
+            if fields.len() != 7 {
+                return Err(format!("line {}: expected 7 tab-separated fields", line_number));
+            }
+
+            let day = fields[0].parse::<u32>().map_err(|_| format!("line {}: invalid day {}", line_number, fields[0]))?;
+            let part = Part::parse(fields[1]).ok_or_else(|| format!("line {}: invalid part {}", line_number, fields[1]))?;
+            let median = fields[5].parse::<f64>().map_err(|_| format!("line {}: invalid median {}", line_number, fields[5]))?;
+
+            medians.insert((day, part, String::from(fields[2])), median);
+        }
+
+        Ok(Baseline {
+            medians,
+        })
+    }
+
+    pub fn load(path : &Path) -> Result<Baseline, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Baseline::parse(&contents))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn median_ms(&self, timing : &Timing) -> Option<f64> {
+        self.medians.get(&(timing.day, timing.part, timing.input.clone())).cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timing(samples_ms : &[u64]) -> Timing {
+        let mut samples = samples_ms.iter().map(|ms| Duration::from_millis(*ms)).collect::<Vec<Duration>>();
+        samples.sort();
+
+        Timing {
+            day : 5,
+            part : Part::B,
+            input : String::from("input"),
+            samples,
+        }
+    }
+
+    #[test]
+    fn stats_odd() {
+        let timing = timing(&[30, 10, 20]);
+        assert_eq!(timing.min_ms(), 10.0);
+        assert_eq!(timing.median_ms(), 20.0);
+        assert_eq!(timing.max_ms(), 30.0);
+    }
+
+    #[test]
+    fn stats_even() {
+        let timing = timing(&[40, 10, 20, 30]);
+        assert_eq!(timing.median_ms(), 25.0);
+    }
+
+    #[test]
+    fn baseline_round_trip() {
+        let mut report = Report::new();
+        report.add(timing(&[1, 2, 3]));
+
+        let serialized = report.serialize();
+        assert_eq!(serialized, "# day\tpart\tinput\titerations\tmin_ms\tmedian_ms\tmax_ms\n05\tb\tinput\t3\t1.000\t2.000\t3.000\n");
+
+        let baseline = Baseline::parse(&serialized).unwrap();
+        assert_eq!(baseline.median_ms(&timing(&[5])), Some(2.0));
+    }
+}
@@ -0,0 +1,33 @@
+use aoclib::solution::Registry;
+
+// Every day's crate exposes a Solver implementing aoclib::solution::Solution. A new day only
+// needs to be added here to become runnable.
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.register(p01::Solver);
+    registry.register(p02::Solver);
+    registry.register(p03::Solver);
+    registry.register(p04::Solver);
+    registry.register(p05::Solver);
+    registry.register(p06::Solver);
+    registry.register(p07::Solver);
+    registry.register(p08::Solver);
+    registry.register(p09::Solver);
+    registry.register(p10::Solver);
+    registry.register(p11::Solver);
+    registry.register(p12::Solver);
+    registry.register(p13::Solver);
+    registry.register(p14::Solver);
+    registry.register(p15::Solver);
+    registry.register(p16::Solver);
+    registry.register(p17::Solver);
+    registry.register(p18::Solver);
+    registry.register(p19::Solver);
+    registry.register(p20::Solver);
+    registry.register(p21::Solver);
+    registry.register(p22::Solver);
+    registry.register(p23::Solver);
+    registry.register(p24::Solver);
+    registry.register(p25::Solver);
+    registry
+}
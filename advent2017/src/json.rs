@@ -0,0 +1,65 @@
+// Just enough JSON writing for the runner's machine-readable output, without pulling in a
+// serialization library.
+pub fn string(input : &str) -> String {
+    let mut output = String::from("\"");
+    for ch in input.chars() {
+        match ch {
+            '"' => output += "\\\"",
+            '\\' => output += "\\\\",
+            '\n' => output += "\\n",
+            '\r' => output += "\\r",
+            '\t' => output += "\\t",
+            ch if (ch as u32) < 0x20 => output += &format!("\\u{:04x}", ch as u32),
+            ch => output.push(ch),
+        }
+    }
+    output.push('"');
+    output
+}
+
+// Builds an object from already-serialized values, keeping fields in the order they're added.
+pub struct Object {
+    fields : Vec<(String, String)>,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object {
+            fields : vec![],
+        }
+    }
+
+    pub fn field(mut self, name : &str, value : String) -> Object {
+        self.fields.push((String::from(name), value));
+        self
+    }
+
+    pub fn string_field(self, name : &str, value : &str) -> Object {
+        self.field(name, string(value))
+    }
+
+    pub fn serialize(&self) -> String {
+        let fields = self.fields.iter().map(|(name, value)| format!("{}:{}", string(name), value)).collect::<Vec<String>>();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escape() {
+        assert_eq!(string("abc"), "\"abc\"");
+        assert_eq!(string("a \"b\"\n\\"), "\"a \\\"b\\\"\\n\\\\\"");
+        assert_eq!(string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn object() {
+        let inner = Object::new().string_field("total", "512");
+        let outer = Object::new().field("day", 4.to_string()).string_field("part", "a").field("secondary", inner.serialize());
+        assert_eq!(outer.serialize(), "{\"day\":4,\"part\":\"a\",\"secondary\":{\"total\":\"512\"}}");
+        assert_eq!(Object::new().serialize(), "{}");
+    }
+}
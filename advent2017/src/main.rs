@@ -0,0 +1,838 @@
+extern crate aoclib;
+
+extern crate p01;
+extern crate p02;
+extern crate p03;
+extern crate p04;
+extern crate p05;
+extern crate p06;
+extern crate p07;
+extern crate p08;
+extern crate p09;
+extern crate p10;
+extern crate p11;
+extern crate p12;
+extern crate p13;
+extern crate p14;
+extern crate p15;
+extern crate p16;
+extern crate p17;
+extern crate p18;
+extern crate p19;
+extern crate p20;
+extern crate p21;
+extern crate p22;
+extern crate p23;
+extern crate p24;
+extern crate p25;
+
+mod answers;
+mod bench;
+mod days;
+mod json;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+use aoclib::aocisa::cfg::Cfg;
+use aoclib::aocisa::RegisterHolder;
+use aoclib::aocisa::debugger::Debugger;
+use aoclib::aocisa::decompile::Pseudocode;
+use aoclib::aocisa::optimize::OptimizedProgram;
+use aoclib::aocisa::profile::Profiler;
+use aoclib::aocisa::snapshot::Snapshot;
+use aoclib::aocisa::source::Source;
+use aoclib::input;
+use aoclib::log;
+use aoclib::input::{Input, InputSource};
+use aoclib::simulation::Simulation;
+use aoclib::solution::{AnySolution, Part, Registry, SolveError, Solved};
+use answers::{AnswerKey, Answers};
+use bench::{Baseline, Report, Timing};
+use json::Object;
+
+const USAGE : &str =
+r"usage: advent2017 run (--day N | --days FIRST-LAST | --all) [--part a|b]
+                        [--input PATH | --inputs NAME[,NAME...] | --inputs all]
+                        [--format text|json]
+
+  --day N               run a single day. Its input is read from stdin unless
+                        --input or --inputs is given.
+  --days FIRST-LAST     run a range of days. By default each reads its named
+                        input, i.e. pNN/inputs/input.txt.
+  --all                 run every registered day
+  --part a|b            run only one part. By default both parts run.
+  --input PATH          read the input for a single day from a file
+  --inputs NAMES        run each day against the named inputs in pNN/inputs,
+                        e.g. example,input, or all for every one there.
+                        Names ending in _a or _b only run that part.
+  --format text|json    how to print answers. json prints one object holding every
+                        answer with its input, elapsed time, and secondary values.
+
+       advent2017 verify [--day N | --days FIRST-LAST | --all] [--part a|b]
+                         [--inputs NAME[,NAME...] | --inputs all] [--record]
+
+  Runs every selected day against its named inputs (all of them by default) and
+  checks the answers against answers.txt at the root of the workspace.
+  --record              add any answers that are missing from answers.txt
+
+       advent2017 bench [--day N | --days FIRST-LAST | --all] [--part a|b]
+                        [--inputs NAME[,NAME...] | --inputs all]
+                        [--iterations N] [--report PATH] [--baseline PATH]
+
+  Times each selected day's parts against its named input (input by default).
+  --iterations N        how many times to run each part. The default is 5.
+  --report PATH         write the timings as a tab-separated report
+  --baseline PATH       compare the median times against an earlier report
+
+       advent2017 debug --day N [--input PATH | --inputs NAME] [--set REG=VALUE]...
+
+  Steps through a day's input as an aocisa program, as in days 18 and 23, reading
+  debugger commands from stdin. Type help at the prompt for the commands.
+  The day's named input is used unless another is given.
+  --set REG=VALUE       set a register before the program starts, e.g. a=1
+
+       advent2017 disasm --day N [--input PATH | --inputs NAME]
+                         [--dot | --pseudocode | --optimize]
+
+  Prints a day's aocisa program split into basic blocks, with jumps resolved to
+  block labels and loop bodies indented. The day's named input is used unless
+  another is given.
+  --dot                 print the control-flow graph for Graphviz instead
+  --pseudocode          print the program as structured pseudocode instead, if
+                        its jumps can be made into ifs and loops
+  --optimize            print the program as text instead, with a comment above
+                        each loop that exec --optimize replaces
+
+       advent2017 profile --day N [--input PATH | --inputs NAME] [--set REG=VALUE]...
+                          [--top N] [--max-steps N]
+
+  Runs a day's aocisa program and reports the instructions, opcodes, and loops
+  that executed the most. The day's named input is used unless another is given.
+  --set REG=VALUE       set a register before the program starts, e.g. a=1
+  --top N               how many instructions and loops to list. The default is 10.
+  --max-steps N         stop after this many instructions. The default is 100000000.
+
+       advent2017 exec --day N [--input PATH | --inputs NAME] [--set REG=VALUE]...
+                       [--optimize] [--max-steps N [--pause PATH]] [--resume PATH]
+
+  Runs a day's aocisa program to the end and prints its registers. snd and rcv do
+  nothing. The day's named input is used unless another is given.
+  --set REG=VALUE       set a register before the program starts, e.g. a=1. With
+                        --resume, this overrides the snapshot's value.
+  --optimize            replace the loops that match known idioms, as in day 23
+  --max-steps N         stop after this many instructions, counting each one an
+                        optimized loop stands in for
+  --pause PATH          if the program stops at --max-steps, save a snapshot of it
+  --resume PATH         start from a snapshot instead of the beginning
+
+  Every command also takes:
+  --log LEVEL           show tracing from every day at LEVEL or more severe:
+                        off, error, warn, info, debug, or trace. The default is off.
+  --log-day N=LEVEL     show tracing from day N at LEVEL, overriding --log";
+
+const DEFAULT_BENCH_ITERATIONS : u32 = 5;
+
+const DEFAULT_PROFILE_TOP : usize = 10;
+
+const DEFAULT_PROFILE_MAX_STEPS : usize = 100_000_000;
+
+const DEFAULT_INPUT_NAME : &str = "input";
+
+enum DaySelection {
+    Single(u32),
+    Range(u32, u32),
+    All,
+}
+
+#[derive(PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+enum ListingFormat {
+    Blocks,
+    Dot,
+    Pseudocode,
+    Optimized,
+}
+
+enum InputSelection {
+    Default,
+    File(PathBuf),
+    Named(Vec<String>),
+    AllNamed,
+}
+
+struct RunOptions {
+    selection : DaySelection,
+    parts : Vec<Part>,
+    inputs : InputSelection,
+    record : bool,
+    iterations : u32,
+    report : Option<PathBuf>,
+    baseline : Option<PathBuf>,
+    format : OutputFormat,
+    registers : Vec<(char, i64)>,
+    listing : ListingFormat,
+    top : usize,
+    max_steps : Option<usize>,
+    optimize : bool,
+    pause : Option<PathBuf>,
+    resume : Option<PathBuf>,
+    log_filter : log::Filter,
+}
+
+fn usage_error(message : &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_day_number(input : &str) -> u32 {
+    input.parse::<u32>().unwrap_or_else(|_| {
+        usage_error(&format!("invalid day number {}", input))
+    })
+}
+
+fn parse_log_level(input : &str) -> log::Level {
+    log::Level::parse(input).unwrap_or_else(|| {
+        usage_error(&format!("invalid log level {}", input))
+    })
+}
+
+// Some options only make sense for some commands.
+fn require_command(command : &str, expected : &[&str], arg : &str) {
+    if !expected.contains(&command) {
+        usage_error(&format!("{} can only be used with {}", arg, expected.join(" or ")));
+    }
+}
+
+fn parse_run_options(command : &str, mut args : impl Iterator<Item = String>, default_selection : Option<DaySelection>) -> RunOptions {
+    let mut selection = default_selection;
+    let mut parts = vec![Part::A, Part::B];
+    let mut inputs = InputSelection::Default;
+    let mut record = false;
+    let mut iterations = DEFAULT_BENCH_ITERATIONS;
+    let mut report = None;
+    let mut baseline = None;
+    let mut format = OutputFormat::Text;
+    let mut registers = vec![];
+    let mut listing = ListingFormat::Blocks;
+    let mut top = DEFAULT_PROFILE_TOP;
+    let mut max_steps = None;
+    let mut optimize = false;
+    let mut pause = None;
+    let mut resume = None;
+    let mut log_filter = log::Filter::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name : &str| {
+            args.next().unwrap_or_else(|| usage_error(&format!("{} requires a value", name)))
+        };
+
+        match arg.as_str() {
+            "--day" => {
+                selection = Some(DaySelection::Single(parse_day_number(&value("--day"))));
+            },
+            "--days" => {
+                let range = value("--days");
+                let mut split = range.splitn(2, '-');
+                let first = parse_day_number(split.next().unwrap());
+                let last = split.next().map(parse_day_number).unwrap_or(first);
+                if first > last {
+                    usage_error(&format!("invalid day range {}: day {} comes after day {}", range, first, last));
+                }
+
+                selection = Some(DaySelection::Range(first, last));
+            },
+            "--all" => {
+                selection = Some(DaySelection::All);
+            },
+            "--part" => {
+                let part = value("--part");
+                parts = vec![Part::parse(&part).unwrap_or_else(|| {
+                    usage_error(&format!("invalid part {}", part))
+                })];
+            },
+            "--input" => {
+                inputs = InputSelection::File(PathBuf::from(value("--input")));
+            },
+            "--inputs" => {
+                let names = value("--inputs");
+                inputs = if names == "all" {
+                    InputSelection::AllNamed
+                } else {
+                    InputSelection::Named(names.split(',').map(String::from).collect())
+                };
+            },
+            "--record" => {
+                require_command(command, &["verify"], &arg);
+                record = true;
+            },
+            "--iterations" => {
+                require_command(command, &["bench"], &arg);
+                let count = value("--iterations");
+                iterations = match count.parse::<u32>() {
+                    Ok(count) if count > 0 => count,
+                    _ => usage_error(&format!("invalid iteration count {}", count)),
+                };
+            },
+            "--report" => {
+                require_command(command, &["bench"], &arg);
+                report = Some(PathBuf::from(value("--report")));
+            },
+            "--baseline" => {
+                require_command(command, &["bench"], &arg);
+                baseline = Some(PathBuf::from(value("--baseline")));
+            },
+            "--format" => {
+                require_command(command, &["run"], &arg);
+                format = match value("--format").as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    other => usage_error(&format!("invalid format {}", other)),
+                };
+            },
+            "--set" => {
+                require_command(command, &["debug", "profile", "exec"], &arg);
+                let setting = value("--set");
+                let mut split = setting.splitn(2, '=');
+                let register = split.next().unwrap();
+                let register_value = split.next().and_then(|v| v.parse::<i64>().ok());
+                match (register.chars().next(), register.chars().count(), register_value) {
+                    (Some(r), 1, Some(v)) => registers.push((r, v)),
+                    _ => usage_error(&format!("--set needs REG=VALUE, not {}", setting)),
+                }
+            },
+            "--dot" => {
+                require_command(command, &["disasm"], &arg);
+                listing = ListingFormat::Dot;
+            },
+            "--pseudocode" => {
+                require_command(command, &["disasm"], &arg);
+                listing = ListingFormat::Pseudocode;
+            },
+            "--top" => {
+                require_command(command, &["profile"], &arg);
+                let count = value("--top");
+                top = match count.parse::<usize>() {
+                    Ok(count) if count > 0 => count,
+                    _ => usage_error(&format!("invalid count {}", count)),
+                };
+            },
+            "--max-steps" => {
+                require_command(command, &["profile", "exec"], &arg);
+                let count = value("--max-steps");
+                max_steps = Some(count.parse::<usize>().unwrap_or_else(|_| {
+                    usage_error(&format!("invalid step count {}", count))
+                }));
+            },
+            "--optimize" => {
+                require_command(command, &["disasm", "exec"], &arg);
+                if command == "disasm" {
+                    listing = ListingFormat::Optimized;
+                } else {
+                    optimize = true;
+                }
+            },
+            "--pause" => {
+                require_command(command, &["exec"], &arg);
+                pause = Some(PathBuf::from(value("--pause")));
+            },
+            "--resume" => {
+                require_command(command, &["exec"], &arg);
+                resume = Some(PathBuf::from(value("--resume")));
+            },
+            "--log" => {
+                log_filter.set_default(parse_log_level(&value("--log")));
+            },
+            "--log-day" => {
+                let setting = value("--log-day");
+                let mut split = setting.splitn(2, '=');
+                let day = parse_day_number(split.next().unwrap());
+                let level = split.next().unwrap_or_else(|| usage_error(&format!("--log-day needs DAY=LEVEL, not {}", setting)));
+                log_filter.set_day(day, parse_log_level(level));
+            },
+            _ => usage_error(&format!("unknown argument {}", arg)),
+        }
+    }
+
+    let selection = selection.unwrap_or_else(|| usage_error("no days selected"));
+
+    if let InputSelection::File(_) = inputs {
+        if let DaySelection::Single(_) = selection {
+        } else {
+            usage_error("--input can only be used with --day");
+        }
+    }
+
+    if pause.is_some() && max_steps.is_none() {
+        usage_error("--pause needs --max-steps to say when to pause");
+    }
+
+    RunOptions {
+        selection,
+        parts,
+        inputs,
+        record,
+        iterations,
+        report,
+        baseline,
+        format,
+        registers,
+        listing,
+        top,
+        max_steps,
+        optimize,
+        pause,
+        resume,
+        log_filter,
+    }
+}
+
+// Each day's crate lives next to this one in the workspace, with its puzzle inputs alongside.
+fn day_inputs_dir(day : u32) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(format!("p{:02}", day))
+        .join("inputs")
+}
+
+fn input_sources(options : &RunOptions, day : u32) -> Vec<InputSource> {
+    match options.inputs {
+        InputSelection::Default => {
+            if let DaySelection::Single(_) = options.selection {
+                vec![InputSource::Stdin]
+            } else {
+                vec![InputSource::Named(String::from(DEFAULT_INPUT_NAME))]
+            }
+        },
+        InputSelection::File(ref path) => vec![InputSource::File(path.clone())],
+        InputSelection::Named(ref names) => {
+            names.iter().filter(|name| {
+                // When running many days, not every day has every kind of input, so only ask for
+                // the ones that exist.
+                let exists = input::named_input_path(&day_inputs_dir(day), name).is_file();
+                if !exists {
+                    eprintln!("day {:02} has no input named {}", day, name);
+                }
+                exists
+            }).map(|name| InputSource::Named(name.clone())).collect()
+        },
+        InputSelection::AllNamed => {
+            input::find_named_inputs(&day_inputs_dir(day)).unwrap_or_else(|e| {
+                eprintln!("failed to list inputs for day {:02}: {}", day, e);
+                vec![]
+            }).into_iter().map(InputSource::Named).collect()
+        },
+    }
+}
+
+fn load_input(solution : &dyn AnySolution, source : &InputSource) -> Input {
+    source.load(&day_inputs_dir(solution.day()), solution.trims_input()).unwrap_or_else(|e| {
+        eprintln!("failed to read input {:?} for day {:02}: {}", source, solution.day(), e);
+        process::exit(1);
+    })
+}
+
+fn selected_solutions<'r>(registry : &'r Registry, selection : &DaySelection) -> Vec<&'r dyn AnySolution> {
+    match *selection {
+        DaySelection::Single(number) => {
+            vec![registry.get(number).unwrap_or_else(|| {
+                usage_error(&format!("day {} is not registered", number))
+            })]
+        },
+        DaySelection::Range(first, last) => {
+            if let Some(missing) = (first ..= last).find(|&day| registry.get(day).is_none()) {
+                usage_error(&format!("day {} is not registered, so --days {}-{} can't be run", missing, first, last));
+            }
+
+            registry.iter().filter(|solution| solution.day() >= first && solution.day() <= last).collect()
+        },
+        DaySelection::All => registry.iter().collect(),
+    }
+}
+
+// Puzzle examples are often only meaningful for one part, so those inputs are named to say so.
+fn input_applies_to_part(source : &InputSource, part : Part) -> bool {
+    if let InputSource::Named(ref name) = *source {
+        input::named_input_part(name).is_none_or(|only| only == part)
+    } else {
+        true
+    }
+}
+
+fn json_result(day : u32, part : Part, input_name : &str, solved : &Solved, elapsed_ms : f64) -> String {
+    let secondary = solved.secondary.iter().fold(Object::new(), |object, &(name, ref value)| {
+        object.string_field(name, value)
+    });
+
+    Object::new()
+        .field("day", day.to_string())
+        .string_field("part", part.name())
+        .string_field("input", input_name)
+        .string_field("answer", &solved.answer)
+        .field("elapsed_ms", format!("{:.3}", elapsed_ms))
+        .field("secondary", secondary.serialize())
+        .serialize()
+}
+
+// Bad input is reported and skipped so that the other days still run.
+fn print_solve_error(label : &str, error : &SolveError) {
+    eprintln!("{}: {}", label, error);
+}
+
+fn run(options : &RunOptions) {
+    let registry = days::registry();
+    let run_start = Instant::now();
+    let mut json_results = vec![];
+    let mut errors = 0;
+
+    for solution in selected_solutions(&registry, &options.selection) {
+        for source in input_sources(options, solution.day()) {
+            let input = load_input(solution, &source);
+
+            for part in options.parts.iter().filter(|part| input_applies_to_part(&source, **part)) {
+                let start = Instant::now();
+                let solved = match solution.solve_detailed(*part, &input.contents) {
+                    Ok(solved) => solved,
+                    Err(e) => {
+                        print_solve_error(&format!("day {:02} part {} [{}]", solution.day(), part, input.name), &e);
+                        errors += 1;
+                        continue;
+                    },
+                };
+                let elapsed_ms = bench::duration_ms(start.elapsed());
+
+                match options.format {
+                    OutputFormat::Text => println!("day {:02} part {} [{}]: {}", solution.day(), part, input.name, solved.answer),
+                    OutputFormat::Json => json_results.push(json_result(solution.day(), *part, &input.name, &solved, elapsed_ms)),
+                }
+            }
+        }
+    }
+
+    // Everything is printed at the end so that the output is a single JSON document.
+    if options.format == OutputFormat::Json {
+        println!("{{\"results\":[\n{}\n],\"elapsed_ms\":{:.3}}}", json_results.join(",\n"), bench::duration_ms(run_start.elapsed()));
+    }
+
+    if errors > 0 {
+        process::exit(1);
+    }
+}
+
+fn answers_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("answers.txt")
+}
+
+// Shows where two answers first differ, which matters for long answers like knot hashes.
+fn print_diff(expected : &str, actual : &str) {
+    let first_difference = expected.chars().zip(actual.chars()).take_while(|&(e, a)| e == a).count();
+    println!("    expected: {}", expected);
+    println!("    actual:   {}", actual);
+    println!("              {}^", " ".repeat(first_difference));
+}
+
+fn verify(options : &mut RunOptions) {
+    match options.inputs {
+        InputSelection::Default => options.inputs = InputSelection::AllNamed,
+        InputSelection::File(_) => usage_error("verify only checks named inputs"),
+        _ => {},
+    }
+
+    let path = answers_path();
+    let mut answers = Answers::load(&path).unwrap_or_else(|e| {
+        eprintln!("failed to load answers: {}", e);
+        process::exit(1);
+    });
+
+    let registry = days::registry();
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+
+    for solution in selected_solutions(&registry, &options.selection) {
+        for source in input_sources(options, solution.day()) {
+            let input = load_input(solution, &source);
+
+            for part in options.parts.iter().filter(|part| input_applies_to_part(&source, **part)) {
+                let key = AnswerKey::new(solution.day(), *part, &input.name);
+                let label = format!("day {:02} part {} [{}]", key.day, key.part, key.input);
+
+                // A broken day shouldn't stop the rest from being checked.
+                let actual = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                    solution.solve(*part, &input.contents)
+                }));
+
+                let actual = match actual {
+                    Ok(Ok(actual)) => actual,
+                    Ok(Err(e)) => {
+                        println!("FAIL    {}: {}", label, e);
+                        failed += 1;
+                        continue;
+                    },
+                    Err(_) => {
+                        println!("FAIL    {}: panicked", label);
+                        failed += 1;
+                        continue;
+                    },
+                };
+
+                let expected = answers.get(&key).map(String::from);
+                match expected {
+                    Some(ref expected) if *expected == actual => {
+                        println!("PASS    {}: {}", label, actual);
+                        passed += 1;
+                    },
+                    Some(ref expected) => {
+                        println!("FAIL    {}", label);
+                        print_diff(expected, &actual);
+                        failed += 1;
+                    },
+                    None => {
+                        println!("MISSING {}: {}", label, actual);
+                        missing += 1;
+                        if options.record {
+                            answers.insert(key, &actual);
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+
+    if options.record && missing > 0 {
+        answers.save(&path).unwrap_or_else(|e| {
+            eprintln!("failed to save answers to {}: {}", path.display(), e);
+            process::exit(1);
+        });
+        println!("recorded {} answers in {}", missing, path.display());
+    }
+
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+fn bench(options : &mut RunOptions) {
+    match options.inputs {
+        InputSelection::Default => options.inputs = InputSelection::Named(vec![String::from(DEFAULT_INPUT_NAME)]),
+        InputSelection::File(_) => usage_error("bench only times named inputs"),
+        _ => {},
+    }
+
+    let baseline = options.baseline.as_ref().map(|path| {
+        Baseline::load(path).unwrap_or_else(|e| {
+            eprintln!("failed to load baseline: {}", e);
+            process::exit(1);
+        })
+    });
+
+    let registry = days::registry();
+    let mut report = Report::new();
+    let mut errors = 0;
+
+    for solution in selected_solutions(&registry, &options.selection) {
+        for source in input_sources(options, solution.day()) {
+            let input = load_input(solution, &source);
+
+            for part in options.parts.iter().filter(|part| input_applies_to_part(&source, **part)) {
+                let timing = match Timing::measure(solution, *part, &input.name, &input.contents, options.iterations) {
+                    Ok(timing) => timing,
+                    Err(e) => {
+                        print_solve_error(&format!("day {:02} part {} [{}]", solution.day(), part, input.name), &e);
+                        errors += 1;
+                        continue;
+                    },
+                };
+
+                let comparison = match baseline.as_ref().and_then(|baseline| baseline.median_ms(&timing)) {
+                    Some(baseline_ms) if baseline_ms > 0.0 => {
+                        format!(" ({:+.1}% vs baseline {:.3} ms)", (timing.median_ms() - baseline_ms) * 100.0 / baseline_ms, baseline_ms)
+                    },
+                    _ => String::new(),
+                };
+
+                println!("day {:02} part {} [{}]: min {:.3} ms, median {:.3} ms, max {:.3} ms{}", timing.day, timing.part, timing.input, timing.min_ms(), timing.median_ms(), timing.max_ms(), comparison);
+                report.add(timing);
+            }
+        }
+    }
+
+    if let Some(ref path) = options.report {
+        fs::write(path, report.serialize()).unwrap_or_else(|e| {
+            eprintln!("failed to write report to {}: {}", path.display(), e);
+            process::exit(1);
+        });
+    }
+
+    if errors > 0 {
+        process::exit(1);
+    }
+}
+
+// The aocisa commands work on one day's input, read as a program. The debugger's commands come
+// from stdin, so the program can't, and the others follow suit by using the named input.
+fn load_single_input(options : &mut RunOptions, command : &str) -> Input {
+    match options.selection {
+        DaySelection::Single(_) => {},
+        _ => usage_error(&format!("{} works on a single day", command)),
+    }
+
+    if let InputSelection::Default = options.inputs {
+        options.inputs = InputSelection::Named(vec![String::from(DEFAULT_INPUT_NAME)]);
+    }
+
+    let registry = days::registry();
+    let solution = selected_solutions(&registry, &options.selection)[0];
+    let sources = input_sources(options, solution.day());
+    if sources.len() != 1 {
+        usage_error(&format!("{} needs exactly one input", command));
+    }
+
+    load_input(solution, &sources[0])
+}
+
+// Parses a single input as an aocisa program, keeping its text so output can point at lines.
+fn load_source(options : &mut RunOptions, command : &str) -> Source {
+    let input = load_single_input(options, command);
+    Source::parse(&input.contents).unwrap_or_else(|e| {
+        eprintln!("input {} is not an aocisa program: {}", input.name, e);
+        process::exit(1);
+    })
+}
+
+fn debug(options : &mut RunOptions) {
+    let source = load_source(options, "debug");
+    let mut debugger = Debugger::for_source(&source);
+    for &(register, value) in options.registers.iter() {
+        *debugger.registers_mut().get_reg_mut(register) = value;
+    }
+
+    let stdin = io::stdin();
+    debugger.interact(stdin.lock(), io::stdout()).unwrap_or_else(|e| {
+        eprintln!("debugger failed: {}", e);
+        process::exit(1);
+    });
+}
+
+fn disasm(options : &mut RunOptions) {
+    let source = load_source(options, "disasm");
+    let program = source.program();
+    match options.listing {
+        ListingFormat::Blocks => println!("{}", Cfg::build(program)),
+        ListingFormat::Dot => println!("{}", Cfg::build(program).to_dot()),
+        ListingFormat::Optimized => print!("{}", OptimizedProgram::for_source(&source)),
+        ListingFormat::Pseudocode => {
+            match Pseudocode::decompile(program) {
+                Ok(pseudocode) => print!("{}", pseudocode),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                },
+            }
+        },
+    }
+}
+
+fn profile(options : &mut RunOptions) {
+    let source = load_source(options, "profile");
+    let mut profiler = Profiler::for_source(&source);
+    for &(register, value) in options.registers.iter() {
+        *profiler.registers_mut().get_reg_mut(register) = value;
+    }
+
+    let max_steps = options.max_steps.unwrap_or(DEFAULT_PROFILE_MAX_STEPS);
+    profiler.run_for(max_steps);
+    if !profiler.is_halted() {
+        println!("stopped after {} steps without halting\n", max_steps);
+    }
+
+    println!("{}", profiler.report(options.top));
+}
+
+// Runs on plain registers rather than through the profiler or debugger, so that long runs like
+// day 23's can go at full speed, and be paused into a snapshot and resumed later.
+fn exec(options : &mut RunOptions) {
+    let source = load_source(options, "exec");
+    let program = if options.optimize {
+        OptimizedProgram::for_source(&source)
+    } else {
+        OptimizedProgram::plain(source.program())
+    };
+
+    let mut registers = RegisterHolder::new();
+    let (mut ip, mut steps) = (0, 0);
+    if let Some(ref path) = options.resume {
+        let resumed = Snapshot::load(path).and_then(|snapshot| program.restore(&mut registers, &snapshot));
+        match resumed {
+            Ok((resumed_ip, resumed_steps)) => {
+                ip = resumed_ip;
+                steps = resumed_steps;
+            },
+            Err(e) => {
+                eprintln!("can't resume from {}: {}", path.display(), e);
+                process::exit(1);
+            },
+        }
+    }
+
+    for &(register, value) in options.registers.iter() {
+        *registers.get_reg_mut(register) = value;
+    }
+
+    // Steps are instructions of the original program even when it's optimized, so a run paused
+    // after some number of them stops in the same place either way.
+    let max_steps = options.max_steps.map_or(u64::MAX, |max_steps| max_steps as u64);
+    let (ip, ran) = program.run_from(&mut registers, ip, max_steps);
+    steps += ran;
+
+    if ip < program.len() {
+        let line = source.line_of(ip).map_or(String::new(), |line| format!(" (line {})", line));
+        println!("paused at {}{} after {} instructions", ip, line, steps);
+        if let Some(ref path) = options.pause {
+            program.snapshot(&registers, ip, steps).save(path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+            println!("saved to {}", path.display());
+        }
+    } else {
+        println!("halted after {} instructions", steps);
+    }
+
+    let values = registers.values().iter().map(|&(r, value)| format!("{}={}", r, value)).collect::<Vec<String>>();
+    println!("{}", values.join(" "));
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| usage_error("no command given"));
+
+    let default_selection = match command.as_str() {
+        "run" | "debug" | "disasm" | "profile" | "exec" => None,
+        "verify" | "bench" => Some(DaySelection::All),
+        _ => usage_error(&format!("unknown command {}", command)),
+    };
+
+    let mut options = parse_run_options(&command, args, default_selection);
+    log::set_filter(options.log_filter.clone());
+
+    match command.as_str() {
+        "run" => run(&options),
+        "verify" => verify(&mut options),
+        "bench" => bench(&mut options),
+        "debug" => debug(&mut options),
+        "disasm" => disasm(&mut options),
+        "profile" => profile(&mut options),
+        "exec" => exec(&mut options),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use aocisa::snapshot::{ProcessState, Snapshot};
+use aocisa::source::Source;
+use parse_error::ParseError;
+
+pub mod bytecode;
+pub mod cfg;
+pub mod debugger;
+pub mod decompile;
+pub mod dialect;
+pub mod optimize;
+pub mod profile;
+pub mod scheduler;
+pub mod snapshot;
+pub mod source;
+pub mod symbolic;
+#[cfg(test)] mod testing;
+
+// The little assembly language from days 18 and 23.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RegisterOrValue {
+    Reg(char),
+    Val(i64),
+}
+
+#[derive(Clone)]
+pub enum Instruction {
+    Snd(RegisterOrValue),
+    Set(char, RegisterOrValue),
+    Add(char, RegisterOrValue),
+    Sub(char, RegisterOrValue),
+    Mul(char, RegisterOrValue),
+    Mod(char, RegisterOrValue),
+    Rcv(char),
+    Jgz(RegisterOrValue, RegisterOrValue),
+    Jnz(RegisterOrValue, RegisterOrValue),
+
+    // An opcode that only some dialect has, with its operands in order.
+    Custom(&'static str, Vec<RegisterOrValue>),
+}
+
+#[derive(Clone)]
+pub struct Program {
+    pub instructions : Vec<Instruction>,
+}
+
+// Every register starts out at 0.
+pub struct RegisterHolder {
+    registers : HashMap<char, i64>,
+}
+
+static ZERO : i64 = 0;
+
+impl fmt::Display for RegisterOrValue {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegisterOrValue::Reg(reg) => write!(f, "{}", reg),
+            RegisterOrValue::Val(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Snd(x) => write!(f, "snd {}", x),
+            Instruction::Set(r, x) => write!(f, "set {} {}", r, x),
+            Instruction::Add(r, x) => write!(f, "add {} {}", r, x),
+            Instruction::Sub(r, x) => write!(f, "sub {} {}", r, x),
+            Instruction::Mul(r, x) => write!(f, "mul {} {}", r, x),
+            Instruction::Mod(r, x) => write!(f, "mod {} {}", r, x),
+            Instruction::Rcv(r) => write!(f, "rcv {}", r),
+            Instruction::Jgz(x, y) => write!(f, "jgz {} {}", x, y),
+            Instruction::Jnz(x, y) => write!(f, "jnz {} {}", x, y),
+            Instruction::Custom(name, operands) => {
+                write!(f, "{}", name)?;
+                for operand in operands.iter() {
+                    write!(f, " {}", operand)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Instruction {
+    // The operands in the order they're written, with the registers that get written to as Reg.
+    pub fn operands(&self) -> Vec<RegisterOrValue> {
+        match self {
+            Instruction::Snd(x) => vec![*x],
+            Instruction::Set(r, x) |
+            Instruction::Add(r, x) |
+            Instruction::Sub(r, x) |
+            Instruction::Mul(r, x) |
+            Instruction::Mod(r, x) => vec![RegisterOrValue::Reg(*r), *x],
+            Instruction::Rcv(r) => vec![RegisterOrValue::Reg(*r)],
+            Instruction::Jgz(x, y) |
+            Instruction::Jnz(x, y) => vec![*x, *y],
+            Instruction::Custom(_, operands) => operands.clone(),
+        }
+    }
+}
+
+impl Program {
+    // Reads one instruction per line, in the same format as Source, which also keeps the text.
+    pub fn load(input : &str) -> Result<Program, ParseError> {
+        Source::parse(input).map(Source::into_program)
+    }
+}
+
+// One instruction per line, in the same format load reads.
+impl fmt::Display for Program {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        for inst in self.instructions.iter() {
+            writeln!(f, "{}", inst)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl RegisterHolder {
+    pub fn new() -> RegisterHolder {
+        RegisterHolder {
+            registers : HashMap::new(),
+        }
+    }
+
+    pub fn get_reg(&self, reg : char) -> &i64 {
+        self.registers.get(&reg).unwrap_or(&ZERO)
+    }
+
+    pub fn get_reg_mut(&mut self, reg : char) -> &mut i64 {
+        self.registers.entry(reg).or_insert(0)
+    }
+
+    pub fn evaluate(&self, rv : &RegisterOrValue) -> i64 {
+        match rv {
+            RegisterOrValue::Reg(reg) => *self.get_reg(*reg),
+            RegisterOrValue::Val(value) => *value,
+        }
+    }
+
+    // Writes to the register that an operand names. Operands that get written to only ever parse
+    // as registers, so anything else is a mistake in the caller.
+    pub fn store(&mut self, target : &RegisterOrValue, value : i64) {
+        match target {
+            RegisterOrValue::Reg(reg) => *self.get_reg_mut(*reg) = value,
+            RegisterOrValue::Val(_) => panic!("can't store {} into {}", value, target),
+        }
+    }
+
+    // Carries out the instructions that only touch registers. Sending, receiving, jumping, and
+    // whatever a dialect's own opcodes do are up to the caller.
+    pub fn apply_instruction(&mut self, inst : &Instruction) {
+        match inst {
+            Instruction::Set(reg, x) => {
+                let value = self.evaluate(x);
+                *self.get_reg_mut(*reg) = value;
+            },
+            Instruction::Add(reg, x) => {
+                let value = self.evaluate(x);
+                *self.get_reg_mut(*reg) += value;
+            },
+            Instruction::Sub(reg, x) => {
+                let value = self.evaluate(x);
+                *self.get_reg_mut(*reg) -= value;
+            },
+            Instruction::Mul(reg, x) => {
+                let value = self.evaluate(x);
+                *self.get_reg_mut(*reg) *= value;
+            },
+            Instruction::Mod(reg, x) => {
+                let value = self.evaluate(x);
+                *self.get_reg_mut(*reg) %= value;
+            },
+            Instruction::Snd(..) |
+            Instruction::Rcv(..) |
+            Instruction::Jgz(..) |
+            Instruction::Jnz(..) |
+            Instruction::Custom(..) => {},
+        }
+    }
+
+    // Where execution goes after inst, taking jumps into account.
+    pub fn get_next_ip(&self, inst : &Instruction, ip : usize) -> usize {
+        let offset = match inst {
+            Instruction::Jgz(x, y) if self.evaluate(x) > 0 => self.evaluate(y),
+            Instruction::Jnz(x, y) if self.evaluate(x) != 0 => self.evaluate(y),
+            _ => 1,
+        };
+
+        ((ip as i64) + offset) as usize
+    }
+}
+
+impl RegisterHolder {
+    // Every register that's been touched, in alphabetical order.
+    pub fn values(&self) -> Vec<(char, i64)> {
+        let mut values = self.registers.iter().map(|(&r, &value)| (r, value)).collect::<Vec<(char, i64)>>();
+        values.sort();
+        values
+    }
+
+    // These registers as a single machine, partway through a program with the given number of
+    // instructions.
+    pub fn snapshot(&self, instructions : usize, ip : usize, steps : u64) -> Snapshot {
+        Snapshot {
+            processes : vec![ProcessState {
+                instructions,
+                ip,
+                steps,
+                registers : self.values(),
+                ..ProcessState::default()
+            }],
+            channels : vec![],
+        }
+    }
+
+    // Takes the registers from a single machine's snapshot and returns where it was and how many
+    // steps it had taken. Fails without changing anything if the snapshot isn't of a single
+    // machine running a program with the given number of instructions.
+    pub fn restore(&mut self, snapshot : &Snapshot, instructions : usize) -> Result<(usize, u64), String> {
+        let state = snapshot.single(instructions)?;
+        self.registers = state.registers.iter().cloned().collect();
+        Ok((state.ip, state.steps))
+    }
+}
+
+impl Default for RegisterHolder {
+    fn default() -> RegisterHolder {
+        RegisterHolder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registers() {
+        let program = Program::load("set a 5\nmul a a\nmod a 7\njgz a -2").unwrap();
+        let mut registers = RegisterHolder::new();
+        for inst in program.instructions.iter().take(3) {
+            registers.apply_instruction(inst);
+        }
+
+        assert_eq!(*registers.get_reg('a'), 4);
+        assert_eq!(*registers.get_reg('b'), 0);
+        assert_eq!(registers.get_next_ip(&program.instructions[3], 3), 1);
+        assert_eq!(program.to_string(), "set a 5\nmul a a\nmod a 7\njgz a -2\n");
+    }
+}
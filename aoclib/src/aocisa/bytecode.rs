@@ -0,0 +1,386 @@
+use aocisa::{Instruction, Program, RegisterOrValue};
+use aocisa::snapshot::{ProcessState, Snapshot};
+
+// An operand with the register lookup already done: either an index into the machine's register
+// slots or a constant.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operand {
+    Slot(usize),
+    Value(i64),
+}
+
+// One instruction, with registers replaced by slot indices.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Op {
+    Snd(Operand),
+    Set(usize, Operand),
+    Add(usize, Operand),
+    Sub(usize, Operand),
+    Mul(usize, Operand),
+    Mod(usize, Operand),
+    Rcv(usize),
+    Jgz(Operand, Operand),
+    Jnz(Operand, Operand),
+}
+
+// A program decoded once up front, so running it doesn't have to match on RegisterOrValue or
+// look registers up by name on every step.
+pub struct Bytecode {
+    pub ops : Vec<Op>,
+
+    // The register name for each slot, in the order they first appear in the program.
+    names : Vec<char>,
+}
+
+// What happened when the machine took a step. snd and rcv mean something different on each day,
+// so they're handed back to the caller instead of being carried out here.
+#[derive(PartialEq, Debug)]
+pub enum Step {
+    // Executed an instruction that only touches registers or the instruction pointer.
+    Ran,
+    // Executed a snd with this value.
+    Sent(i64),
+    // Stopped at a rcv into this register. The instruction pointer doesn't move until the caller
+    // calls receive or skip_receive.
+    Receiving(char),
+    // The instruction pointer is outside the program.
+    Halted,
+}
+
+pub struct Machine<'b> {
+    code : &'b Bytecode,
+    slots : Vec<i64>,
+    ip : usize,
+    steps : u64,
+}
+
+// The slot for a register, giving it a new one if it hasn't been seen yet.
+fn slot_for(names : &mut Vec<char>, register : char) -> usize {
+    match names.iter().position(|&name| name == register) {
+        Some(slot) => slot,
+        None => {
+            names.push(register);
+            names.len() - 1
+        },
+    }
+}
+
+fn operand_for(names : &mut Vec<char>, rv : &RegisterOrValue) -> Operand {
+    match *rv {
+        RegisterOrValue::Reg(r) => Operand::Slot(slot_for(names, r)),
+        RegisterOrValue::Val(v) => Operand::Value(v),
+    }
+}
+
+impl Bytecode {
+    // Only the shared opcodes compile. A program with some dialect's own opcodes has to run on
+    // that dialect's Interpreter instead.
+    pub fn compile(program : &Program) -> Bytecode {
+        let mut names = vec![];
+
+        let ops = program.instructions.iter().map(|inst| {
+            let names = &mut names;
+            match *inst {
+                Instruction::Snd(ref x) => Op::Snd(operand_for(names, x)),
+                Instruction::Set(r, ref x) => Op::Set(slot_for(names, r), operand_for(names, x)),
+                Instruction::Add(r, ref x) => Op::Add(slot_for(names, r), operand_for(names, x)),
+                Instruction::Sub(r, ref x) => Op::Sub(slot_for(names, r), operand_for(names, x)),
+                Instruction::Mul(r, ref x) => Op::Mul(slot_for(names, r), operand_for(names, x)),
+                Instruction::Mod(r, ref x) => Op::Mod(slot_for(names, r), operand_for(names, x)),
+                Instruction::Rcv(r) => Op::Rcv(slot_for(names, r)),
+                Instruction::Jgz(ref x, ref y) => Op::Jgz(operand_for(names, x), operand_for(names, y)),
+                Instruction::Jnz(ref x, ref y) => Op::Jnz(operand_for(names, x), operand_for(names, y)),
+                Instruction::Custom(name, _) => panic!("bytecode has no {} opcode", name),
+            }
+        }).collect();
+
+        Bytecode {
+            ops,
+            names,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    // The slot holding the given register, or None if the program never mentions it.
+    pub fn slot(&self, register : char) -> Option<usize> {
+        self.names.iter().position(|&name| name == register)
+    }
+
+    pub fn registers(&self) -> &[char] {
+        &self.names
+    }
+}
+
+impl<'b> Machine<'b> {
+    pub fn new(code : &'b Bytecode) -> Machine<'b> {
+        Machine {
+            code,
+            slots : vec![0; code.names.len()],
+            ip : 0,
+            steps : 0,
+        }
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.ip >= self.code.len()
+    }
+
+    // The register the current instruction receives into, if it's a rcv.
+    pub fn waiting_on(&self) -> Option<char> {
+        match self.code.ops.get(self.ip) {
+            Some(&Op::Rcv(r)) => Some(self.code.names[r]),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'b Bytecode {
+        self.code
+    }
+
+    // Registers the program never mentions are always zero.
+    pub fn get(&self, register : char) -> i64 {
+        self.code.slot(register).map_or(0, |slot| self.slots[slot])
+    }
+
+    // Every register the program mentions and its value, in slot order.
+    pub fn registers(&self) -> Vec<(char, i64)> {
+        self.code.names.iter().cloned().zip(self.slots.iter().cloned()).collect()
+    }
+
+    // Setting a register the program never mentions can't affect anything, so it's ignored.
+    pub fn set(&mut self, register : char, value : i64) {
+        if let Some(slot) = self.code.slot(register) {
+            self.slots[slot] = value;
+        }
+    }
+
+    // The machine's part of a snapshot. It doesn't know about sends and receives, so those are 0.
+    pub fn process_state(&self) -> ProcessState {
+        ProcessState {
+            instructions : self.code.len(),
+            ip : self.ip,
+            steps : self.steps,
+            sends : 0,
+            receives : 0,
+            registers : self.registers(),
+        }
+    }
+
+    // Fails without changing anything if the state is of a program with a different length, or has
+    // a register the program doesn't mention.
+    pub fn restore_process(&mut self, state : &ProcessState) -> Result<(), String> {
+        state.check_instructions(self.code.len())?;
+        let mut slots = vec![0; self.slots.len()];
+        for &(register, value) in state.registers.iter() {
+            let slot = self.code.slot(register).ok_or_else(|| format!("register {} isn't in the program", register))?;
+            slots[slot] = value;
+        }
+
+        self.slots = slots;
+        self.ip = state.ip;
+        self.steps = state.steps;
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            processes : vec![self.process_state()],
+            channels : vec![],
+        }
+    }
+
+    pub fn restore(&mut self, snapshot : &Snapshot) -> Result<(), String> {
+        if !snapshot.channels.is_empty() {
+            return Err(format!("snapshot has {} channels, not a single machine", snapshot.channels.len()));
+        }
+
+        let state = snapshot.single(self.code.len())?;
+        self.restore_process(state)
+    }
+
+    fn value(&self, operand : Operand) -> i64 {
+        match operand {
+            Operand::Slot(slot) => self.slots[slot],
+            Operand::Value(v) => v,
+        }
+    }
+
+    pub fn step(&mut self) -> Step {
+        if self.is_halted() {
+            return Step::Halted;
+        }
+
+        let mut result = Step::Ran;
+        let mut offset = 1;
+        match self.code.ops[self.ip] {
+            Op::Snd(x) => {
+                result = Step::Sent(self.value(x));
+            },
+            Op::Set(r, x) => {
+                self.slots[r] = self.value(x);
+            },
+            Op::Add(r, x) => {
+                self.slots[r] += self.value(x);
+            },
+            Op::Sub(r, x) => {
+                self.slots[r] -= self.value(x);
+            },
+            Op::Mul(r, x) => {
+                self.slots[r] *= self.value(x);
+            },
+            Op::Mod(r, x) => {
+                self.slots[r] %= self.value(x);
+            },
+            Op::Rcv(r) => {
+                return Step::Receiving(self.code.names[r]);
+            },
+            Op::Jgz(x, y) => {
+                if self.value(x) > 0 {
+                    offset = self.value(y);
+                }
+            },
+            Op::Jnz(x, y) => {
+                if self.value(x) != 0 {
+                    offset = self.value(y);
+                }
+            },
+        }
+
+        // Same arithmetic as RegisterHolder::get_next_ip, so jumping off the front of the program
+        // wraps around to a huge instruction pointer and halts.
+        self.ip = ((self.ip as i64) + offset) as usize;
+        self.steps += 1;
+        result
+    }
+
+    // Steps until something the caller has to deal with: a snd, a rcv, or the end of the program.
+    pub fn run(&mut self) -> Step {
+        loop {
+            match self.step() {
+                Step::Ran => {},
+                other => return other,
+            }
+        }
+    }
+
+    // Finishes the rcv the machine is stopped at by storing value in its register.
+    pub fn receive(&mut self, value : i64) {
+        if let Op::Rcv(r) = self.code.ops[self.ip] {
+            self.slots[r] = value;
+            self.ip += 1;
+            self.steps += 1;
+        } else {
+            panic!("not stopped at a rcv: ip {}", self.ip);
+        }
+    }
+
+    // Moves past the rcv the machine is stopped at without changing anything.
+    pub fn skip_receive(&mut self) {
+        if let Op::Rcv(_) = self.code.ops[self.ip] {
+            self.ip += 1;
+            self.steps += 1;
+        } else {
+            panic!("not stopped at a rcv: ip {}", self.ip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aocisa::RegisterHolder;
+    use aocisa::dialect::OPCODES;
+    use aocisa::testing::{Random, random_program};
+
+    const REGISTERS : &str = "abcde";
+
+    // Runs the program with both the interpreter and the bytecode, checking after every step that
+    // they agree on the instruction pointer and every register. Returns the registers and whether
+    // the program finished.
+    fn run_both(input : &str, max_steps : u64) -> (RegisterHolder, bool) {
+        let program = Program::load(input).unwrap();
+        let code = Bytecode::compile(&program);
+        let mut machine = Machine::new(&code);
+        let mut registers = RegisterHolder::new();
+        let mut ip = 0;
+
+        while ip < program.instructions.len() && machine.steps() < max_steps {
+            let inst = &program.instructions[ip];
+            registers.apply_instruction(inst);
+            ip = registers.get_next_ip(inst, ip);
+
+            match machine.step() {
+                Step::Ran | Step::Sent(_) => {},
+                Step::Receiving(_) => machine.skip_receive(),
+                Step::Halted => panic!("bytecode halted early at step {}", machine.steps()),
+            }
+
+            assert_eq!(machine.ip(), ip, "{}", inst);
+            for r in REGISTERS.chars() {
+                assert_eq!(machine.get(r), *registers.get_reg(r), "register {} after {}", r, inst);
+            }
+        }
+
+        assert_eq!(machine.is_halted(), ip >= program.instructions.len());
+        (registers, machine.is_halted())
+    }
+
+    #[test]
+    fn same_as_interpreter() {
+        let mut random = Random(0x2017);
+        for _ in 0 .. 500 {
+            let length = random.next(12) as usize + 1;
+            run_both(&random_program(&mut random, &OPCODES, REGISTERS, length), 40);
+        }
+    }
+
+    #[test]
+    fn loops() {
+        let input =
+r"set b 7
+set c 6
+add a b
+sub c 1
+jnz c -2
+set d 100
+mod d 7
+jgz d -10";
+        let (registers, halted) = run_both(input, 1000);
+        assert!(halted);
+        assert_eq!(*registers.get_reg('a'), 42);
+        assert_eq!(*registers.get_reg('d'), 2);
+    }
+
+    #[test]
+    fn events() {
+        let code = Bytecode::compile(&Program::load("snd p\nrcv a\nadd a p\nsnd a").unwrap());
+        assert_eq!(code.registers(), &['p', 'a']);
+
+        let mut machine = Machine::new(&code);
+        machine.set('p', 5);
+        machine.set('z', 9);
+        assert_eq!(machine.run(), Step::Sent(5));
+        assert_eq!(machine.run(), Step::Receiving('a'));
+        assert_eq!(machine.run(), Step::Receiving('a'));
+        assert_eq!(machine.waiting_on(), Some('a'));
+        machine.receive(10);
+        assert_eq!(machine.run(), Step::Sent(15));
+        assert_eq!(machine.run(), Step::Halted);
+        assert_eq!(machine.steps(), 4);
+    }
+}
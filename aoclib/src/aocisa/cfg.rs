@@ -0,0 +1,442 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use aocisa::{Instruction, Program, RegisterOrValue};
+
+// Where control can go after a block.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Edge {
+    Block(usize),
+    // Off either end of the program.
+    Exit,
+    // A jump whose offset is in a register, so it could go anywhere.
+    Unknown,
+}
+
+// A run of instructions that's only entered at the top and only left at the bottom.
+#[derive(PartialEq, Debug)]
+pub struct Block {
+    pub start : usize,
+    pub end : usize,
+    pub successors : Vec<Edge>,
+}
+
+// A natural loop: the header is the only way in, and each latch jumps back to it.
+#[derive(PartialEq, Debug)]
+pub struct Loop {
+    pub header : usize,
+    pub latches : BTreeSet<usize>,
+    pub body : BTreeSet<usize>,
+}
+
+// The control-flow graph of a program, worked out from its jumps without running it. Offsets and
+// conditions that are constants are resolved, so "jnz 1 3" is an unconditional jump and
+// "jgz 0 3" never jumps at all.
+pub struct Cfg<'p> {
+    program : &'p Program,
+    pub blocks : Vec<Block>,
+    pub loops : Vec<Loop>,
+    reachable : BTreeSet<usize>,
+}
+
+// Whether a jump is taken: Some(true) always, Some(false) never, or None if it depends on a
+// register. A dialect's own opcodes might jump anywhere, so they're treated as jumps too.
+fn jump_taken(inst : &Instruction) -> Option<Option<bool>> {
+    match *inst {
+        Instruction::Jgz(RegisterOrValue::Val(v), _) => Some(Some(v > 0)),
+        Instruction::Jnz(RegisterOrValue::Val(v), _) => Some(Some(v != 0)),
+        Instruction::Jgz(..) |
+        Instruction::Jnz(..) |
+        Instruction::Custom(..) => Some(None),
+        _ => None,
+    }
+}
+
+// The instruction a jump goes to when taken, if the offset is a constant. Jumping off the front
+// of the program is the same as jumping off the end.
+fn jump_target(inst : &Instruction, ip : usize) -> Option<Option<usize>> {
+    match *inst {
+        Instruction::Jgz(_, RegisterOrValue::Val(offset)) |
+        Instruction::Jnz(_, RegisterOrValue::Val(offset)) => {
+            let target = (ip as i64) + offset;
+            Some(if target >= 0 { Some(target as usize) } else { None })
+        },
+        _ => None,
+    }
+}
+
+// Every instruction that can come after the one at ip, as instruction pointers. None stands for
+// anywhere at all.
+fn next_ips(inst : &Instruction, ip : usize) -> Vec<Option<usize>> {
+    match jump_taken(inst) {
+        None | Some(Some(false)) => vec![Some(ip + 1)],
+        Some(taken) => {
+            let target = match jump_target(inst, ip) {
+                Some(Some(target)) => Some(target),
+                // Off the front of the program, which exits just like going off the end.
+                Some(None) => Some(usize::MAX),
+                None => None,
+            };
+
+            if taken == Some(true) {
+                vec![target]
+            } else {
+                vec![target, Some(ip + 1)]
+            }
+        },
+    }
+}
+
+impl<'p> Cfg<'p> {
+    pub fn build(program : &'p Program) -> Cfg<'p> {
+        let insts = &program.instructions;
+
+        // A block starts at the beginning, at every jump target, and right after every jump.
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for (ip, inst) in insts.iter().enumerate() {
+            if let Some(Some(false)) = jump_taken(inst) {
+                continue;
+            }
+
+            if jump_taken(inst).is_some() {
+                leaders.insert(ip + 1);
+                if let Some(Some(target)) = jump_target(inst, ip) {
+                    leaders.insert(target);
+                }
+            }
+        }
+
+        let starts = leaders.into_iter().filter(|&ip| ip < insts.len()).collect::<Vec<usize>>();
+        let block_of = |ip : usize| starts.iter().position(|&start| start == ip);
+
+        let blocks = starts.iter().enumerate().map(|(i, &start)| {
+            let end = starts.get(i + 1).cloned().unwrap_or(insts.len());
+            let last = end - 1;
+            let mut successors = next_ips(&insts[last], last).into_iter().map(|next| {
+                match next {
+                    Some(ip) if ip < insts.len() => Edge::Block(block_of(ip).unwrap()),
+                    Some(_) => Edge::Exit,
+                    None => Edge::Unknown,
+                }
+            }).collect::<Vec<Edge>>();
+            successors.dedup();
+
+            Block {
+                start,
+                end,
+                successors,
+            }
+        }).collect::<Vec<Block>>();
+
+        let mut cfg = Cfg {
+            program,
+            blocks,
+            loops : vec![],
+            reachable : BTreeSet::new(),
+        };
+
+        cfg.find_loops();
+        cfg
+    }
+
+    // The block containing the instruction at ip.
+    pub fn block_of(&self, ip : usize) -> Option<usize> {
+        self.blocks.iter().position(|block| block.start <= ip && ip < block.end)
+    }
+
+    pub fn is_reachable(&self, block : usize) -> bool {
+        self.reachable.contains(&block)
+    }
+
+    // How many loops the block is inside of.
+    pub fn depth(&self, block : usize) -> usize {
+        self.loops.iter().filter(|l| l.body.contains(&block)).count()
+    }
+
+    fn block_successors(&self, block : usize) -> Vec<usize> {
+        self.blocks[block].successors.iter().filter_map(|edge| {
+            match *edge {
+                Edge::Block(b) => Some(b),
+                _ => None,
+            }
+        }).collect()
+    }
+
+    // A block dominates another if every path from the start to the other goes through it. A
+    // back edge is a jump to a dominator, and the loop's body is everything that can reach the
+    // jump without going through its target. Jumping backwards into the middle of some other
+    // path isn't a loop in that sense, so it's left alone.
+    fn find_loops(&mut self) {
+        if self.blocks.is_empty() {
+            return;
+        }
+
+        let mut to_visit = vec![0];
+        while let Some(block) = to_visit.pop() {
+            if self.reachable.insert(block) {
+                to_visit.extend(self.block_successors(block));
+            }
+        }
+
+        let mut predecessors : Vec<Vec<usize>> = vec![vec![]; self.blocks.len()];
+        for &block in self.reachable.iter() {
+            for successor in self.block_successors(block) {
+                predecessors[successor].push(block);
+            }
+        }
+
+        let mut dominators = vec![self.reachable.clone(); self.blocks.len()];
+        dominators[0] = [0].iter().cloned().collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in self.reachable.iter().skip(1) {
+                let mut new_dominators = predecessors[block].iter().fold(self.reachable.clone(), |common, &pred| {
+                    common.intersection(&dominators[pred]).cloned().collect()
+                });
+                new_dominators.insert(block);
+
+                if new_dominators != dominators[block] {
+                    dominators[block] = new_dominators;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut latches : BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for &block in self.reachable.iter() {
+            for successor in self.block_successors(block) {
+                if dominators[block].contains(&successor) {
+                    latches.entry(successor).or_default().insert(block);
+                }
+            }
+        }
+
+        for (header, latches) in latches.into_iter() {
+            let mut body = BTreeSet::new();
+            body.insert(header);
+            let mut to_visit = latches.iter().cloned().collect::<Vec<usize>>();
+            while let Some(block) = to_visit.pop() {
+                if body.insert(block) {
+                    to_visit.extend(predecessors[block].iter().cloned());
+                }
+            }
+
+            self.loops.push(Loop {
+                header,
+                latches,
+                body,
+            });
+        }
+    }
+
+    fn describe_edge(edge : &Edge) -> String {
+        match *edge {
+            Edge::Block(b) => format!("b{}", b),
+            Edge::Exit => String::from("exit"),
+            Edge::Unknown => String::from("?"),
+        }
+    }
+
+    // The listing with a label on every block, jumps annotated with the block they go to, and
+    // loop bodies indented by how deeply they're nested.
+    pub fn listing(&self) -> String {
+        let mut lines = vec![];
+
+        for (b, block) in self.blocks.iter().enumerate() {
+            let mut notes = vec![];
+            if let Some(l) = self.loops.iter().find(|l| l.header == b) {
+                let latches = l.latches.iter().map(|latch| format!("b{}", latch)).collect::<Vec<String>>();
+                notes.push(format!("loop, repeats from {}", latches.join(", ")));
+            }
+
+            if !self.is_reachable(b) {
+                notes.push(String::from("unreachable"));
+            }
+
+            if notes.is_empty() {
+                lines.push(format!("b{}:", b));
+            } else {
+                lines.push(format!("{:<8}; {}", format!("b{}:", b), notes.join(", ")));
+            }
+
+            let indent = "  ".repeat(self.depth(b) + 1);
+            for ip in block.start .. block.end {
+                let inst = &self.program.instructions[ip];
+                let text = format!("{}{:>3}: {}", indent, ip, inst);
+
+                // Only the taken side of a jump gets annotated, since the other side is always
+                // the next block.
+                let target = match (jump_taken(inst), jump_target(inst, ip)) {
+                    (None, _) | (Some(Some(false)), _) => None,
+                    (Some(_), Some(Some(target))) if target < self.program.instructions.len() => Some(Edge::Block(self.block_of(target).unwrap())),
+                    (Some(_), Some(_)) => Some(Edge::Exit),
+                    (Some(_), None) => Some(Edge::Unknown),
+                };
+
+                match target {
+                    Some(edge) => lines.push(format!("{:<28}; -> {}", text, Cfg::describe_edge(&edge))),
+                    None => lines.push(text),
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    // The graph in Graphviz's DOT language, with back edges drawn in red.
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![
+            String::from("digraph program {"),
+            String::from("    node [shape=box, fontname=\"monospace\"];"),
+        ];
+
+        let mut uses_exit = false;
+        let mut uses_unknown = false;
+
+        for (b, block) in self.blocks.iter().enumerate() {
+            let insts = (block.start .. block.end).map(|ip| {
+                format!("{}: {}\\l", ip, self.program.instructions[ip])
+            }).collect::<String>();
+            lines.push(format!("    b{} [label=\"b{}\\l{}\"];", b, b, insts));
+
+            for edge in block.successors.iter() {
+                uses_exit = uses_exit || *edge == Edge::Exit;
+                uses_unknown = uses_unknown || *edge == Edge::Unknown;
+
+                let is_back_edge = match *edge {
+                    Edge::Block(to) => self.loops.iter().any(|l| l.header == to && l.latches.contains(&b)),
+                    _ => false,
+                };
+
+                lines.push(format!("    b{} -> {}{};", b, Cfg::describe_edge(edge).replace("?", "unknown"), if is_back_edge { " [color=red]" } else { "" }));
+            }
+        }
+
+        if uses_exit {
+            lines.push(String::from("    exit [shape=oval];"));
+        }
+
+        if uses_unknown {
+            lines.push(String::from("    unknown [shape=diamond, label=\"?\"];"));
+        }
+
+        lines.push(String::from("}"));
+        lines.join("\n")
+    }
+}
+
+impl<'p> fmt::Display for Cfg<'p> {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.listing())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Day 23's program, counting composite numbers from 81 to 98 in steps of 17.
+    const COMPOSITES : &str =
+r"set b 81
+set c b
+jnz a 2
+jnz 1 5
+mul b 100
+sub b -100000
+set c b
+sub c -17000
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -17
+jnz 1 -23";
+
+    #[test]
+    fn blocks() {
+        let program = Program::load("set a 1\njgz a 2\nadd a 1\nmul a 2").unwrap();
+        let cfg = Cfg::build(&program);
+        assert_eq!(cfg.blocks, vec![
+            Block { start : 0, end : 2, successors : vec![Edge::Block(2), Edge::Block(1)] },
+            Block { start : 2, end : 3, successors : vec![Edge::Block(2)] },
+            Block { start : 3, end : 4, successors : vec![Edge::Exit] },
+        ]);
+        assert!(cfg.loops.is_empty());
+
+        let program = Program::load("jnz 1 2\nadd a 1\njgz b c\njnz 0 -10").unwrap();
+        let cfg = Cfg::build(&program);
+        assert_eq!(cfg.blocks[0].successors, vec![Edge::Block(2)]);
+        assert_eq!(cfg.blocks[2].successors, vec![Edge::Unknown, Edge::Block(3)]);
+        assert_eq!(cfg.blocks[3].successors, vec![Edge::Exit]);
+        assert!(!cfg.is_reachable(1));
+    }
+
+    #[test]
+    fn loops() {
+        let program = Program::load(COMPOSITES).unwrap();
+        let cfg = Cfg::build(&program);
+        let headers = cfg.loops.iter().map(|l| cfg.blocks[l.header].start).collect::<Vec<usize>>();
+        assert_eq!(headers, vec![8, 10, 11]);
+        assert_eq!(cfg.depth(cfg.block_of(12).unwrap()), 3);
+        assert_eq!(cfg.depth(cfg.block_of(20).unwrap()), 2);
+        assert_eq!(cfg.depth(cfg.block_of(25).unwrap()), 1);
+        assert_eq!(cfg.depth(cfg.block_of(5).unwrap()), 0);
+
+        // The jump back to 1 can be reached without going through 1, so it isn't a loop.
+        let program = Program::load("jgz a 2\nadd b 1\nadd c 1\njgz d -2").unwrap();
+        let cfg = Cfg::build(&program);
+        assert_eq!(cfg.blocks[2].successors, vec![Edge::Block(1), Edge::Exit]);
+        assert!(cfg.loops.is_empty());
+    }
+
+    #[test]
+    fn listing() {
+        let program = Program::load("set a 3\nsub a 1\njgz a -1\njnz 1 2\nadd b 1").unwrap();
+        let cfg = Cfg::build(&program);
+        assert_eq!(cfg.to_string(),
+r"b0:
+    0: set a 3
+b1:     ; loop, repeats from b1
+      1: sub a 1
+      2: jgz a -1           ; -> b1
+b2:
+    3: jnz 1 2              ; -> exit
+b3:     ; unreachable
+    4: add b 1");
+
+        assert_eq!(cfg.to_dot(),
+r#"digraph program {
+    node [shape=box, fontname="monospace"];
+    b0 [label="b0\l0: set a 3\l"];
+    b0 -> b1;
+    b1 [label="b1\l1: sub a 1\l2: jgz a -1\l"];
+    b1 -> b1 [color=red];
+    b1 -> b2;
+    b2 [label="b2\l3: jnz 1 2\l"];
+    b2 -> exit;
+    b3 [label="b3\l4: add b 1\l"];
+    b3 -> exit;
+    exit [shape=oval];
+}"#);
+    }
+}
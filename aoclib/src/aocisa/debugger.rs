@@ -0,0 +1,388 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::io::{BufRead, Write};
+
+use aocisa::{Program, RegisterHolder, RegisterOrValue};
+use aocisa::dialect::{Interpreter, Plain};
+use aocisa::source::Source;
+use simulation::Simulation;
+
+// Why the debugger stopped running the program.
+#[derive(PartialEq, Debug)]
+pub enum Stop {
+    // Took every step that was asked for.
+    Stepped,
+    // About to execute the instruction at a breakpoint.
+    Breakpoint(usize),
+    // A watched register changed, from the first value to the second.
+    Watchpoint(char, i64, i64),
+    // The instruction pointer left the program.
+    Halted,
+}
+
+// Runs an aocisa program one instruction at a time. snd and rcv only mean something to the day
+// that uses them, so here they do nothing but move on to the next instruction.
+pub struct Debugger<'p> {
+    interpreter : Interpreter<'p, Plain>,
+    breakpoints : BTreeSet<usize>,
+    watchpoints : BTreeSet<char>,
+    source : Option<&'p Source>,
+}
+
+const HELP : &str =
+r"s, step [N]       execute N instructions (1 by default)
+c, continue       run until a breakpoint, a watchpoint, or the end of the program
+b, break IP       stop before executing the instruction at IP
+d, delete IP      remove the breakpoint at IP
+w, watch REG      stop whenever REG changes
+u, unwatch REG    stop watching REG
+r, regs           show every register the program uses
+l, list [N]       show N instructions around the current one (5 by default)
+q, quit           stop debugging";
+
+impl<'p> Debugger<'p> {
+    pub fn new(program : &'p Program) -> Debugger<'p> {
+        Debugger {
+            interpreter : Interpreter::plain(program),
+            breakpoints : BTreeSet::new(),
+            watchpoints : BTreeSet::new(),
+            source : None,
+        }
+    }
+
+    // Debugs the program parsed from source, showing each instruction's line in the text.
+    pub fn for_source(source : &'p Source) -> Debugger<'p> {
+        let mut debugger = Debugger::new(source.program());
+        debugger.source = Some(source);
+        debugger
+    }
+
+    pub fn ip(&self) -> usize {
+        self.interpreter.ip()
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.interpreter.steps()
+    }
+
+    pub fn registers(&self) -> &RegisterHolder {
+        self.interpreter.registers()
+    }
+
+    // For setting up registers before the program starts, like day 23 part b's a = 1.
+    pub fn registers_mut(&mut self) -> &mut RegisterHolder {
+        self.interpreter.registers_mut()
+    }
+
+    pub fn add_breakpoint(&mut self, ip : usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip : usize) -> bool {
+        self.breakpoints.remove(&ip)
+    }
+
+    pub fn add_watchpoint(&mut self, register : char) {
+        self.watchpoints.insert(register);
+    }
+
+    pub fn remove_watchpoint(&mut self, register : char) -> bool {
+        self.watchpoints.remove(&register)
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.interpreter.is_halted()
+    }
+
+    fn program(&self) -> &'p Program {
+        self.interpreter.program()
+    }
+
+    // Every register the program mentions, in order, since those are the only interesting ones.
+    pub fn used_registers(&self) -> Vec<char> {
+        let registers = self.program().instructions.iter().flat_map(|inst| inst.operands()).filter_map(|operand| {
+            match operand {
+                RegisterOrValue::Reg(r) => Some(r),
+                RegisterOrValue::Val(_) => None,
+            }
+        }).collect::<BTreeSet<char>>();
+
+        registers.into_iter().collect()
+    }
+
+    // Executes the current instruction. Returns false if the program has already halted.
+    fn execute_one(&mut self) -> bool {
+        self.interpreter.step()
+    }
+
+    // Executes up to the given number of instructions, stopping early at a breakpoint, a change
+    // to a watched register, or the end of the program. At least one instruction is executed, so
+    // running from a breakpoint doesn't stop right away.
+    pub fn run(&mut self, max_steps : u64) -> Stop {
+        for taken in 0 .. max_steps {
+            if taken > 0 && self.breakpoints.contains(&self.ip()) {
+                return Stop::Breakpoint(self.ip());
+            }
+
+            let watched = self.watchpoints.iter().map(|&r| (r, *self.registers().get_reg(r))).collect::<Vec<(char, i64)>>();
+
+            if !self.execute_one() {
+                return Stop::Halted;
+            }
+
+            for (r, old_value) in watched {
+                let new_value = *self.registers().get_reg(r);
+                if new_value != old_value {
+                    return Stop::Watchpoint(r, old_value, new_value);
+                }
+            }
+        }
+
+        if self.is_halted() {
+            Stop::Halted
+        } else {
+            Stop::Stepped
+        }
+    }
+
+    pub fn step(&mut self) -> Stop {
+        self.run(1)
+    }
+
+    pub fn continue_running(&mut self) -> Stop {
+        self.run(u64::MAX)
+    }
+
+    pub fn describe_position(&self) -> String {
+        if self.is_halted() {
+            format!("[step {}] halted at {}", self.steps(), self.ip())
+        } else {
+            format!("[step {}] {}: {}{}", self.steps(), self.ip(), self.program().instructions[self.ip()], self.line_note(self.ip()))
+        }
+    }
+
+    pub fn describe_registers(&self) -> String {
+        self.used_registers().iter().map(|&r| format!("{} = {}", r, self.registers().get_reg(r))).collect::<Vec<String>>().join("\n")
+    }
+
+    fn describe_stop(&self, stop : &Stop) -> String {
+        let reason = match *stop {
+            Stop::Stepped => String::new(),
+            Stop::Breakpoint(ip) => format!("breakpoint at {}\n", ip),
+            Stop::Watchpoint(r, old_value, new_value) => format!("{} changed from {} to {}\n", r, old_value, new_value),
+            Stop::Halted => String::from("program halted\n"),
+        };
+
+        format!("{}{}", reason, self.describe_position())
+    }
+
+    // Where an instruction came from, written like a comment so listings still read as source.
+    fn line_note(&self, ip : usize) -> String {
+        match self.source.and_then(|source| source.line_of(ip)) {
+            Some(line) => format!("  # line {}", line),
+            None => String::new(),
+        }
+    }
+
+    fn list(&self, count : usize) -> String {
+        let first = self.ip().saturating_sub(count / 2);
+        self.program().instructions.iter().enumerate().skip(first).take(count).map(|(i, inst)| {
+            let marker = if i == self.ip() { ">" } else if self.breakpoints.contains(&i) { "*" } else { " " };
+            format!("{} {:>3}: {}{}", marker, i, inst, self.line_note(i))
+        }).collect::<Vec<String>>().join("\n")
+    }
+
+    // Runs one debugger command and returns what to show for it, or None to quit.
+    pub fn command(&mut self, line : &str) -> Option<String> {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Some(String::new()),
+        };
+        let arg = words.next();
+
+        let parse_ip = |arg : Option<&str>| arg.and_then(|a| a.parse::<usize>().ok());
+        let parse_register = |arg : Option<&str>| {
+            arg.and_then(|a| {
+                let mut chars = a.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(r), None) => Some(r),
+                    _ => None,
+                }
+            })
+        };
+
+        Some(match name {
+            "s" | "step" => {
+                match arg.map_or(Some(1), |a| a.parse::<u64>().ok()) {
+                    Some(count) => {
+                        let stop = self.run(count);
+                        self.describe_stop(&stop)
+                    },
+                    None => String::from("step takes a number of instructions"),
+                }
+            },
+            "c" | "continue" => {
+                let stop = self.continue_running();
+                self.describe_stop(&stop)
+            },
+            "b" | "break" => {
+                match parse_ip(arg) {
+                    Some(ip) => {
+                        self.add_breakpoint(ip);
+                        format!("breakpoint at {}", ip)
+                    },
+                    None => String::from("break takes an instruction index"),
+                }
+            },
+            "d" | "delete" => {
+                match parse_ip(arg) {
+                    Some(ip) if self.remove_breakpoint(ip) => format!("removed breakpoint at {}", ip),
+                    Some(ip) => format!("no breakpoint at {}", ip),
+                    None => String::from("delete takes an instruction index"),
+                }
+            },
+            "w" | "watch" => {
+                match parse_register(arg) {
+                    Some(r) => {
+                        self.add_watchpoint(r);
+                        format!("watching {}", r)
+                    },
+                    None => String::from("watch takes a register name"),
+                }
+            },
+            "u" | "unwatch" => {
+                match parse_register(arg) {
+                    Some(r) if self.remove_watchpoint(r) => format!("stopped watching {}", r),
+                    Some(r) => format!("{} is not watched", r),
+                    None => String::from("unwatch takes a register name"),
+                }
+            },
+            "r" | "regs" => self.describe_registers(),
+            "l" | "list" => {
+                match arg.map_or(Some(5), |a| a.parse::<usize>().ok()) {
+                    Some(count) => self.list(count),
+                    None => String::from("list takes a number of instructions"),
+                }
+            },
+            "h" | "help" => String::from(HELP),
+            "q" | "quit" => return None,
+            _ => format!("unknown command {}. Type help for the list of commands.", name),
+        })
+    }
+
+    // Reads commands from input until it runs out or the user quits.
+    pub fn interact<R, W>(&mut self, input : R, mut output : W) -> io::Result<()>
+        where R : BufRead, W : Write {
+        writeln!(output, "{}", self.describe_position())?;
+        write!(output, "(debug) ")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            match self.command(&line?) {
+                Some(response) => {
+                    if !response.is_empty() {
+                        writeln!(output, "{}", response)?;
+                    }
+                },
+                None => break,
+            }
+
+            write!(output, "(debug) ")?;
+            output.flush()?;
+        }
+
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Counts a down from 3, adding to b each time.
+    const COUNTDOWN : &str =
+r"set a 3
+add b 10
+sub a 1
+jnz a -2
+set c 1";
+
+    #[test]
+    fn single_step() {
+        let program = Program::load(COUNTDOWN).unwrap();
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(debugger.step(), Stop::Stepped);
+        assert_eq!(debugger.ip(), 1);
+        assert_eq!(*debugger.registers().get_reg('a'), 3);
+        assert_eq!(debugger.run(3), Stop::Stepped);
+        assert_eq!(debugger.ip(), 1);
+        assert_eq!(debugger.steps(), 4);
+        assert_eq!(debugger.continue_running(), Stop::Halted);
+        assert_eq!(debugger.steps(), 11);
+        assert_eq!(*debugger.registers().get_reg('b'), 30);
+        assert_eq!(debugger.step(), Stop::Halted);
+        assert_eq!(debugger.steps(), 11);
+    }
+
+    #[test]
+    fn breakpoints() {
+        let program = Program::load(COUNTDOWN).unwrap();
+        let mut debugger = Debugger::new(&program);
+        debugger.add_breakpoint(2);
+        assert_eq!(debugger.continue_running(), Stop::Breakpoint(2));
+        assert_eq!(*debugger.registers().get_reg('b'), 10);
+        assert_eq!(debugger.continue_running(), Stop::Breakpoint(2));
+        assert_eq!(*debugger.registers().get_reg('b'), 20);
+        assert!(debugger.remove_breakpoint(2));
+        assert_eq!(debugger.continue_running(), Stop::Halted);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let program = Program::load(COUNTDOWN).unwrap();
+        let mut debugger = Debugger::new(&program);
+        debugger.add_watchpoint('a');
+        assert_eq!(debugger.continue_running(), Stop::Watchpoint('a', 0, 3));
+        assert_eq!(debugger.continue_running(), Stop::Watchpoint('a', 3, 2));
+        assert_eq!(debugger.ip(), 3);
+        debugger.remove_watchpoint('a');
+        debugger.add_watchpoint('c');
+        assert_eq!(debugger.continue_running(), Stop::Watchpoint('c', 0, 1));
+        assert!(debugger.is_halted());
+    }
+
+    #[test]
+    fn commands() {
+        let program = Program::load(COUNTDOWN).unwrap();
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(debugger.command("b 3"), Some(String::from("breakpoint at 3")));
+        assert_eq!(debugger.command("c"), Some(String::from("breakpoint at 3\n[step 3] 3: jnz a -2")));
+        assert_eq!(debugger.command("regs"), Some(String::from("a = 2\nb = 10\nc = 0")));
+        assert_eq!(debugger.command("step 2"), Some(String::from("[step 5] 2: sub a 1")));
+        assert_eq!(debugger.command("list 3"), Some(String::from("    1: add b 10\n>   2: sub a 1\n*   3: jnz a -2")));
+        assert_eq!(debugger.command("delete 3"), Some(String::from("removed breakpoint at 3")));
+        assert_eq!(debugger.command("c"), Some(String::from("program halted\n[step 11] halted at 5")));
+        assert_eq!(debugger.command("watch"), Some(String::from("watch takes a register name")));
+        assert_eq!(debugger.command("q"), None);
+    }
+
+    #[test]
+    fn interactive() {
+        let program = Program::load(COUNTDOWN).unwrap();
+        let mut debugger = Debugger::new(&program);
+        let mut output = vec![];
+        debugger.interact("s\nq\nc\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "[step 0] 0: set a 3\n(debug) [step 1] 1: add b 10\n(debug) \n");
+        assert_eq!(debugger.steps(), 1);
+    }
+
+    #[test]
+    fn source_lines() {
+        let source = Source::parse(&format!("# countdown\n\n{}", COUNTDOWN)).unwrap();
+        let mut debugger = Debugger::for_source(&source);
+        assert_eq!(debugger.command("s"), Some(String::from("[step 1] 1: add b 10  # line 4")));
+        assert_eq!(debugger.command("list 2"), Some(String::from("    0: set a 3  # line 3\n>   1: add b 10  # line 4")));
+    }
+}
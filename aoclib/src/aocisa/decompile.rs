@@ -0,0 +1,700 @@
+use std::fmt;
+
+use aocisa::{Instruction, Program, RegisterOrValue};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Expr {
+    Reg(char),
+    Val(i64),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Test {
+    NonZero,
+    Zero,
+    Positive,
+    NotPositive,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Cond {
+    pub value : Expr,
+    pub test : Test,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Assign {
+    Set,
+    Add,
+    Sub,
+    Mul,
+    Mod,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Stmt {
+    Assign(char, Assign, Expr),
+    Send(Expr),
+    Receive(char),
+    If(Cond, Vec<Stmt>, Vec<Stmt>),
+    While(Cond, Vec<Stmt>),
+    DoWhile(Vec<Stmt>, Cond),
+    Loop(Vec<Stmt>),
+    // Leave the innermost loop.
+    Break,
+    // Go back to the top of the innermost loop, which is always a plain loop.
+    Continue,
+    // Stop the whole program.
+    Halt,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct DecompileError {
+    pub ip : usize,
+    pub reason : &'static str,
+}
+
+// A program lifted into nested ifs and loops. Only jumps that fit that shape can be lifted: ones
+// that skip forward within the same block, loop back to the top of one, or leave it with a break
+// or a halt.
+pub struct Pseudocode {
+    pub body : Vec<Stmt>,
+}
+
+// A jump that might be taken. cond is None if it always is.
+struct Jump {
+    cond : Option<Cond>,
+    target : i64,
+}
+
+// The loop that the code being structured is inside of.
+struct LoopContext {
+    header : usize,
+    // Where a jump means continue, for the loops that have one.
+    continue_at : Option<usize>,
+    // Where a jump means break.
+    exit : usize,
+}
+
+impl Expr {
+    fn from(rv : &RegisterOrValue) -> Expr {
+        match *rv {
+            RegisterOrValue::Reg(r) => Expr::Reg(r),
+            RegisterOrValue::Val(v) => Expr::Val(v),
+        }
+    }
+}
+
+impl Cond {
+    pub fn negate(&self) -> Cond {
+        Cond {
+            value : self.value,
+            test : match self.test {
+                Test::NonZero => Test::Zero,
+                Test::Zero => Test::NonZero,
+                Test::Positive => Test::NotPositive,
+                Test::NotPositive => Test::Positive,
+            },
+        }
+    }
+}
+
+// The jump at ip, or None if it isn't one or can never be taken.
+fn decode_jump(inst : &Instruction, ip : usize) -> Result<Option<Jump>, DecompileError> {
+    let (cond, offset) = match *inst {
+        Instruction::Jgz(ref x, ref y) => (Cond { value : Expr::from(x), test : Test::Positive }, y),
+        Instruction::Jnz(ref x, ref y) => (Cond { value : Expr::from(x), test : Test::NonZero }, y),
+        Instruction::Custom(..) => {
+            return Err(DecompileError {
+                ip,
+                reason : "only its dialect knows where it goes",
+            });
+        },
+        _ => return Ok(None),
+    };
+
+    let cond = match (cond.value, cond.test) {
+        (Expr::Val(v), Test::Positive) => if v > 0 { None } else { return Ok(None) },
+        (Expr::Val(v), _) => if v != 0 { None } else { return Ok(None) },
+        _ => Some(cond),
+    };
+
+    match *offset {
+        RegisterOrValue::Val(offset) => Ok(Some(Jump {
+            cond,
+            target : (ip as i64) + offset,
+        })),
+        RegisterOrValue::Reg(_) => Err(DecompileError {
+            ip,
+            reason : "its offset is in a register",
+        }),
+    }
+}
+
+// Turns a jump that leaves the code being structured into a statement.
+fn escape(target : i64, ip : usize, len : usize, context : &[LoopContext]) -> Result<Stmt, DecompileError> {
+    let innermost = context.last();
+    if target >= 0 && innermost.and_then(|l| l.continue_at) == Some(target as usize) {
+        Ok(Stmt::Continue)
+    } else if target >= 0 && innermost.map(|l| l.exit) == Some(target as usize) {
+        Ok(Stmt::Break)
+    } else if target < 0 || target as usize >= len {
+        Ok(Stmt::Halt)
+    } else {
+        Err(DecompileError {
+            ip,
+            reason : "it goes somewhere that isn't a loop's top or exit",
+        })
+    }
+}
+
+impl Pseudocode {
+    pub fn decompile(program : &Program) -> Result<Pseudocode, DecompileError> {
+        let insts = &program.instructions;
+        let mut context = vec![];
+        Ok(Pseudocode {
+            body : Pseudocode::structure(insts, 0, insts.len(), &mut context)?,
+        })
+    }
+
+    // Structures the instructions from start up to end, where control is known to leave only by
+    // falling off the end or by a jump that escape understands.
+    fn structure(insts : &[Instruction], start : usize, end : usize, context : &mut Vec<LoopContext>) -> Result<Vec<Stmt>, DecompileError> {
+        let mut stmts = vec![];
+        let mut ip = start;
+
+        while ip < end {
+            // A loop starts here if something later in the block jumps back to here. The
+            // furthest such jump is the bottom of the loop.
+            let is_own_header = ip == start && context.last().map(|l| l.header) == Some(start);
+            if !is_own_header {
+                let mut latch = None;
+                for (j, inst) in insts.iter().enumerate().take(end).skip(ip) {
+                    if let Some(jump) = decode_jump(inst, j)? {
+                        if jump.target == ip as i64 {
+                            latch = Some((j, jump));
+                        }
+                    }
+                }
+
+                if let Some((j, jump)) = latch {
+                    context.push(LoopContext {
+                        header : ip,
+                        continue_at : if jump.cond.is_none() { Some(ip) } else { None },
+                        exit : j + 1,
+                    });
+                    let body = Pseudocode::structure(insts, ip, j, context)?;
+                    context.pop();
+
+                    stmts.push(match jump.cond {
+                        Some(cond) => Stmt::DoWhile(body, cond),
+                        None => Stmt::Loop(body),
+                    });
+
+                    ip = j + 1;
+                    continue;
+                }
+            }
+
+            let jump = match decode_jump(&insts[ip], ip)? {
+                Some(jump) => jump,
+                None => {
+                    if let Some(stmt) = Pseudocode::simple_statement(&insts[ip]) {
+                        stmts.push(stmt);
+                    }
+
+                    ip += 1;
+                    continue;
+                },
+            };
+
+            let target = jump.target;
+            let is_forward = target > ip as i64 && target <= end as i64;
+
+            match jump.cond {
+                None if target == (ip + 1) as i64 => {
+                    ip += 1;
+                },
+                None if is_forward => {
+                    // Jumping forward to a test that jumps back is a while loop.
+                    let test = target as usize;
+                    let test_jump = if test < end { decode_jump(&insts[test], test)? } else { None };
+                    match test_jump {
+                        Some(Jump { cond : Some(cond), target : back }) if back == (ip + 1) as i64 => {
+                            context.push(LoopContext {
+                                header : ip + 1,
+                                continue_at : None,
+                                exit : test + 1,
+                            });
+                            let body = Pseudocode::structure(insts, ip + 1, test, context)?;
+                            context.pop();
+
+                            stmts.push(Stmt::While(cond, body));
+                            ip = test + 1;
+                        },
+                        _ => {
+                            return Err(DecompileError {
+                                ip,
+                                reason : "it skips over code that nothing else reaches",
+                            });
+                        },
+                    }
+                },
+                None => {
+                    stmts.push(escape(target, ip, insts.len(), context)?);
+                    ip += 1;
+                },
+                Some(cond) if is_forward => {
+                    // The instructions up to the target only run if the jump isn't taken. If they
+                    // end by jumping further forward, the ones in between are the else.
+                    let target = target as usize;
+                    let else_jump = if target - 1 > ip { decode_jump(&insts[target - 1], target - 1)? } else { None };
+                    let (then_end, else_end) = match else_jump {
+                        Some(Jump { cond : None, target : after }) if after > target as i64 && after <= end as i64 => (target - 1, after as usize),
+                        _ => (target, target),
+                    };
+
+                    let then_stmts = Pseudocode::structure(insts, ip + 1, then_end, context)?;
+                    let else_stmts = Pseudocode::structure(insts, target, else_end, context)?;
+                    if then_stmts.is_empty() {
+                        if !else_stmts.is_empty() {
+                            stmts.push(Stmt::If(cond, else_stmts, vec![]));
+                        }
+                    } else {
+                        stmts.push(Stmt::If(cond.negate(), then_stmts, else_stmts));
+                    }
+
+                    ip = else_end;
+                },
+                Some(cond) => {
+                    stmts.push(Stmt::If(cond, vec![escape(target, ip, insts.len(), context)?], vec![]));
+                    ip += 1;
+                },
+            }
+        }
+
+        Ok(stmts)
+    }
+
+    fn simple_statement(inst : &Instruction) -> Option<Stmt> {
+        Some(match *inst {
+            Instruction::Snd(ref x) => Stmt::Send(Expr::from(x)),
+            Instruction::Set(r, ref x) => Stmt::Assign(r, Assign::Set, Expr::from(x)),
+            Instruction::Add(r, ref x) => Stmt::Assign(r, Assign::Add, Expr::from(x)),
+            Instruction::Sub(r, ref x) => Stmt::Assign(r, Assign::Sub, Expr::from(x)),
+            Instruction::Mul(r, ref x) => Stmt::Assign(r, Assign::Mul, Expr::from(x)),
+            Instruction::Mod(r, ref x) => Stmt::Assign(r, Assign::Mod, Expr::from(x)),
+            Instruction::Rcv(r) => Stmt::Receive(r),
+            Instruction::Jgz(..) |
+            Instruction::Jnz(..) |
+            Instruction::Custom(..) => return None,
+        })
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expr::Reg(r) => write!(f, "{}", r),
+            Expr::Val(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let test = match self.test {
+            Test::NonZero => "!= 0",
+            Test::Zero => "== 0",
+            Test::Positive => "> 0",
+            Test::NotPositive => "<= 0",
+        };
+
+        write!(f, "{} {}", self.value, test)
+    }
+}
+
+impl fmt::Display for DecompileError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can't decompile the jump at {}: {}", self.ip, self.reason)
+    }
+}
+
+fn write_block(f : &mut fmt::Formatter, stmts : &[Stmt], depth : usize) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+
+    for stmt in stmts.iter() {
+        match *stmt {
+            Stmt::Assign(r, op, value) => {
+                // Adding a negative constant reads better as subtracting, and the other way
+                // around, which is how day 23 spells its increments.
+                let (op, value) = match (op, value) {
+                    (Assign::Add, Expr::Val(v)) if v < 0 && v != i64::MIN => ("-=", Expr::Val(-v)),
+                    (Assign::Sub, Expr::Val(v)) if v < 0 && v != i64::MIN => ("+=", Expr::Val(-v)),
+                    (Assign::Set, _) => ("=", value),
+                    (Assign::Add, _) => ("+=", value),
+                    (Assign::Sub, _) => ("-=", value),
+                    (Assign::Mul, _) => ("*=", value),
+                    (Assign::Mod, _) => ("%=", value),
+                };
+
+                writeln!(f, "{}{} {} {}", indent, r, op, value)?;
+            },
+            Stmt::Send(value) => writeln!(f, "{}send({})", indent, value)?,
+            Stmt::Receive(r) => writeln!(f, "{}{} = receive()", indent, r)?,
+            Stmt::If(cond, ref then_stmts, ref else_stmts) => {
+                writeln!(f, "{}if {} {{", indent, cond)?;
+                write_block(f, then_stmts, depth + 1)?;
+                if !else_stmts.is_empty() {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    write_block(f, else_stmts, depth + 1)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            },
+            Stmt::While(cond, ref body) => {
+                writeln!(f, "{}while {} {{", indent, cond)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+            },
+            Stmt::DoWhile(ref body, cond) => {
+                writeln!(f, "{}do {{", indent)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}} while {}", indent, cond)?;
+            },
+            Stmt::Loop(ref body) => {
+                writeln!(f, "{}loop {{", indent)?;
+                write_block(f, body, depth + 1)?;
+                writeln!(f, "{}}}", indent)?;
+            },
+            Stmt::Break => writeln!(f, "{}break", indent)?,
+            Stmt::Continue => writeln!(f, "{}continue", indent)?,
+            Stmt::Halt => writeln!(f, "{}halt", indent)?,
+        }
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Pseudocode {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write_block(f, &self.body, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aocisa::RegisterHolder;
+    use aocisa::testing::{Random, random_program};
+    use std::collections::HashMap;
+
+    enum Flow {
+        Normal,
+        Break,
+        Continue,
+        Halt,
+    }
+
+    // Runs pseudocode directly, as a check that it means the same thing as the program it came
+    // from. Panics if it takes more than budget statements.
+    struct Evaluator {
+        registers : HashMap<char, i64>,
+        budget : u32,
+    }
+
+    impl Evaluator {
+        fn value(&self, expr : Expr) -> i64 {
+            match expr {
+                Expr::Reg(r) => *self.registers.get(&r).unwrap_or(&0),
+                Expr::Val(v) => v,
+            }
+        }
+
+        fn holds(&self, cond : Cond) -> bool {
+            let value = self.value(cond.value);
+            match cond.test {
+                Test::NonZero => value != 0,
+                Test::Zero => value == 0,
+                Test::Positive => value > 0,
+                Test::NotPositive => value <= 0,
+            }
+        }
+
+        // Runs one pass through a loop, returning how the loop ends if this pass ends it.
+        fn run_loop_body(&mut self, body : &[Stmt]) -> Option<Flow> {
+            assert!(self.budget > 0, "pseudocode ran too long");
+            self.budget -= 1;
+
+            match self.run(body) {
+                Flow::Break => Some(Flow::Normal),
+                Flow::Halt => Some(Flow::Halt),
+                Flow::Normal | Flow::Continue => None,
+            }
+        }
+
+        fn run(&mut self, stmts : &[Stmt]) -> Flow {
+            for stmt in stmts.iter() {
+                assert!(self.budget > 0, "pseudocode ran too long");
+                self.budget -= 1;
+
+                let flow = match *stmt {
+                    Stmt::Assign(r, op, value) => {
+                        let value = self.value(value);
+                        let reg = self.registers.entry(r).or_insert(0);
+                        match op {
+                            Assign::Set => *reg = value,
+                            Assign::Add => *reg += value,
+                            Assign::Sub => *reg -= value,
+                            Assign::Mul => *reg *= value,
+                            Assign::Mod => *reg %= value,
+                        }
+                        Flow::Normal
+                    },
+                    Stmt::Send(_) |
+                    Stmt::Receive(_) => Flow::Normal,
+                    Stmt::If(cond, ref then_stmts, ref else_stmts) => {
+                        if self.holds(cond) {
+                            self.run(then_stmts)
+                        } else {
+                            self.run(else_stmts)
+                        }
+                    },
+                    Stmt::While(cond, ref body) => {
+                        let mut flow = Flow::Normal;
+                        while self.holds(cond) {
+                            if let Some(end) = self.run_loop_body(body) {
+                                flow = end;
+                                break;
+                            }
+                        }
+                        flow
+                    },
+                    Stmt::DoWhile(ref body, cond) => {
+                        let mut flow = Flow::Normal;
+                        loop {
+                            if let Some(end) = self.run_loop_body(body) {
+                                flow = end;
+                                break;
+                            }
+
+                            if !self.holds(cond) {
+                                break;
+                            }
+                        }
+                        flow
+                    },
+                    Stmt::Loop(ref body) => {
+                        loop {
+                            if let Some(end) = self.run_loop_body(body) {
+                                break end;
+                            }
+                        }
+                    },
+                    Stmt::Break => Flow::Break,
+                    Stmt::Continue => Flow::Continue,
+                    Stmt::Halt => Flow::Halt,
+                };
+
+                match flow {
+                    Flow::Normal => {},
+                    other => return other,
+                }
+            }
+
+            Flow::Normal
+        }
+    }
+
+    const REGISTERS : &str = "abcdefgh";
+
+    // Runs the program both ways from the same starting registers, if the real one halts within
+    // max_steps, and checks that they end up the same.
+    fn check(program : &Program, pseudocode : &Pseudocode, initial : &[(char, i64)], max_steps : u32) -> bool {
+        let mut registers = RegisterHolder::new();
+        for &(r, v) in initial.iter() {
+            *registers.get_reg_mut(r) = v;
+        }
+
+        let mut ip = 0;
+        let mut steps = 0;
+        while ip < program.instructions.len() {
+            if steps == max_steps {
+                return false;
+            }
+
+            let inst = &program.instructions[ip];
+            registers.apply_instruction(inst);
+            ip = registers.get_next_ip(inst, ip);
+            steps += 1;
+        }
+
+        let mut evaluator = Evaluator {
+            registers : initial.iter().cloned().collect(),
+            budget : max_steps * 10,
+        };
+        evaluator.run(&pseudocode.body);
+
+        for r in REGISTERS.chars() {
+            assert_eq!(evaluator.value(Expr::Reg(r)), *registers.get_reg(r), "register {} in\n{}\n{}", r, program, pseudocode);
+        }
+
+        true
+    }
+
+    // Day 23's program with smaller numbers, so it runs quickly in both forms.
+    fn coprocessor(b : i64, scale : i64, offset : i64, span : i64, step : i64) -> String {
+        format!(
+r"set b {}
+set c b
+jnz a 2
+jnz 1 5
+mul b {}
+sub b -{}
+set c b
+sub c -{}
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -{}
+jnz 1 -23", b, scale, offset, span, step)
+    }
+
+    #[test]
+    fn coprocessor_loops() {
+        let program = Program::load(&coprocessor(81, 100, 100000, 17000, 17)).unwrap();
+        let pseudocode = Pseudocode::decompile(&program).unwrap();
+        assert_eq!(pseudocode.to_string(),
+r"b = 81
+c = b
+if a != 0 {
+    b *= 100
+    b += 100000
+    c = b
+    c += 17000
+}
+loop {
+    f = 1
+    d = 2
+    do {
+        e = 2
+        do {
+            g = d
+            g *= e
+            g -= b
+            if g == 0 {
+                f = 0
+            }
+            e += 1
+            g = e
+            g -= b
+        } while g != 0
+        d += 1
+        g = d
+        g -= b
+    } while g != 0
+    if f == 0 {
+        h += 1
+    }
+    g = b
+    g -= c
+    if g == 0 {
+        break
+    }
+    b += 17
+}
+");
+    }
+
+    #[test]
+    fn coprocessor_same_as_interpreter() {
+        let program = Program::load(&coprocessor(5, 2, 3, 21, 3)).unwrap();
+        let pseudocode = Pseudocode::decompile(&program).unwrap();
+        assert!(check(&program, &pseudocode, &[], 1000000));
+        assert!(check(&program, &pseudocode, &[('a', 1)], 1000000));
+    }
+
+    #[test]
+    fn while_and_else() {
+        let input =
+r"jnz 1 3
+add b a
+sub a 1
+jgz a -2
+jgz b 3
+set c 1
+jnz 1 2
+set c 2
+jnz c 100
+set d 1";
+        let program = Program::load(input).unwrap();
+        let pseudocode = Pseudocode::decompile(&program).unwrap();
+        assert_eq!(pseudocode.to_string(),
+r"while a > 0 {
+    b += a
+    a -= 1
+}
+if b <= 0 {
+    c = 1
+} else {
+    c = 2
+}
+if c != 0 {
+    halt
+}
+d = 1
+");
+
+        for a in -1 .. 5 {
+            assert!(check(&program, &pseudocode, &[('a', a)], 1000));
+        }
+    }
+
+    #[test]
+    fn unstructured() {
+        let program = Program::load("jgz a b\nadd a 1").unwrap();
+        assert_eq!(Pseudocode::decompile(&program).err(), Some(DecompileError { ip : 0, reason : "its offset is in a register" }));
+
+        // Jumps into the middle of the loop.
+        let program = Program::load("jnz a 2\nadd b 1\nadd c 1\njgz c -2").unwrap();
+        assert_eq!(Pseudocode::decompile(&program).err().map(|e| e.ip), Some(3));
+    }
+
+    #[test]
+    fn random_programs() {
+        let mut random = Random(0x23);
+        let mut checked = 0;
+
+        for _ in 0 .. 3000 {
+            let length = random.next(10) as usize + 2;
+            let input = random_program(&mut random, &["set", "add", "sub", "mul", "mod", "jgz", "jnz"], &REGISTERS[.. 4], length);
+
+            let program = Program::load(&input).unwrap();
+            if let Ok(pseudocode) = Pseudocode::decompile(&program) {
+                if check(&program, &pseudocode, &[('a', random.value(-3, 3))], 40) {
+                    checked += 1;
+                }
+            }
+        }
+
+        assert!(checked > 500, "only checked {}", checked);
+    }
+}
@@ -0,0 +1,602 @@
+use std::collections::{HashMap, VecDeque};
+
+use aocisa::{Instruction, Program, RegisterHolder, RegisterOrValue};
+use aocisa::snapshot::{ChannelState, Snapshot};
+use aocisa::source::Source;
+use parse_error::ParseError;
+use simulation::Simulation;
+
+pub const OPCODES : [&str; 9] = ["snd", "set", "add", "sub", "mul", "mod", "rcv", "jgz", "jnz"];
+
+pub fn opcode(inst : &Instruction) -> &'static str {
+    match inst {
+        Instruction::Snd(..) => "snd",
+        Instruction::Set(..) => "set",
+        Instruction::Add(..) => "add",
+        Instruction::Sub(..) => "sub",
+        Instruction::Mul(..) => "mul",
+        Instruction::Mod(..) => "mod",
+        Instruction::Rcv(..) => "rcv",
+        Instruction::Jgz(..) => "jgz",
+        Instruction::Jnz(..) => "jnz",
+        Instruction::Custom(name, _) => name,
+    }
+}
+
+// Where the program goes once an instruction has been carried out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Flow {
+    // On to the next instruction.
+    Next,
+    // This many instructions forward or back from this one.
+    Jump(i64),
+    // Stay on this instruction and try it again on the next step, like a rcv with nothing to
+    // receive yet.
+    Wait,
+    // Stop the program.
+    Halt,
+}
+
+// Carries out one instruction, given the dialect, for opcodes that keep track of something like
+// the last sound played, and the instruction's operands in the order they're written.
+pub type Handler<D> = fn(&mut D, &mut RegisterHolder, &[RegisterOrValue]) -> Flow;
+
+// Each dialect picks which opcodes exist and can give any of them, or opcodes of its own, a
+// handler. Everything else gets the shared handlers, where snd and rcv do nothing.
+pub trait Dialect {
+    // The shared opcodes this dialect has. Programs that use any other opcode that the dialect
+    // doesn't have a handler for are rejected up front.
+    fn opcodes(&self) -> &[&'static str] {
+        &OPCODES
+    }
+
+    // Opcodes that only this dialect has, along with their operands: r for a register that gets
+    // written to and v for a register or number that gets read. Each one needs a handler.
+    fn own_opcodes(&self) -> &[(&'static str, &'static str)] {
+        &[]
+    }
+
+    // Handlers keyed by opcode, taking the place of the shared ones.
+    fn handlers(&self) -> Vec<(&'static str, Handler<Self>)> {
+        vec![]
+    }
+
+    // Adds whatever the dialect keeps track of to a snapshot of the program, as channels or its
+    // process's counters.
+    fn save(&self, _snapshot : &mut Snapshot) {
+    }
+
+    // Picks that back up, failing without changing anything if it isn't there. Dialects that
+    // don't keep anything expect no channels.
+    fn restore(&mut self, snapshot : &Snapshot) -> Result<(), String> {
+        if snapshot.channels.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("snapshot has {} channels, but the program doesn't use any", snapshot.channels.len()))
+        }
+    }
+}
+
+macro_rules! arithmetic_handler {
+    ($name:ident, $op:tt) => {
+        fn $name<D>(_dialect : &mut D, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+            let value = registers.evaluate(&operands[0]) $op registers.evaluate(&operands[1]);
+            registers.store(&operands[0], value);
+            Flow::Next
+        }
+    };
+}
+
+arithmetic_handler!(add, +);
+arithmetic_handler!(sub, -);
+arithmetic_handler!(mul, *);
+arithmetic_handler!(modulo, %);
+
+fn set<D>(_dialect : &mut D, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+    let value = registers.evaluate(&operands[1]);
+    registers.store(&operands[0], value);
+    Flow::Next
+}
+
+fn jump_if(taken : bool, registers : &RegisterHolder, offset : &RegisterOrValue) -> Flow {
+    if taken {
+        Flow::Jump(registers.evaluate(offset))
+    } else {
+        Flow::Next
+    }
+}
+
+fn jgz<D>(_dialect : &mut D, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+    jump_if(registers.evaluate(&operands[0]) > 0, registers, &operands[1])
+}
+
+fn jnz<D>(_dialect : &mut D, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+    jump_if(registers.evaluate(&operands[0]) != 0, registers, &operands[1])
+}
+
+fn nothing<D>(_dialect : &mut D, _registers : &mut RegisterHolder, _operands : &[RegisterOrValue]) -> Flow {
+    Flow::Next
+}
+
+// The handler every dialect starts out with for a shared opcode.
+fn shared_handler<D>(name : &str) -> Option<Handler<D>> {
+    Some(match name {
+        "set" => set,
+        "add" => add,
+        "sub" => sub,
+        "mul" => mul,
+        "mod" => modulo,
+        "jgz" => jgz,
+        "jnz" => jnz,
+        "snd" | "rcv" => nothing,
+        _ => return None,
+    })
+}
+
+// The handler for every opcode a dialect has, keyed by opcode, along with the opcodes in the order
+// to list them in errors.
+fn handler_table<D : Dialect>(dialect : &D) -> (HashMap<&'static str, Handler<D>>, Vec<&'static str>) {
+    let mut names = dialect.opcodes().to_vec();
+    let mut table = names.iter().filter_map(|&name| shared_handler(name).map(|handler| (name, handler))).collect::<HashMap<_, _>>();
+    for (name, handler) in dialect.handlers() {
+        if table.insert(name, handler).is_none() {
+            names.push(name);
+        }
+    }
+
+    (table, names)
+}
+
+fn channel_queue(snapshot : &Snapshot, name : &str) -> Result<VecDeque<i64>, String> {
+    snapshot.channel(name).map(|channel| channel.queue.iter().cloned().collect()).ok_or_else(|| {
+        format!("snapshot has no {} channel", name)
+    })
+}
+
+// Every shared opcode, with snd and rcv doing nothing.
+pub struct Plain;
+
+impl Dialect for Plain {
+}
+
+// Day 18 part a: snd plays a sound, and rcv recovers the last one played if its register isn't
+// zero, which ends the program.
+#[derive(Default)]
+pub struct Sound {
+    pub last_played : Option<i64>,
+    pub recovered : Option<i64>,
+}
+
+impl Sound {
+    fn play(&mut self, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+        self.last_played = Some(registers.evaluate(&operands[0]));
+        Flow::Next
+    }
+
+    fn recover(&mut self, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+        if registers.evaluate(&operands[0]) != 0 {
+            self.recovered = self.last_played;
+            Flow::Halt
+        } else {
+            Flow::Next
+        }
+    }
+}
+
+impl Dialect for Sound {
+    fn opcodes(&self) -> &[&'static str] {
+        &["snd", "set", "add", "mul", "mod", "rcv", "jgz"]
+    }
+
+    fn handlers(&self) -> Vec<(&'static str, Handler<Sound>)> {
+        vec![("snd", Sound::play), ("rcv", Sound::recover)]
+    }
+
+    // The last sound played is kept as a channel holding at most one value.
+    fn save(&self, snapshot : &mut Snapshot) {
+        snapshot.channels.push(ChannelState {
+            name : String::from("played"),
+            queue : self.last_played.into_iter().collect(),
+        });
+    }
+
+    fn restore(&mut self, snapshot : &Snapshot) -> Result<(), String> {
+        let played = channel_queue(snapshot, "played")?;
+        if played.len() > 1 {
+            return Err(format!("snapshot has {} sounds played, not at most one", played.len()));
+        }
+
+        self.last_played = played.front().cloned();
+        self.recovered = None;
+        Ok(())
+    }
+}
+
+// Day 18 part b: snd queues a message for someone else, and rcv takes the oldest one that has
+// arrived, waiting if there isn't one yet.
+#[derive(Default)]
+pub struct Messages {
+    pub inbox : VecDeque<i64>,
+    pub outbox : VecDeque<i64>,
+    pub sent : u64,
+}
+
+impl Messages {
+    fn send(&mut self, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+        self.outbox.push_back(registers.evaluate(&operands[0]));
+        self.sent += 1;
+        Flow::Next
+    }
+
+    fn receive(&mut self, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+        match self.inbox.pop_front() {
+            Some(value) => {
+                registers.store(&operands[0], value);
+                Flow::Next
+            },
+            None => Flow::Wait,
+        }
+    }
+}
+
+impl Dialect for Messages {
+    fn opcodes(&self) -> &[&'static str] {
+        &["snd", "set", "add", "mul", "mod", "rcv", "jgz"]
+    }
+
+    fn handlers(&self) -> Vec<(&'static str, Handler<Messages>)> {
+        vec![("snd", Messages::send), ("rcv", Messages::receive)]
+    }
+
+    fn save(&self, snapshot : &mut Snapshot) {
+        snapshot.processes[0].sends = self.sent;
+        for &(name, queue) in [("inbox", &self.inbox), ("outbox", &self.outbox)].iter() {
+            snapshot.channels.push(ChannelState {
+                name : String::from(name),
+                queue : queue.iter().cloned().collect(),
+            });
+        }
+    }
+
+    fn restore(&mut self, snapshot : &Snapshot) -> Result<(), String> {
+        let inbox = channel_queue(snapshot, "inbox")?;
+        let outbox = channel_queue(snapshot, "outbox")?;
+        self.inbox = inbox;
+        self.outbox = outbox;
+        self.sent = snapshot.processes[0].sends;
+        Ok(())
+    }
+}
+
+// Day 23's coprocessor, which trades snd, rcv, and jgz for sub and jnz.
+pub struct Coprocessor;
+
+impl Dialect for Coprocessor {
+    fn opcodes(&self) -> &[&'static str] {
+        &["set", "sub", "mul", "jnz"]
+    }
+}
+
+// Runs a program in a given dialect. Stepping stops when the program halts, either by leaving
+// the program or because the dialect said so, or while it's waiting on a rcv.
+pub struct Interpreter<'p, D : Dialect> {
+    program : &'p Program,
+    source : Option<&'p Source>,
+    // Looked up once up front for each instruction.
+    decoded : Vec<(Handler<D>, Vec<RegisterOrValue>)>,
+    registers : RegisterHolder,
+    ip : usize,
+    steps : u64,
+    halted : bool,
+    waiting : bool,
+    dialect : D,
+}
+
+impl<'p, D : Dialect> Interpreter<'p, D> {
+    // Fails on the first instruction that the dialect doesn't have a handler for, pointing at its
+    // line in the source.
+    pub fn new(source : &'p Source, dialect : D) -> Result<Interpreter<'p, D>, ParseError> {
+        Interpreter::decode(source.program(), Some(source), dialect)
+    }
+
+    fn decode(program : &'p Program, source : Option<&'p Source>, dialect : D) -> Result<Interpreter<'p, D>, ParseError> {
+        let (table, names) = handler_table(&dialect);
+        let decoded = program.instructions.iter().enumerate().map(|(ip, inst)| {
+            let name = opcode(inst);
+            match table.get(name) {
+                Some(&handler) => Ok((handler, inst.operands())),
+                None => {
+                    let line = source.and_then(|source| source.line_of(ip)).unwrap_or(ip + 1);
+                    Err(ParseError::new(line, 1, &format!("one of {}", names.join(", ")), name))
+                },
+            }
+        }).collect::<Result<Vec<_>, ParseError>>()?;
+
+        Ok(Interpreter {
+            program,
+            source,
+            decoded,
+            registers : RegisterHolder::new(),
+            ip : 0,
+            steps : 0,
+            halted : false,
+            waiting : false,
+            dialect,
+        })
+    }
+
+    pub fn program(&self) -> &'p Program {
+        self.program
+    }
+
+    // The text the program came from, if the interpreter was made from one.
+    pub fn source(&self) -> Option<&'p Source> {
+        self.source
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    // How many instructions have run, not counting rcvs that are still waiting.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn registers(&self) -> &RegisterHolder {
+        &self.registers
+    }
+
+    pub fn registers_mut(&mut self) -> &mut RegisterHolder {
+        &mut self.registers
+    }
+
+    pub fn dialect(&self) -> &D {
+        &self.dialect
+    }
+
+    // For handing the dialect something from outside, like a message for a rcv to pick up.
+    pub fn dialect_mut(&mut self) -> &mut D {
+        &mut self.dialect
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted || self.ip >= self.program.instructions.len()
+    }
+
+    // Whether the last step stopped on a rcv that's waiting. Stepping again tries the rcv again.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    // The registers, where the program is, and whatever the dialect is holding on to.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = self.registers.snapshot(self.program.instructions.len(), self.ip, self.steps);
+        self.dialect.save(&mut snapshot);
+        snapshot
+    }
+
+    // Picks up where a snapshot of the same program in the same dialect left off. Fails without
+    // changing anything if the snapshot doesn't fit.
+    pub fn restore(&mut self, snapshot : &Snapshot) -> Result<(), String> {
+        let mut registers = RegisterHolder::new();
+        let (ip, steps) = registers.restore(snapshot, self.program.instructions.len())?;
+        self.dialect.restore(snapshot)?;
+        self.registers = registers;
+        self.ip = ip;
+        self.steps = steps;
+        self.halted = false;
+        self.waiting = false;
+        Ok(())
+    }
+}
+
+impl<'p> Interpreter<'p, Plain> {
+    // Plain has every shared opcode, so this only fails if the program was parsed with some
+    // other dialect's own opcodes, which is a mistake in the caller.
+    pub fn plain(program : &'p Program) -> Interpreter<'p, Plain> {
+        Interpreter::decode(program, None, Plain).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl<'p, D : Dialect> Simulation for Interpreter<'p, D> {
+    type State = usize;
+
+    fn step(&mut self) -> bool {
+        if self.is_halted() {
+            return false;
+        }
+
+        let (handler, ref operands) = self.decoded[self.ip];
+        self.waiting = false;
+
+        match handler(&mut self.dialect, &mut self.registers, operands) {
+            Flow::Next => self.ip += 1,
+            // Jumping off the front of the program wraps around to a huge instruction pointer
+            // and halts, the same as RegisterHolder::get_next_ip.
+            Flow::Jump(offset) => self.ip = ((self.ip as i64) + offset) as usize,
+            Flow::Wait => {
+                self.waiting = true;
+                return false;
+            },
+            Flow::Halt => self.halted = true,
+        }
+
+        self.steps += 1;
+        true
+    }
+
+    fn state(&self) -> usize {
+        self.ip
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DUET : &str =
+r"snd 1
+snd 2
+snd p
+rcv a
+rcv b
+rcv c
+rcv d";
+
+    #[test]
+    fn sound() {
+        let source = Source::parse("set a 1\nadd a 2\nmul a a\nmod a 5\nsnd a\nset a 0\nrcv a\njgz a -1\nset a 1\njgz a -2").unwrap();
+        let mut interpreter = Interpreter::new(&source, Sound::default()).unwrap();
+        interpreter.run_to_end();
+        assert!(interpreter.is_halted());
+        assert_eq!(interpreter.dialect().recovered, Some(4));
+        assert_eq!(interpreter.ip(), 6);
+    }
+
+    #[test]
+    fn messages() {
+        let source = Source::parse(DUET).unwrap();
+        let mut programs = (0 .. 2).map(|id| {
+            let mut interpreter = Interpreter::new(&source, Messages::default()).unwrap();
+            *interpreter.registers_mut().get_reg_mut('p') = id;
+            interpreter
+        }).collect::<Vec<_>>();
+
+        assert_eq!(programs[0].run_to_end(), 3);
+        assert!(programs[0].is_waiting());
+
+        // Deliver everything each one has sent to the other until they're both stuck.
+        loop {
+            for i in 0 .. 2 {
+                while let Some(value) = programs[i].dialect_mut().outbox.pop_front() {
+                    programs[1 - i].dialect_mut().inbox.push_back(value);
+                }
+            }
+
+            if programs.iter_mut().map(|p| p.run_to_end()).sum::<usize>() == 0 {
+                break;
+            }
+        }
+
+        assert_eq!(programs[1].dialect().sent, 3);
+        assert_eq!(*programs[1].registers().get_reg('c'), 0);
+        assert_eq!(programs[1].ip(), 6);
+        assert!(programs[1].is_waiting() && !programs[1].is_halted());
+    }
+
+    #[test]
+    fn resume() {
+        let source = Source::parse(DUET).unwrap();
+        let mut interpreter = Interpreter::new(&source, Messages::default()).unwrap();
+        interpreter.dialect_mut().inbox.push_back(7);
+        assert_eq!(interpreter.run_for(4), 4);
+
+        let snapshot = interpreter.snapshot();
+        assert_eq!(snapshot.to_string(),
+r"aocisa snapshot
+process 0
+instructions 7
+ip 4
+steps 4 instructions
+sends 3
+receives 0
+registers a=7
+channel inbox
+channel outbox 1 2 0
+");
+
+        let mut resumed = Interpreter::new(&source, Messages::default()).unwrap();
+        resumed.restore(&snapshot).unwrap();
+        resumed.dialect_mut().inbox.push_back(8);
+        resumed.run_to_end();
+        assert_eq!(resumed.ip(), 5);
+        assert_eq!(resumed.steps(), 5);
+        assert_eq!(*resumed.registers().get_reg('b'), 8);
+        assert_eq!(resumed.dialect().outbox.len(), 3);
+
+        // Each dialect wants its own channels.
+        assert_eq!(Interpreter::new(&source, Sound::default()).unwrap().restore(&snapshot), Err(String::from("snapshot has no played channel")));
+        assert_eq!(Interpreter::plain(source.program()).restore(&snapshot), Err(String::from("snapshot has 2 channels, but the program doesn't use any")));
+        let plain = Interpreter::plain(source.program()).snapshot();
+        assert_eq!(resumed.restore(&plain), Err(String::from("snapshot has no inbox channel")));
+        assert_eq!(resumed.ip(), 5);
+    }
+
+    #[test]
+    fn unsupported() {
+        let source = Source::parse("# plays a\nset a 1\n\nsnd a").unwrap();
+        let error = Interpreter::new(&source, Coprocessor).err().unwrap();
+        assert_eq!(error.to_string(), "line 4, column 1: expected one of set, sub, mul, jnz, found \"snd\"");
+        assert!(Interpreter::new(&source, Plain).is_ok());
+
+        let source = Source::parse(DUET).unwrap();
+        assert!(Interpreter::new(&source, Coprocessor).is_err());
+    }
+
+    // Counts down with an opcode of its own, prints with another, and stops at the first snd.
+    #[derive(Default)]
+    struct Printer {
+        printed : Vec<i64>,
+    }
+
+    impl Printer {
+        fn out(&mut self, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+            self.printed.push(registers.evaluate(&operands[0]));
+            Flow::Next
+        }
+
+        fn dec(&mut self, registers : &mut RegisterHolder, operands : &[RegisterOrValue]) -> Flow {
+            let value = registers.evaluate(&operands[0]) - 1;
+            registers.store(&operands[0], value);
+            Flow::Next
+        }
+
+        fn stop(&mut self, _registers : &mut RegisterHolder, _operands : &[RegisterOrValue]) -> Flow {
+            Flow::Halt
+        }
+    }
+
+    impl Dialect for Printer {
+        fn opcodes(&self) -> &[&'static str] {
+            &["set", "jgz"]
+        }
+
+        fn own_opcodes(&self) -> &[(&'static str, &'static str)] {
+            &[("out", "v"), ("dec", "r")]
+        }
+
+        fn handlers(&self) -> Vec<(&'static str, Handler<Printer>)> {
+            vec![("out", Printer::out), ("dec", Printer::dec), ("snd", Printer::stop)]
+        }
+    }
+
+    const COUNTDOWN : &str =
+r"set a 3
+out a   # prints
+dec a
+jgz a -2
+snd 0
+set b 1";
+
+    #[test]
+    fn handlers() {
+        let source = Source::parse_for(COUNTDOWN, &Printer::default()).unwrap();
+        assert_eq!(source.program().instructions[2].to_string(), "dec a");
+
+        let mut interpreter = Interpreter::new(&source, Printer::default()).unwrap();
+        interpreter.run_to_end();
+        assert_eq!(interpreter.dialect().printed, vec![3, 2, 1]);
+        assert!(interpreter.is_halted());
+        assert_eq!(interpreter.ip(), 4);
+        assert_eq!(*interpreter.registers().get_reg('b'), 0);
+
+        // Only the dialect's own opcodes parse, and only with the operands it asked for.
+        assert_eq!(Source::parse(COUNTDOWN).err(), Some(ParseError::new(2, 1, "an instruction", "out")));
+        assert_eq!(Source::parse_for("dec 1", &Printer::default()).err(), Some(ParseError::new(1, 5, "a register", "1")));
+        let error = Interpreter::new(&source, Plain).err().unwrap();
+        assert_eq!(error.to_string(), "line 2, column 1: expected one of snd, set, add, sub, mul, mod, rcv, jgz, jnz, found \"out\"");
+        let error = Interpreter::new(&Source::parse("set a 1\nmul a 2").unwrap(), Printer::default()).err().unwrap();
+        assert_eq!(error.to_string(), "line 2, column 1: expected one of set, jgz, out, dec, snd, found \"mul\"");
+    }
+}
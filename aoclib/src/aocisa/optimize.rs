@@ -0,0 +1,620 @@
+use std::fmt;
+
+use aocisa::{Instruction, Program, RegisterHolder, RegisterOrValue};
+use aocisa::snapshot::Snapshot;
+use aocisa::source::Source;
+
+// One slot of an optimized program. Macro-instructions stand in for a whole loop starting at
+// their index and have the same effect on the registers as running it. The loop's instructions
+// stay where they are after it, so jumps into the middle of a loop still work.
+pub enum Op<'p> {
+    Basic(&'p Instruction),
+
+    // add target factor / sub counter 1 / jnz counter -2, or the same with sub for the first
+    // instruction or a counter counting up to zero. Multiplies instead of adding in a loop.
+    MultiplyAdd {
+        target : char,
+        factor : &'p RegisterOrValue,
+        subtract : bool,
+        counter : char,
+        counter_step : i64,
+    },
+
+    // Checks whether factor * cofactor == product for every cofactor up to product, clearing
+    // flag if so. scratch holds the comparison.
+    DivisorSearch {
+        factor : char,
+        cofactor : char,
+        product : char,
+        flag : char,
+        scratch : char,
+    },
+
+    // A DivisorSearch with cofactor starting at 2 for every factor up to product, which clears
+    // flag if product is composite.
+    CompositeSearch {
+        factor : char,
+        cofactor : char,
+        product : char,
+        flag : char,
+        scratch : char,
+    },
+}
+
+pub struct OptimizedProgram<'p> {
+    program : &'p Program,
+    source : Option<&'p Source>,
+    pub ops : Vec<Op<'p>>,
+}
+
+fn as_reg(rv : &RegisterOrValue) -> Option<char> {
+    match *rv {
+        RegisterOrValue::Reg(r) => Some(r),
+        RegisterOrValue::Val(_) => None,
+    }
+}
+
+fn as_val(rv : &RegisterOrValue) -> Option<i64> {
+    match *rv {
+        RegisterOrValue::Reg(_) => None,
+        RegisterOrValue::Val(v) => Some(v),
+    }
+}
+
+fn all_different(registers : &[char]) -> bool {
+    registers.iter().enumerate().all(|(i, r)| !registers[i + 1 ..].contains(r))
+}
+
+impl<'p> Op<'p> {
+    // How many of the original instructions this stands in for.
+    pub fn span(&self) -> usize {
+        match *self {
+            Op::Basic(_) => 1,
+            Op::MultiplyAdd { .. } => 3,
+            Op::DivisorSearch { .. } => 9,
+            Op::CompositeSearch { .. } => 14,
+        }
+    }
+
+    fn match_multiply_add(insts : &'p [Instruction]) -> Option<Op<'p>> {
+        if insts.len() < 3 {
+            return None;
+        }
+
+        let (target, factor, subtract) = match insts[0] {
+            Instruction::Add(r, ref x) => (r, x, false),
+            Instruction::Sub(r, ref x) => (r, x, true),
+            _ => return None,
+        };
+
+        let (counter, counter_step) = match insts[1] {
+            Instruction::Add(r, ref x) => (r, as_val(x)?),
+            Instruction::Sub(r, ref x) => (r, -as_val(x)?),
+            _ => return None,
+        };
+
+        match insts[2] {
+            Instruction::Jnz(ref x, ref y) if as_reg(x) == Some(counter) && as_val(y) == Some(-2) => {},
+            _ => return None,
+        }
+
+        if (counter_step != 1 && counter_step != -1) || target == counter || as_reg(factor) == Some(target) || as_reg(factor) == Some(counter) {
+            return None;
+        }
+
+        Some(Op::MultiplyAdd {
+            target,
+            factor,
+            subtract,
+            counter,
+            counter_step,
+        })
+    }
+
+    // set g d / mul g e / sub g b / jnz g 2 / set f 0 / sub e -1 / set g e / sub g b / jnz g -8
+    fn match_divisor_search(insts : &'p [Instruction]) -> Option<Op<'p>> {
+        if insts.len() < 9 {
+            return None;
+        }
+
+        let (scratch, factor) = match insts[0] {
+            Instruction::Set(g, ref d) => (g, as_reg(d)?),
+            _ => return None,
+        };
+
+        let cofactor = match insts[1] {
+            Instruction::Mul(g, ref e) if g == scratch => as_reg(e)?,
+            _ => return None,
+        };
+
+        let product = match insts[2] {
+            Instruction::Sub(g, ref b) if g == scratch => as_reg(b)?,
+            _ => return None,
+        };
+
+        let flag = match insts[4] {
+            Instruction::Set(f, ref zero) if as_val(zero) == Some(0) => f,
+            _ => return None,
+        };
+
+        let matches = match (&insts[3], &insts[5], &insts[6], &insts[7], &insts[8]) {
+            (Instruction::Jnz(g1, skip),
+             Instruction::Sub(e1, step),
+             Instruction::Set(g2, e2),
+             Instruction::Sub(g3, b1),
+             Instruction::Jnz(g4, back)) => {
+                as_reg(g1) == Some(scratch) && as_val(skip) == Some(2) &&
+                *e1 == cofactor && as_val(step) == Some(-1) &&
+                *g2 == scratch && as_reg(e2) == Some(cofactor) &&
+                *g3 == scratch && as_reg(b1) == Some(product) &&
+                as_reg(g4) == Some(scratch) && as_val(back) == Some(-8)
+            },
+            _ => false,
+        };
+
+        if !matches || !all_different(&[factor, cofactor, product, flag, scratch]) {
+            return None;
+        }
+
+        Some(Op::DivisorSearch {
+            factor,
+            cofactor,
+            product,
+            flag,
+            scratch,
+        })
+    }
+
+    // set e 2 / the divisor search / sub d -1 / set g d / sub g b / jnz g -13
+    fn match_composite_search(insts : &'p [Instruction]) -> Option<Op<'p>> {
+        if insts.len() < 14 {
+            return None;
+        }
+
+        let (factor, cofactor, product, flag, scratch) = match Op::match_divisor_search(&insts[1 ..])? {
+            Op::DivisorSearch { factor, cofactor, product, flag, scratch } => (factor, cofactor, product, flag, scratch),
+            _ => unreachable!(),
+        };
+
+        let matches = match (&insts[0], &insts[10], &insts[11], &insts[12], &insts[13]) {
+            (Instruction::Set(e1, two),
+             Instruction::Sub(d1, step),
+             Instruction::Set(g1, d2),
+             Instruction::Sub(g2, b1),
+             Instruction::Jnz(g3, back)) => {
+                *e1 == cofactor && as_val(two) == Some(2) &&
+                *d1 == factor && as_val(step) == Some(-1) &&
+                *g1 == scratch && as_reg(d2) == Some(factor) &&
+                *g2 == scratch && as_reg(b1) == Some(product) &&
+                as_reg(g3) == Some(scratch) && as_val(back) == Some(-13)
+            },
+            _ => false,
+        };
+
+        if !matches {
+            return None;
+        }
+
+        Some(Op::CompositeSearch {
+            factor,
+            cofactor,
+            product,
+            flag,
+            scratch,
+        })
+    }
+
+    // Applies a macro-instruction's effects and returns how many of the original instructions
+    // running the loop would have taken. Returns None without changing anything if the loop
+    // wouldn't behave the way the macro assumes, e.g. a counter that has to wrap around, or if it
+    // would take more than budget instructions, so that a paused run stops exactly where a plain
+    // one would.
+    fn apply_macro(&self, registers : &mut RegisterHolder, budget : u64) -> Option<u64> {
+        match *self {
+            Op::Basic(_) => None,
+            Op::MultiplyAdd { target, factor, subtract, counter, counter_step } => {
+                let count = *registers.get_reg(counter);
+                let iterations = -count * counter_step;
+                if iterations < 1 || 3 * iterations as u64 > budget {
+                    return None;
+                }
+
+                let amount = registers.evaluate(factor) * iterations;
+                *registers.get_reg_mut(target) += if subtract { -amount } else { amount };
+                *registers.get_reg_mut(counter) = 0;
+                Some(3 * iterations as u64)
+            },
+            Op::DivisorSearch { factor, cofactor, product, flag, scratch } => {
+                let d = *registers.get_reg(factor);
+                let e = *registers.get_reg(cofactor);
+                let b = *registers.get_reg(product);
+                if e >= b {
+                    return None;
+                }
+
+                let hits = divisor_hits(d, e, b);
+                let cost = 8 * (b - e) as u64 + hits;
+                if cost > budget {
+                    return None;
+                }
+
+                if hits > 0 {
+                    *registers.get_reg_mut(flag) = 0;
+                }
+
+                *registers.get_reg_mut(cofactor) = b;
+                *registers.get_reg_mut(scratch) = 0;
+                Some(cost)
+            },
+            Op::CompositeSearch { factor, cofactor, product, flag, scratch } => {
+                let d = *registers.get_reg(factor);
+                let b = *registers.get_reg(product);
+                if d >= b || b <= 2 {
+                    return None;
+                }
+
+                // A factor in [d, b) with a cofactor in [2, b) has to be between 2 and b / 2.
+                let hits = (if d < 2 { 2 } else { d } ..= b / 2).filter(|&candidate| b % candidate == 0).count() as u64;
+
+                // Each factor sets the cofactor, runs the divisor search from 2, and takes four
+                // more instructions to move on to the next one.
+                let cost = (b - d) as u64 * (5 + 8 * (b - 2) as u64) + hits;
+                if cost > budget {
+                    return None;
+                }
+
+                if hits > 0 {
+                    *registers.get_reg_mut(flag) = 0;
+                }
+
+                *registers.get_reg_mut(factor) = b;
+                *registers.get_reg_mut(cofactor) = b;
+                *registers.get_reg_mut(scratch) = 0;
+                Some(cost)
+            },
+        }
+    }
+}
+
+// How many cofactors in [e, b) give d * cofactor == b, each of which runs the set f 0 in the
+// divisor search.
+fn divisor_hits(d : i64, e : i64, b : i64) -> u64 {
+    if d == 0 {
+        if b == 0 { (b - e) as u64 } else { 0 }
+    } else if b % d == 0 && b / d >= e && b / d < b {
+        1
+    } else {
+        0
+    }
+}
+
+impl<'p> fmt::Display for Op<'p> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Op::Basic(inst) => write!(f, "{}", inst),
+            Op::MultiplyAdd { target, factor, subtract, counter, .. } => {
+                write!(f, "{} {} * {} into {}, {} = 0", if subtract { "subtract" } else { "add" }, factor, counter, target, counter)
+            },
+            Op::DivisorSearch { factor, cofactor, product, flag, .. } => {
+                write!(f, "{} = 0 if {} * ({} .. {}) hits {}", flag, factor, cofactor, product, product)
+            },
+            Op::CompositeSearch { factor, product, flag, .. } => {
+                write!(f, "{} = 0 if {} has a factor from {} up", flag, product, factor)
+            },
+        }
+    }
+}
+
+impl<'p> OptimizedProgram<'p> {
+    // The program as-is, with no macro-instructions.
+    pub fn plain(program : &'p Program) -> OptimizedProgram<'p> {
+        OptimizedProgram {
+            program,
+            source : None,
+            ops : program.instructions.iter().map(Op::Basic).collect(),
+        }
+    }
+
+    // Replaces every loop that matches a known idiom, preferring the biggest match at each index.
+    pub fn optimize(program : &'p Program) -> OptimizedProgram<'p> {
+        let insts = program.instructions.as_slice();
+
+        OptimizedProgram {
+            program,
+            source : None,
+            ops : (0 .. insts.len()).map(|i| {
+                Op::match_composite_search(&insts[i ..])
+                    .or_else(|| Op::match_divisor_search(&insts[i ..]))
+                    .or_else(|| Op::match_multiply_add(&insts[i ..]))
+                    .unwrap_or(Op::Basic(&insts[i]))
+            }).collect(),
+        }
+    }
+
+    // Optimizes the program parsed from source, so that it can be written back out with the
+    // source's comments and blank lines.
+    pub fn for_source(source : &'p Source) -> OptimizedProgram<'p> {
+        let mut optimized = OptimizedProgram::optimize(source.program());
+        optimized.source = Some(source);
+        optimized
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub fn macro_count(&self) -> usize {
+        self.ops.iter().filter(|op| op.span() > 1).count()
+    }
+
+    // The original program as text, with a comment above each loop that was replaced saying what
+    // it does instead. Comments are all that's added, so the text loads back into the same
+    // program. That's the source's text if there is one, and otherwise one instruction per line.
+    pub fn to_source(&self) -> Source {
+        let notes = self.ops.iter().enumerate().filter(|&(_, op)| op.span() > 1).map(|(ip, op)| {
+            (ip, format!("optimized: {}, in place of {} instructions", op, op.span()))
+        }).collect::<Vec<(usize, String)>>();
+
+        match self.source {
+            Some(source) => source.annotate(&notes),
+            None => Source::from_program(self.program.clone()).annotate(&notes),
+        }
+    }
+
+    // Executes the op at ip, taking at most budget of the original instructions, and returns the
+    // next ip and how many it took. Only the original instruction is looked at for snd and rcv, so
+    // days that use those need to handle them first.
+    pub fn execute(&self, registers : &mut RegisterHolder, ip : usize, budget : u64) -> (usize, u64) {
+        match self.ops[ip].apply_macro(registers, budget) {
+            Some(instructions) => (ip + self.ops[ip].span(), instructions),
+            None => {
+                let inst = &self.program.instructions[ip];
+                registers.apply_instruction(inst);
+                (registers.get_next_ip(inst, ip), 1)
+            },
+        }
+    }
+
+    // Runs from the start until the program ends, returning the number of instructions executed,
+    // counting each one a macro-instruction stands in for.
+    pub fn run(&self, registers : &mut RegisterHolder) -> u64 {
+        self.run_from(registers, 0, u64::MAX).1
+    }
+
+    // Runs from ip until the program ends or max_steps instructions have been executed, returning
+    // where it stopped and how many it executed. Steps are always the original instructions, with
+    // or without macro-instructions, so a long run can be paused this way, snapshotted, and
+    // carried on later from the ip it returned and end up where an uninterrupted run would.
+    pub fn run_from(&self, registers : &mut RegisterHolder, mut ip : usize, max_steps : u64) -> (usize, u64) {
+        let mut steps = 0;
+        while ip < self.ops.len() && steps < max_steps {
+            let (next_ip, instructions) = self.execute(registers, ip, max_steps - steps);
+            ip = next_ip;
+            steps += instructions;
+        }
+        (ip, steps)
+    }
+
+    // A paused run, stopped at ip after steps instructions. Whether the program was optimized
+    // doesn't matter, so a run can be paused with one and resumed with the other.
+    pub fn snapshot(&self, registers : &RegisterHolder, ip : usize, steps : u64) -> Snapshot {
+        registers.snapshot(self.len(), ip, steps)
+    }
+
+    // Replaces the registers with a snapshot's, returning the ip and step count to carry on from.
+    pub fn restore(&self, registers : &mut RegisterHolder, snapshot : &Snapshot) -> Result<(usize, u64), String> {
+        registers.restore(snapshot, self.len())
+    }
+}
+
+impl<'p> fmt::Display for OptimizedProgram<'p> {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_source())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run_both(input : &str, a : i64, expected_macros : usize) -> (RegisterHolder, u64, u64) {
+        let program = Program::load(input).unwrap();
+
+        let mut plain_registers = RegisterHolder::new();
+        *plain_registers.get_reg_mut('a') = a;
+        let plain_steps = OptimizedProgram::plain(&program).run(&mut plain_registers);
+
+        let optimized = OptimizedProgram::optimize(&program);
+        assert_eq!(optimized.macro_count(), expected_macros);
+        let mut optimized_registers = RegisterHolder::new();
+        *optimized_registers.get_reg_mut('a') = a;
+        let optimized_steps = optimized.run(&mut optimized_registers);
+
+        for r in "abcdefgh".chars() {
+            assert_eq!(optimized_registers.get_reg(r), plain_registers.get_reg(r), "register {}", r);
+        }
+
+        assert_eq!(optimized_steps, plain_steps);
+
+        (optimized_registers, plain_steps, optimized_steps)
+    }
+
+    #[test]
+    fn multiply() {
+        let input =
+r"set b 7
+set c 6
+add a b
+sub c 1
+jnz c -2
+set d -4
+sub a 3
+sub d -1
+jnz d -2";
+        let (registers, steps, _) = run_both(input, 1, 2);
+        assert_eq!(*registers.get_reg('a'), 1 + 7 * 6 - 3 * 4);
+        assert_eq!(steps, 2 + 3 * 6 + 1 + 3 * 4);
+    }
+
+    #[test]
+    fn multiply_needs_wrap() {
+        // With the counter at zero the loop would have to wrap all the way around, so only the
+        // add runs.
+        let program = Program::load("add a 5\nsub c 1\njnz c -2").unwrap();
+        let optimized = OptimizedProgram::optimize(&program);
+        let mut registers = RegisterHolder::new();
+        assert_eq!(optimized.execute(&mut registers, 0, u64::MAX), (1, 1));
+        assert_eq!(*registers.get_reg('a'), 5);
+
+        // Too long for the budget, so again only the add runs.
+        *registers.get_reg_mut('c') = 3;
+        assert_eq!(optimized.execute(&mut registers, 0, 8), (1, 1));
+        assert_eq!(*registers.get_reg('a'), 10);
+
+        assert_eq!(optimized.execute(&mut registers, 0, 9), (3, 9));
+        assert_eq!(*registers.get_reg('a'), 25);
+        assert_eq!(*registers.get_reg('c'), 0);
+    }
+
+    // Day 23's program, counting composite numbers between b and c in steps of s.
+    fn composites(b : i64, c : i64, s : i64) -> String {
+        format!(
+r"set b {}
+set c {}
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -{}
+jnz 1 -23", b, c, s)
+    }
+
+    #[test]
+    fn composite_search() {
+        let (registers, _, _) = run_both(&composites(81, 115, 17), 0, 2);
+        assert_eq!(*registers.get_reg('h'), 3);
+
+        let (registers, _, _) = run_both(&composites(3, 23, 1), 0, 2);
+        assert_eq!(*registers.get_reg('h'), 21 - 8);
+    }
+
+    #[test]
+    fn divisor_search() {
+        // Counts as many hits as set f 0 would run, with negative cofactors and even when every
+        // cofactor hits.
+        for &(d, e, b, hit) in [(3, 1, 6, true), (-2, -5, 6, true), (4, 1, 6, false), (0, -3, 0, true), (0, -3, 5, false)].iter() {
+            let input = format!("set f 1\nset d {}\nset e {}\nset b {}\nset g d\nmul g e\nsub g b\njnz g 2\nset f 0\nsub e -1\nset g e\nsub g b\njnz g -8", d, e, b);
+            let (registers, _, _) = run_both(&input, 0, 1);
+            assert_eq!(*registers.get_reg('f'), if hit { 0 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn resume() {
+        let program = Program::load(&composites(3, 23, 1)).unwrap();
+        let optimized = OptimizedProgram::optimize(&program);
+        let mut registers = RegisterHolder::new();
+        let (ip, steps) = optimized.run_from(&mut registers, 0, 20);
+        assert_eq!(steps, 20);
+        let text = optimized.snapshot(&registers, ip, steps).to_string();
+
+        // Pick it up without the macro-instructions, which only changes how long it takes.
+        let plain = OptimizedProgram::plain(&program);
+        let mut resumed = RegisterHolder::new();
+        let (ip, _) = plain.restore(&mut resumed, &Snapshot::parse(&text).unwrap()).unwrap();
+        plain.run_from(&mut resumed, ip, u64::MAX);
+        assert_eq!(*resumed.get_reg('h'), 21 - 8);
+
+        let other = Program::load("set a 1").unwrap();
+        assert!(OptimizedProgram::plain(&other).restore(&mut resumed, &Snapshot::parse(&text).unwrap()).is_err());
+        assert_eq!(*resumed.get_reg('h'), 21 - 8);
+    }
+
+    #[test]
+    fn pause_anywhere() {
+        let program = Program::load(&composites(81, 115, 17)).unwrap();
+        let optimized = OptimizedProgram::optimize(&program);
+        let mut expected = RegisterHolder::new();
+        let total = optimized.run(&mut expected);
+
+        // Pauses inside a macro-instruction's loop too, which have to be run instruction by
+        // instruction to stop at the right place.
+        for &pause in [1, 7, 20, 1000, 55555, total - 1].iter() {
+            let mut registers = RegisterHolder::new();
+            let (ip, steps) = optimized.run_from(&mut registers, 0, pause);
+            assert_eq!(steps, pause);
+            let text = optimized.snapshot(&registers, ip, steps).to_string();
+
+            let mut resumed = RegisterHolder::new();
+            let (ip, steps) = optimized.restore(&mut resumed, &Snapshot::parse(&text).unwrap()).unwrap();
+            let (ip, ran) = optimized.run_from(&mut resumed, ip, u64::MAX);
+            assert_eq!(ip, optimized.len());
+            assert_eq!(steps + ran, total, "paused after {}", pause);
+            assert_eq!(resumed.values(), expected.values(), "paused after {}", pause);
+        }
+    }
+
+    #[test]
+    fn display() {
+        let program = Program::load(&composites(81, 81, 17)).unwrap();
+        let optimized = OptimizedProgram::optimize(&program);
+        assert_eq!(optimized.ops[4].to_string(), "f = 0 if b has a factor from d up");
+        assert_eq!(optimized.ops[5].to_string(), "f = 0 if d * (e .. b) hits b");
+        assert_eq!(optimized.ops[6].to_string(), "mul g e");
+
+        let text = optimized.to_string();
+        assert!(text.contains("set d 2\n# optimized: f = 0 if b has a factor from d up, in place of 14 instructions\nset e 2\n"));
+        assert_eq!(text.lines().filter(|line| line.starts_with('#')).count(), 2);
+
+        let source = Source::parse(&text).unwrap();
+        assert_eq!(source.program().to_string(), program.to_string());
+        assert_eq!(source.line_of(4), Some(6));
+        assert_eq!(OptimizedProgram::optimize(source.program()).to_string(), text);
+    }
+
+    #[test]
+    fn commented() {
+        let input =
+r"# multiplies b by c into a
+set b 7
+set c 6
+
+    # the loop
+    add a b   # a += b
+    sub c 1
+    jnz c -2
+snd a
+";
+        let source = Source::parse(input).unwrap();
+        let optimized = OptimizedProgram::for_source(&source);
+        let text = optimized.to_string();
+        assert_eq!(text, input.replace("    add a b", "    # optimized: add b * c into a, c = 0, in place of 3 instructions\n    add a b"));
+
+        let reloaded = Source::parse(&text).unwrap();
+        assert_eq!(reloaded.program().to_string(), source.program().to_string());
+        assert_eq!(reloaded.line_of(2), Some(7));
+        assert_eq!(OptimizedProgram::for_source(&reloaded).macro_count(), 1);
+    }
+}
@@ -0,0 +1,239 @@
+use std::cmp::Reverse;
+
+use aocisa::{Program, RegisterHolder};
+use aocisa::cfg::Cfg;
+use aocisa::dialect::{Dialect, Interpreter, OPCODES, Plain, opcode};
+use aocisa::source::Source;
+use simulation::Simulation;
+
+// Runs a program through an interpreter, counting how many times each instruction executes. By
+// default that's the plain dialect, where snd and rcv do nothing but move on to the next
+// instruction, as in the debugger.
+pub struct Profiler<'p, D : Dialect = Plain> {
+    interpreter : Interpreter<'p, D>,
+    counts : Vec<u64>,
+    source : Option<&'p Source>,
+}
+
+// How much of the run a loop accounts for, counting everything nested inside it.
+#[derive(PartialEq, Debug)]
+pub struct LoopProfile {
+    pub first_ip : usize,
+    pub last_ip : usize,
+    pub depth : usize,
+    pub count : u64,
+}
+
+impl<'p> Profiler<'p> {
+    pub fn new(program : &'p Program) -> Profiler<'p> {
+        Profiler::with_interpreter(Interpreter::plain(program))
+    }
+
+    // Profiles the program parsed from source, so the report can point at lines in the text.
+    pub fn for_source(source : &'p Source) -> Profiler<'p> {
+        let mut profiler = Profiler::new(source.program());
+        profiler.source = Some(source);
+        profiler
+    }
+}
+
+impl<'p, D : Dialect> Profiler<'p, D> {
+    // Profiles whatever the interpreter runs from here on, pointing at lines in its source if it
+    // has one.
+    pub fn with_interpreter(interpreter : Interpreter<'p, D>) -> Profiler<'p, D> {
+        Profiler {
+            counts : vec![0; interpreter.program().instructions.len()],
+            source : interpreter.source(),
+            interpreter,
+        }
+    }
+
+    pub fn interpreter(&self) -> &Interpreter<'p, D> {
+        &self.interpreter
+    }
+
+    pub fn registers(&self) -> &RegisterHolder {
+        self.interpreter.registers()
+    }
+
+    pub fn registers_mut(&mut self) -> &mut RegisterHolder {
+        self.interpreter.registers_mut()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.interpreter.is_halted()
+    }
+
+    fn program(&self) -> &'p Program {
+        self.interpreter.program()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn count(&self, ip : usize) -> u64 {
+        self.counts[ip]
+    }
+
+    pub fn opcode_count(&self, name : &str) -> u64 {
+        self.program().instructions.iter().zip(self.counts.iter()).filter(|&(inst, _)| {
+            opcode(inst) == name
+        }).map(|(_, &count)| count).sum()
+    }
+
+    // Every opcode that ran, most executed first. Ties go in the order of the shared opcodes, then
+    // the dialect's own ones as they first appear.
+    pub fn opcode_totals(&self) -> Vec<(&'static str, u64)> {
+        let mut names = OPCODES.to_vec();
+        for inst in self.program().instructions.iter() {
+            if !names.contains(&opcode(inst)) {
+                names.push(opcode(inst));
+            }
+        }
+
+        let mut totals = names.into_iter().map(|name| (name, self.opcode_count(name))).filter(|&(_, count)| count > 0).collect::<Vec<(&str, u64)>>();
+        totals.sort_by_key(|&(_, count)| Reverse(count));
+        totals
+    }
+
+    // The instruction pointers that ran the most, with their counts, most executed first.
+    pub fn hottest(&self, limit : usize) -> Vec<(usize, u64)> {
+        let mut ranked = self.counts.iter().cloned().enumerate().filter(|&(_, count)| count > 0).collect::<Vec<(usize, u64)>>();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        ranked
+    }
+
+    // Every loop in the program, most executed first.
+    pub fn loops(&self) -> Vec<LoopProfile> {
+        let cfg = Cfg::build(self.program());
+        let mut loops = cfg.loops.iter().map(|l| {
+            let blocks = l.body.iter().map(|&b| &cfg.blocks[b]).collect::<Vec<_>>();
+            LoopProfile {
+                first_ip : blocks.iter().map(|block| block.start).min().unwrap(),
+                last_ip : blocks.iter().map(|block| block.end).max().unwrap() - 1,
+                depth : cfg.depth(l.header),
+                count : blocks.iter().map(|block| self.counts[block.start .. block.end].iter().sum::<u64>()).sum(),
+            }
+        }).collect::<Vec<LoopProfile>>();
+
+        loops.sort_by(|a, b| b.count.cmp(&a.count).then(a.first_ip.cmp(&b.first_ip)));
+        loops
+    }
+
+    // A ranked table of the hottest instructions, then the opcode totals and the loops.
+    pub fn report(&self, limit : usize) -> String {
+        let total = self.total();
+        let percent = |count : u64| if total == 0 { 0.0 } else { 100.0 * count as f64 / total as f64 };
+        let mut lines = vec![format!("{} instructions executed", total)];
+
+        lines.push(String::from("\nhottest instructions:"));
+        for (ip, count) in self.hottest(limit) {
+            let line = match self.source.and_then(|source| source.line_of(ip)) {
+                Some(line) => format!("  # line {}", line),
+                None => String::new(),
+            };
+
+            lines.push(format!("{:>5} {:>12} {:>6.1}%  {}{}", ip, count, percent(count), self.program().instructions[ip], line));
+        }
+
+        lines.push(String::from("\nby opcode:"));
+        for (name, count) in self.opcode_totals() {
+            lines.push(format!("  {} {:>12} {:>6.1}%", name, count, percent(count)));
+        }
+
+        let loops = self.loops();
+        if !loops.is_empty() {
+            lines.push(String::from("\nloops:"));
+            for l in loops.iter().take(limit) {
+                let range = format!("{}-{}", l.first_ip, l.last_ip);
+                lines.push(format!("{:>9} {:>12} {:>6.1}%  depth {}", range, l.count, percent(l.count), l.depth));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl<'p, D : Dialect> Simulation for Profiler<'p, D> {
+    type State = u64;
+
+    fn step(&mut self) -> bool {
+        let ip = self.interpreter.ip();
+        if self.interpreter.step() {
+            self.counts[ip] += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn state(&self) -> u64 {
+        self.total()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Adds b to a, c times over, then doubles a.
+    const NESTED : &str =
+r"set c 3
+set d b
+add a 1
+sub d 1
+jnz d -2
+sub c 1
+jnz c -5
+mul a 2";
+
+    #[test]
+    fn counts() {
+        let program = Program::load(NESTED).unwrap();
+        let mut profiler = Profiler::new(&program);
+        *profiler.registers_mut().get_reg_mut('b') = 4;
+        assert_eq!(profiler.run_to_end(), 1 + 3 * (1 + 4 * 3 + 2) + 1);
+        assert_eq!(*profiler.registers().get_reg('a'), 24);
+        assert_eq!(profiler.count(2), 12);
+        assert_eq!(profiler.opcode_count("sub"), 12 + 3);
+        assert_eq!(profiler.opcode_totals(), vec![("sub", 15), ("jnz", 15), ("add", 12), ("set", 4), ("mul", 1)]);
+        assert_eq!(profiler.hottest(3), vec![(2, 12), (3, 12), (4, 12)]);
+        assert_eq!(profiler.loops(), vec![
+            LoopProfile { first_ip : 1, last_ip : 6, depth : 1, count : 45 },
+            LoopProfile { first_ip : 2, last_ip : 4, depth : 2, count : 36 },
+        ]);
+    }
+
+    #[test]
+    fn report() {
+        let program = Program::load(NESTED).unwrap();
+        let mut profiler = Profiler::new(&program);
+        *profiler.registers_mut().get_reg_mut('b') = 1;
+        profiler.run_to_end();
+        assert_eq!(profiler.report(2),
+r"20 instructions executed
+
+hottest instructions:
+    1            3   15.0%  set d b
+    2            3   15.0%  add a 1
+
+by opcode:
+  sub            6   30.0%
+  jnz            6   30.0%
+  set            4   20.0%
+  add            3   15.0%
+  mul            1    5.0%
+
+loops:
+      1-6           18   90.0%  depth 1
+      2-4            9   45.0%  depth 2");
+
+        let source = Source::parse(&format!("# nested\n{}", NESTED)).unwrap();
+        let mut profiler = Profiler::for_source(&source);
+        *profiler.registers_mut().get_reg_mut('b') = 1;
+        profiler.run_to_end();
+        assert!(profiler.report(1).contains("    1            3   15.0%  set d b  # line 3\n"));
+    }
+}
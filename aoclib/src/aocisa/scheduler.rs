@@ -0,0 +1,564 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use aocisa::bytecode::{Bytecode, Machine, Step};
+use aocisa::snapshot::{ChannelState, Snapshot};
+
+// How far each process gets to run before the next one takes a turn.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Schedule {
+    // One instruction per turn.
+    RoundRobin,
+    // Until it waits on an empty inbox or halts.
+    UntilBlocked,
+}
+
+// Why the scheduler stopped.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    // Every process ran off the end of the program.
+    Halted,
+    // Every process is waiting on a rcv, so none of them will ever send again.
+    Deadlocked,
+    // Some processes halted, and the rest are waiting on a rcv that nobody is left to answer.
+    Starved,
+    // Some process could still make progress. Only seen when asking before run is done.
+    Running,
+}
+
+// Where a single process is at.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Halted,
+    // Waiting on a rcv into this register with an empty inbox, or with no inbox at all.
+    Blocked(char),
+    // Able to run its next instruction.
+    Ready,
+}
+
+// A snapshot of one process, for explaining how a run ended.
+#[derive(PartialEq, Debug)]
+pub struct ProcessReport {
+    pub id : usize,
+    pub status : Status,
+    pub ip : usize,
+    pub pending : usize,
+    pub registers : Vec<(char, i64)>,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Report {
+    pub outcome : Outcome,
+    pub processes : Vec<ProcessReport>,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Stats {
+    pub sends : u64,
+    pub receives : u64,
+    pub steps : u64,
+}
+
+struct Channel {
+    name : String,
+    reader : usize,
+    queue : VecDeque<i64>,
+}
+
+struct Process<'b> {
+    machine : Machine<'b>,
+    inbox : Option<usize>,
+    outputs : Vec<usize>,
+    sends : u64,
+    receives : u64,
+}
+
+// Runs several programs at once, passing snd values to other processes' rcvs through named FIFO
+// channels. Each process reads from at most one channel but can send into any number of them.
+pub struct Scheduler<'b> {
+    processes : Vec<Process<'b>>,
+    channels : Vec<Channel>,
+    schedule : Schedule,
+}
+
+impl<'b> Scheduler<'b> {
+    // A scheduler with no processes. Add them with add_process, then wire them up with connect.
+    pub fn new(schedule : Schedule) -> Scheduler<'b> {
+        Scheduler {
+            processes : vec![],
+            channels : vec![],
+            schedule,
+        }
+    }
+
+    // Each process sends to the next one, and the last sends back to the first. Channels are
+    // named for who writes to them, like "0->1".
+    pub fn ring(code : &'b Bytecode, count : usize, schedule : Schedule) -> Scheduler<'b> {
+        let mut scheduler = Scheduler::copies(code, count, schedule);
+        for id in 0 .. count {
+            let next = (id + 1) % count;
+            scheduler.connect(id, next, &format!("{}->{}", id, next)).unwrap();
+        }
+
+        scheduler
+    }
+
+    // Processes 0 and 1 talk to each other, then 2 and 3, and so on. With an odd count the last
+    // process has nobody to talk to, so its sends are dropped and its rcvs wait forever.
+    pub fn pairwise(code : &'b Bytecode, count : usize, schedule : Schedule) -> Scheduler<'b> {
+        let mut scheduler = Scheduler::copies(code, count, schedule);
+        for id in 0 .. count {
+            let partner = id ^ 1;
+            if partner < count {
+                scheduler.connect(id, partner, &format!("{}->{}", id, partner)).unwrap();
+            }
+        }
+
+        scheduler
+    }
+
+    // Each process sends to every other process. Every process's inbox is shared by all of its
+    // senders, so they're named like "*->2".
+    pub fn broadcast(code : &'b Bytecode, count : usize, schedule : Schedule) -> Scheduler<'b> {
+        let mut scheduler = Scheduler::copies(code, count, schedule);
+        for id in 0 .. count {
+            for other in (0 .. count).filter(|&other| other != id) {
+                scheduler.connect(id, other, &format!("*->{}", other)).unwrap();
+            }
+        }
+
+        scheduler
+    }
+
+    fn copies(code : &'b Bytecode, count : usize, schedule : Schedule) -> Scheduler<'b> {
+        let mut scheduler = Scheduler::new(schedule);
+        for _ in 0 .. count {
+            scheduler.add_process(code);
+        }
+
+        scheduler
+    }
+
+    // Adds a process running the given program, not connected to anything yet. Returns its ID.
+    pub fn add_process(&mut self, code : &'b Bytecode) -> usize {
+        self.processes.push(Process {
+            machine : Machine::new(code),
+            inbox : None,
+            outputs : vec![],
+            sends : 0,
+            receives : 0,
+        });
+
+        self.processes.len() - 1
+    }
+
+    // Sends everything from's snds into the named channel, and has to's rcvs read from it. The
+    // channel is made the first time it's named, and connecting more senders to it later shares
+    // it between them. Fails without changing anything if to already reads from another channel,
+    // or if the channel already has another reader.
+    pub fn connect(&mut self, from : usize, to : usize, name : &str) -> Result<(), String> {
+        for &id in [from, to].iter() {
+            if id >= self.processes.len() {
+                return Err(format!("there's no process {}", id));
+            }
+        }
+
+        let existing = self.channels.iter().position(|channel| channel.name == name);
+        if let Some(index) = existing {
+            if self.channels[index].reader != to {
+                return Err(format!("channel {} is already read by process {}", name, self.channels[index].reader));
+            }
+        }
+
+        if let Some(inbox) = self.processes[to].inbox {
+            if Some(inbox) != existing {
+                return Err(format!("process {} already reads from channel {}", to, self.channels[inbox].name));
+            }
+        }
+
+        let index = existing.unwrap_or_else(|| {
+            self.channels.push(Channel {
+                name : String::from(name),
+                reader : to,
+                queue : VecDeque::new(),
+            });
+
+            self.channels.len() - 1
+        });
+
+        self.processes[to].inbox = Some(index);
+        if !self.processes[from].outputs.contains(&index) {
+            self.processes[from].outputs.push(index);
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.processes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty()
+    }
+
+    pub fn machine(&self, id : usize) -> &Machine<'b> {
+        &self.processes[id].machine
+    }
+
+    // For setting up registers before running, like day 18's program ID in p.
+    pub fn machine_mut(&mut self, id : usize) -> &mut Machine<'b> {
+        &mut self.processes[id].machine
+    }
+
+    pub fn stats(&self, id : usize) -> Stats {
+        let process = &self.processes[id];
+        Stats {
+            sends : process.sends,
+            receives : process.receives,
+            steps : process.machine.steps(),
+        }
+    }
+
+    // The values waiting in the named channel, oldest first.
+    pub fn channel(&self, name : &str) -> Option<&VecDeque<i64>> {
+        self.channels.iter().find(|channel| channel.name == name).map(|channel| &channel.queue)
+    }
+
+    pub fn channel_names(&self) -> Vec<&str> {
+        self.channels.iter().map(|channel| channel.name.as_str()).collect()
+    }
+
+    pub fn status(&self, id : usize) -> Status {
+        let process = &self.processes[id];
+        if process.machine.is_halted() {
+            Status::Halted
+        } else {
+            match process.machine.waiting_on() {
+                Some(reg) if self.pending(process) == 0 => Status::Blocked(reg),
+                _ => Status::Ready,
+            }
+        }
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        let statuses = (0 .. self.processes.len()).map(|id| self.status(id)).collect::<Vec<Status>>();
+        let num_halted = statuses.iter().filter(|&&status| status == Status::Halted).count();
+
+        if statuses.contains(&Status::Ready) {
+            Outcome::Running
+        } else if num_halted == statuses.len() {
+            Outcome::Halted
+        } else if num_halted == 0 {
+            Outcome::Deadlocked
+        } else {
+            Outcome::Starved
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            outcome : self.outcome(),
+            processes : self.processes.iter().enumerate().map(|(id, process)| {
+                ProcessReport {
+                    id,
+                    status : self.status(id),
+                    ip : process.machine.ip(),
+                    pending : self.pending(process),
+                    registers : process.machine.registers(),
+                }
+            }).collect(),
+        }
+    }
+
+    fn pending(&self, process : &Process) -> usize {
+        process.inbox.map_or(0, |inbox| self.channels[inbox].queue.len())
+    }
+
+    // Gives one process its turn. Returns whether it did anything, which it doesn't if it's
+    // halted or waiting on an empty inbox.
+    fn take_turn(&mut self, id : usize) -> bool {
+        let mut made_progress = false;
+
+        loop {
+            let step = match self.schedule {
+                Schedule::RoundRobin => self.processes[id].machine.step(),
+                Schedule::UntilBlocked => self.processes[id].machine.run(),
+            };
+
+            match step {
+                Step::Ran => {},
+                Step::Sent(value) => {
+                    self.processes[id].sends += 1;
+                    for &output in self.processes[id].outputs.iter() {
+                        self.channels[output].queue.push_back(value);
+                    }
+                },
+                Step::Receiving(_) => {
+                    let inbox = self.processes[id].inbox;
+                    if let Some(value) = inbox.and_then(|inbox| self.channels[inbox].queue.pop_front()) {
+                        self.processes[id].receives += 1;
+                        self.processes[id].machine.receive(value);
+                    } else {
+                        return made_progress;
+                    }
+                },
+                Step::Halted => {
+                    return made_progress;
+                },
+            }
+
+            made_progress = true;
+            if self.schedule == Schedule::RoundRobin {
+                return made_progress;
+            }
+        }
+    }
+
+    // Gives every process a turn, in order, until a whole round goes by where none of them could
+    // do anything. Never returns if some process loops forever without waiting on a rcv.
+    pub fn run(&mut self) -> Outcome {
+        self.run_rounds(u64::MAX)
+    }
+
+    // Like run, but gives up after the given number of rounds, so a long run can be paused and
+    // snapshotted. Returns Running if it stopped early.
+    pub fn run_rounds(&mut self, max_rounds : u64) -> Outcome {
+        let mut made_progress = true;
+        let mut rounds = 0;
+        while made_progress && rounds < max_rounds {
+            made_progress = false;
+            rounds += 1;
+
+            for id in 0 .. self.processes.len() {
+                made_progress = self.take_turn(id) || made_progress;
+            }
+        }
+
+        self.outcome()
+    }
+
+    // Every process's registers and counters, and every value still waiting in a channel.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            processes : self.processes.iter().map(|process| {
+                let mut state = process.machine.process_state();
+                state.sends = process.sends;
+                state.receives = process.receives;
+                state
+            }).collect(),
+            channels : self.channels.iter().map(|channel| {
+                ChannelState {
+                    name : channel.name.clone(),
+                    queue : channel.queue.iter().cloned().collect(),
+                }
+            }).collect(),
+        }
+    }
+
+    // Picks up where a snapshot left off. The scheduler has to have been set up with the same
+    // programs and channels; the schedule can differ. Fails without changing anything if the
+    // snapshot doesn't fit.
+    pub fn restore(&mut self, snapshot : &Snapshot) -> Result<(), String> {
+        if snapshot.processes.len() != self.processes.len() {
+            return Err(format!("snapshot has {} processes, not {}", snapshot.processes.len(), self.processes.len()));
+        }
+
+        let names = snapshot.channels.iter().map(|channel| channel.name.as_str()).collect::<Vec<&str>>();
+        if names != self.channel_names() {
+            return Err(format!("snapshot has channels {}, not {}", names.join(", "), self.channel_names().join(", ")));
+        }
+
+        let mut machines = vec![];
+        for (process, state) in self.processes.iter().zip(snapshot.processes.iter()) {
+            let mut machine = Machine::new(process.machine.code());
+            machine.restore_process(state)?;
+            machines.push(machine);
+        }
+
+        for ((process, machine), state) in self.processes.iter_mut().zip(machines).zip(snapshot.processes.iter()) {
+            process.machine = machine;
+            process.sends = state.sends;
+            process.receives = state.receives;
+        }
+
+        for (channel, state) in self.channels.iter_mut().zip(snapshot.channels.iter()) {
+            channel.queue = state.queue.iter().cloned().collect();
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let description = match *self {
+            Outcome::Halted => "every process halted",
+            Outcome::Deadlocked => "deadlocked, with every process waiting on rcv",
+            Outcome::Starved => "starved, with some processes halted and the rest waiting on rcv",
+            Outcome::Running => "still running",
+        };
+
+        write!(f, "{}", description)
+    }
+}
+
+impl fmt::Display for ProcessReport {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let status = match self.status {
+            Status::Halted => String::from("halted"),
+            Status::Blocked(reg) => format!("blocked on rcv {}", reg),
+            Status::Ready => String::from("ready"),
+        };
+
+        let registers = self.registers.iter().map(|&(r, value)| format!("{}={}", r, value)).collect::<Vec<String>>().join(" ");
+        write!(f, "process {}: {} at ip {}, {} pending; {}", self.id, status, self.ip, self.pending, registers)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.outcome)?;
+        for process in self.processes.iter() {
+            write!(f, "\n{}", process)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aocisa::Program;
+
+    // Like day 18, each process starts with its ID in p.
+    fn start(mut scheduler : Scheduler<'_>) -> Scheduler<'_> {
+        for id in 0 .. scheduler.len() {
+            scheduler.machine_mut(id).set('p', id as i64);
+        }
+
+        scheduler
+    }
+
+    #[test]
+    fn ring() {
+        // Process 0 starts a token that gets incremented on its way around.
+        let input =
+r"jgz p 2
+snd 1
+rcv a
+add a 1
+snd a";
+        let code = Bytecode::compile(&Program::load(input).unwrap());
+        for &schedule in [Schedule::RoundRobin, Schedule::UntilBlocked].iter() {
+            let mut scheduler = start(Scheduler::ring(&code, 3, schedule));
+            assert_eq!(scheduler.run(), Outcome::Halted);
+            assert_eq!(scheduler.machine(0).get('a'), 4);
+            assert_eq!(scheduler.machine(1).get('a'), 2);
+            assert_eq!(scheduler.machine(2).get('a'), 3);
+            assert_eq!(scheduler.stats(0), Stats { sends : 2, receives : 1, steps : 5 });
+            assert_eq!(scheduler.stats(1), Stats { sends : 1, receives : 1, steps : 4 });
+            assert_eq!(scheduler.channel_names(), vec!["0->1", "1->2", "2->0"]);
+            assert_eq!(scheduler.channel("0->1"), Some(&VecDeque::from(vec![4])));
+            assert_eq!(scheduler.channel("1->0"), None);
+        }
+    }
+
+    #[test]
+    fn pairwise() {
+        let input =
+r"snd 1
+snd 2
+snd p
+rcv a
+rcv b
+rcv c
+rcv d";
+        let code = Bytecode::compile(&Program::load(input).unwrap());
+        for &schedule in [Schedule::RoundRobin, Schedule::UntilBlocked].iter() {
+            let mut scheduler = start(Scheduler::pairwise(&code, 5, schedule));
+            assert_eq!(scheduler.run(), Outcome::Deadlocked);
+            assert_eq!(scheduler.machine(2).get('c'), 3);
+            assert_eq!(scheduler.machine(3).get('c'), 2);
+            assert_eq!(scheduler.stats(3), Stats { sends : 3, receives : 3, steps : 6 });
+            assert_eq!(scheduler.stats(4), Stats { sends : 3, receives : 0, steps : 3 });
+            assert_eq!(scheduler.status(4), Status::Blocked('a'));
+            assert_eq!(scheduler.channel_names(), vec!["0->1", "1->0", "2->3", "3->2"]);
+        }
+    }
+
+    #[test]
+    fn broadcast() {
+        let input =
+r"snd p
+rcv a
+rcv b
+add a b";
+        let code = Bytecode::compile(&Program::load(input).unwrap());
+        let mut scheduler = start(Scheduler::broadcast(&code, 4, Schedule::UntilBlocked));
+        assert_eq!(scheduler.run(), Outcome::Halted);
+        assert_eq!(scheduler.stats(2), Stats { sends : 1, receives : 2, steps : 4 });
+        assert_eq!(scheduler.channel("*->2").map(|queue| queue.len()), Some(1));
+
+        let mut scheduler = start(Scheduler::broadcast(&code, 3, Schedule::RoundRobin));
+        assert_eq!(scheduler.run(), Outcome::Halted);
+        assert_eq!(scheduler.machine(0).get('a'), 1 + 2);
+        assert_eq!(scheduler.machine(1).get('a'), 2);
+        assert_eq!(scheduler.machine(2).get('a'), 1);
+    }
+
+    #[test]
+    fn connect() {
+        // A producer feeds a doubler, which feeds a consumer that adds up what it gets.
+        let producer = Bytecode::compile(&Program::load("snd 1\nsnd 2\nsnd 3").unwrap());
+        let doubler = Bytecode::compile(&Program::load("rcv a\nmul a 2\nsnd a\njgz 1 -3").unwrap());
+        let consumer = Bytecode::compile(&Program::load("rcv a\nadd b a\njgz 1 -2").unwrap());
+
+        let mut scheduler = Scheduler::new(Schedule::UntilBlocked);
+        assert_eq!(scheduler.add_process(&producer), 0);
+        assert_eq!(scheduler.add_process(&doubler), 1);
+        assert_eq!(scheduler.add_process(&consumer), 2);
+        scheduler.connect(0, 1, "raw").unwrap();
+        scheduler.connect(1, 2, "doubled").unwrap();
+        scheduler.connect(0, 2, "doubled").unwrap();
+
+        assert_eq!(scheduler.run(), Outcome::Starved);
+        assert_eq!(scheduler.machine(2).get('b'), 2 * (1 + 2 + 3) + (1 + 2 + 3));
+        assert_eq!(scheduler.stats(2).receives, 6);
+        assert_eq!(scheduler.channel_names(), vec!["raw", "doubled"]);
+
+        assert_eq!(scheduler.connect(0, 3, "raw"), Err(String::from("there's no process 3")));
+        assert_eq!(scheduler.connect(2, 0, "raw"), Err(String::from("channel raw is already read by process 1")));
+        assert_eq!(scheduler.connect(0, 1, "other"), Err(String::from("process 1 already reads from channel raw")));
+        assert_eq!(scheduler.channel_names(), vec!["raw", "doubled"]);
+    }
+
+    #[test]
+    fn starved() {
+        // Process 0 sends one value and halts, but process 1 wants two.
+        let input =
+r"jgz p 3
+snd 1
+jgz 1 3
+rcv a
+rcv b";
+        let code = Bytecode::compile(&Program::load(input).unwrap());
+        let mut scheduler = start(Scheduler::ring(&code, 2, Schedule::UntilBlocked));
+        assert_eq!(scheduler.outcome(), Outcome::Running);
+        assert_eq!(scheduler.run(), Outcome::Starved);
+        assert_eq!(scheduler.status(0), Status::Halted);
+        assert_eq!(scheduler.status(1), Status::Blocked('b'));
+        assert_eq!(scheduler.report().processes[1], ProcessReport {
+            id : 1,
+            status : Status::Blocked('b'),
+            ip : 4,
+            pending : 0,
+            registers : vec![('p', 1), ('a', 1), ('b', 0)],
+        });
+        assert_eq!(scheduler.report().to_string(),
+r"starved, with some processes halted and the rest waiting on rcv
+process 0: halted at ip 5, 0 pending; p=0 a=0 b=0
+process 1: blocked on rcv b at ip 4, 0 pending; p=1 a=1 b=0");
+    }
+}
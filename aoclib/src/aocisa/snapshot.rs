@@ -0,0 +1,323 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use parse_error::{ParseError, parse_field};
+
+// Snapshots are plain text so they can be read, diffed, and pasted into a bug report. After the
+// header, each process gets a block of lines starting with its program's length, and each channel
+// gets one line with its name and then its queued values, oldest first:
+//
+//   aocisa snapshot
+//   process 0
+//   instructions 41
+//   ip 17
+//   steps 1234 instructions
+//   sends 3
+//   receives 2
+//   registers i=0 a=-4 p=0
+//   channel 1->0 5 6 7
+const HEADER : &str = "aocisa snapshot";
+
+// What steps counts, written after it so that a count of anything else, like macro-instructions
+// in an optimized run, can't be resumed as if it were one.
+const STEP_UNIT : &str = "instructions";
+
+// Everything about one process that it needs to carry on where it left off. sends and receives
+// are only counted by the scheduler.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct ProcessState {
+    pub instructions : usize,
+    pub ip : usize,
+    pub steps : u64,
+    pub sends : u64,
+    pub receives : u64,
+    pub registers : Vec<(char, i64)>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChannelState {
+    pub name : String,
+    pub queue : Vec<i64>,
+}
+
+// A paused execution: a single machine, or every process and channel in a scheduler. Restoring
+// one needs the same programs it was taken from, which are checked only as far as their lengths.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct Snapshot {
+    pub processes : Vec<ProcessState>,
+    pub channels : Vec<ChannelState>,
+}
+
+fn parse_register(line : &str, field : &str) -> Result<(char, i64), ParseError> {
+    let expected = "a register and value like a=5";
+    let mut parts = field.splitn(2, '=');
+    let name = parts.next().unwrap();
+    let value = parts.next().ok_or_else(|| ParseError::in_line(line, field, expected))?;
+
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(register), None) => Ok((register, parse_field(line, value, "a number")?)),
+        _ => Err(ParseError::in_line(line, name, "a register")),
+    }
+}
+
+impl ProcessState {
+    pub fn check_instructions(&self, instructions : usize) -> Result<(), String> {
+        if self.instructions != instructions {
+            Err(format!("snapshot is of a program with {} instructions, not {}", self.instructions, instructions))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Snapshot {
+    // The only process in a snapshot of a single machine, after checking that it's from a program
+    // with the given number of instructions.
+    pub fn single(&self, instructions : usize) -> Result<&ProcessState, String> {
+        if self.processes.len() != 1 {
+            return Err(format!("snapshot has {} processes, not a single machine", self.processes.len()));
+        }
+
+        self.processes[0].check_instructions(instructions)?;
+        Ok(&self.processes[0])
+    }
+
+    pub fn channel(&self, name : &str) -> Option<&ChannelState> {
+        self.channels.iter().find(|channel| channel.name == name)
+    }
+
+    pub fn parse(input : &str) -> Result<Snapshot, ParseError> {
+        let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line)).filter(|&(_, line)| !line.trim().is_empty());
+        match lines.next() {
+            Some((_, line)) if line.trim() == HEADER => {},
+            Some((line_number, line)) => return Err(ParseError::in_line(line, line, HEADER).on_line(line_number)),
+            None => return Err(ParseError::new(1, 1, HEADER, "")),
+        }
+
+        let mut snapshot = Snapshot::default();
+        for (line_number, line) in lines {
+            snapshot.parse_line(line).map_err(|e| e.on_line(line_number))?;
+        }
+
+        Ok(snapshot)
+    }
+
+    fn parse_line(&mut self, line : &str) -> Result<(), ParseError> {
+        let mut fields = line.split_whitespace();
+        let key = fields.next().unwrap();
+        let end = &line[line.len() ..];
+
+        match key {
+            "process" => {
+                let field = fields.next().unwrap_or(end);
+                let id = parse_field::<usize>(line, field, "a process number")?;
+                if id != self.processes.len() {
+                    return Err(ParseError::in_line(line, field, &format!("process {}", self.processes.len())));
+                }
+
+                self.processes.push(ProcessState::default());
+            },
+            "channel" => {
+                let name = fields.next().ok_or_else(|| ParseError::in_line(line, end, "a channel name"))?;
+                let queue = fields.map(|field| parse_field(line, field, "a number")).collect::<Result<Vec<i64>, ParseError>>()?;
+                self.channels.push(ChannelState {
+                    name : String::from(name),
+                    queue,
+                });
+            },
+            "instructions" | "ip" | "steps" | "sends" | "receives" | "registers" => {
+                let process = self.processes.last_mut().ok_or_else(|| {
+                    ParseError::in_line(line, key, "a process line first")
+                })?;
+
+                if key == "registers" {
+                    process.registers = fields.map(|field| parse_register(line, field)).collect::<Result<Vec<(char, i64)>, ParseError>>()?;
+                } else {
+                    let field = fields.next().unwrap_or(end);
+                    let value = parse_field::<u64>(line, field, "a number")?;
+                    if key == "steps" {
+                        let unit = fields.next().unwrap_or(end);
+                        if unit != STEP_UNIT {
+                            return Err(ParseError::in_line(line, unit, STEP_UNIT));
+                        }
+                    }
+
+                    match key {
+                        "instructions" => process.instructions = value as usize,
+                        "ip" => process.ip = value as usize,
+                        "steps" => process.steps = value,
+                        "sends" => process.sends = value,
+                        _ => process.receives = value,
+                    }
+                }
+            },
+            _ => return Err(ParseError::in_line(line, key, "process, channel, instructions, ip, steps, sends, receives, or registers")),
+        }
+
+        Ok(())
+    }
+
+    pub fn load(path : &Path) -> Result<Snapshot, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Snapshot::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path : &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+
+        for (id, process) in self.processes.iter().enumerate() {
+            writeln!(f, "process {}", id)?;
+            writeln!(f, "instructions {}", process.instructions)?;
+            writeln!(f, "ip {}", process.ip)?;
+            writeln!(f, "steps {} {}", process.steps, STEP_UNIT)?;
+            writeln!(f, "sends {}", process.sends)?;
+            writeln!(f, "receives {}", process.receives)?;
+
+            let registers = process.registers.iter().map(|&(r, value)| format!(" {}={}", r, value)).collect::<String>();
+            writeln!(f, "registers{}", registers)?;
+        }
+
+        for channel in self.channels.iter() {
+            let queue = channel.queue.iter().map(|value| format!(" {}", value)).collect::<String>();
+            writeln!(f, "channel {}{}", channel.name, queue)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aocisa::Program;
+    use aocisa::bytecode::{Bytecode, Machine, Step};
+    use aocisa::scheduler::{Outcome, Schedule, Scheduler};
+
+    // The processes pass a counter back and forth, and whoever takes it past 20 stops, leaving the
+    // other one waiting.
+    const VOLLEY : &str =
+r"jgz p 3
+set a 1
+snd a
+rcv a
+add a 1
+set b a
+add b -20
+jgz b 3
+snd a
+jgz a -6";
+
+    fn start(code : &Bytecode) -> Scheduler<'_> {
+        let mut scheduler = Scheduler::ring(code, 2, Schedule::RoundRobin);
+        scheduler.machine_mut(1).set('p', 1);
+        scheduler
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = Snapshot {
+            processes : vec![
+                ProcessState { instructions : 10, ip : 3, steps : 7, sends : 2, receives : 1, registers : vec![('p', 0), ('a', -5)] },
+                ProcessState { instructions : 4, ip : 12, steps : 9, sends : 0, receives : 0, registers : vec![] },
+            ],
+            channels : vec![
+                ChannelState { name : String::from("1->0"), queue : vec![4, -1] },
+                ChannelState { name : String::from("0->1"), queue : vec![] },
+            ],
+        };
+
+        let text = snapshot.to_string();
+        assert_eq!(text,
+r"aocisa snapshot
+process 0
+instructions 10
+ip 3
+steps 7 instructions
+sends 2
+receives 1
+registers p=0 a=-5
+process 1
+instructions 4
+ip 12
+steps 9 instructions
+sends 0
+receives 0
+registers
+channel 1->0 4 -1
+channel 0->1
+");
+        assert_eq!(Snapshot::parse(&text), Ok(snapshot));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Snapshot::parse("snapshot"), Err(ParseError::new(1, 1, HEADER, "snapshot")));
+        assert_eq!(Snapshot::parse("aocisa snapshot\nip 3"), Err(ParseError::new(2, 1, "a process line first", "ip")));
+        assert_eq!(Snapshot::parse("aocisa snapshot\n\nprocess 1"), Err(ParseError::new(3, 9, "process 0", "1")));
+        assert_eq!(Snapshot::parse("aocisa snapshot\nprocess 0\nregisters a=1 bc=2"), Err(ParseError::new(3, 15, "a register", "bc")));
+        assert_eq!(Snapshot::parse("aocisa snapshot\nchannel 0->1 4 x"), Err(ParseError::new(2, 16, "a number", "x")));
+        assert_eq!(Snapshot::parse("aocisa snapshot\nprocess 0\nsteps 12"), Err(ParseError::new(3, 9, "instructions", "")));
+        assert_eq!(Snapshot::parse("aocisa snapshot\nprocess 0\nsteps 12 ops"), Err(ParseError::new(3, 10, "instructions", "ops")));
+    }
+
+    #[test]
+    fn resume_scheduler() {
+        let code = Bytecode::compile(&Program::load(VOLLEY).unwrap());
+        let mut expected = start(&code);
+        assert_eq!(expected.run(), Outcome::Starved);
+
+        let mut paused = start(&code);
+        assert_eq!(paused.run_rounds(30), Outcome::Running);
+        let snapshot = Snapshot::parse(&paused.snapshot().to_string()).unwrap();
+        assert!(snapshot.channels.iter().any(|channel| !channel.queue.is_empty()));
+
+        let mut resumed = start(&code);
+        resumed.restore(&snapshot).unwrap();
+        assert_eq!(resumed.run(), Outcome::Starved);
+        assert_eq!(resumed.report(), expected.report());
+        for id in 0 .. 2 {
+            assert_eq!(resumed.stats(id), expected.stats(id));
+        }
+
+        let other = Bytecode::compile(&Program::load("snd 1").unwrap());
+        assert!(start(&other).restore(&snapshot).is_err());
+        assert!(Scheduler::ring(&code, 3, Schedule::RoundRobin).restore(&snapshot).is_err());
+
+        // Wired the same way but with the channels named differently.
+        let mut renamed = Scheduler::new(Schedule::RoundRobin);
+        renamed.add_process(&code);
+        renamed.add_process(&code);
+        renamed.connect(0, 1, "ping").unwrap();
+        renamed.connect(1, 0, "pong").unwrap();
+        assert!(renamed.restore(&snapshot).is_err());
+    }
+
+    #[test]
+    fn resume_machine() {
+        let code = Bytecode::compile(&Program::load("set a 10\nadd b a\nadd a -1\njgz a -2").unwrap());
+        let mut machine = Machine::new(&code);
+        for _ in 0 .. 9 {
+            machine.step();
+        }
+
+        let snapshot = machine.snapshot();
+        let mut resumed = Machine::new(&code);
+        resumed.restore(&snapshot).unwrap();
+        assert_eq!(resumed.ip(), machine.ip());
+        assert_eq!(resumed.run(), Step::Halted);
+        assert_eq!(resumed.get('b'), 55);
+        assert_eq!(resumed.steps(), 1 + 3 * 10);
+
+        let mut snapshot = snapshot;
+        snapshot.processes[0].registers.push(('z', 1));
+        assert_eq!(resumed.restore(&snapshot), Err(String::from("register z isn't in the program")));
+    }
+}
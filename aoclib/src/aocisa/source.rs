@@ -0,0 +1,350 @@
+use std::fmt;
+
+use aocisa::{Instruction, Program, RegisterOrValue};
+use aocisa::dialect::{Dialect, OPCODES, Plain};
+use parse_error::{ParseError, parse_field};
+
+// Everything after a # on a line is a comment.
+const COMMENT : char = '#';
+
+// One line of the text exactly as it was written. Instruction lines also remember where in the
+// line the instruction is, so it can be swapped out without disturbing indentation or a trailing
+// comment.
+struct Line {
+    text : String,
+    code : Option<(usize, usize)>,
+}
+
+// A program along with the text it came from, so it can be written back out unchanged and each
+// instruction can be traced back to its line. Blank lines and comments are kept but don't count
+// as instructions.
+pub struct Source {
+    lines : Vec<Line>,
+    line_of_ip : Vec<usize>,
+    trailing_newline : bool,
+    program : Program,
+}
+
+fn parse_register(line : &str, field : &str) -> Result<char, ParseError> {
+    let mut chars = field.chars();
+    match (chars.next(), chars.next()) {
+        (Some(r), None) if r.is_ascii_lowercase() => Ok(r),
+        _ => Err(ParseError::in_line(line, field, "a register")),
+    }
+}
+
+fn parse_operand(line : &str, field : &str) -> Result<RegisterOrValue, ParseError> {
+    if field.starts_with(|c : char| c == '-' || c.is_ascii_digit()) {
+        parse_field(line, field, "a number").map(RegisterOrValue::Val)
+    } else {
+        parse_register(line, field).map(RegisterOrValue::Reg).map_err(|_| {
+            ParseError::in_line(line, field, "a register or a number")
+        })
+    }
+}
+
+fn check_arity(line : &str, code : &str, fields : &[&str], arity : usize) -> Result<(), ParseError> {
+    let end = &code[code.len() ..];
+    if fields.len() <= arity {
+        Err(ParseError::in_line(line, end, &format!("{} operands for {}", arity, fields[0])))
+    } else if fields.len() > arity + 1 {
+        Err(ParseError::in_line(line, fields[arity + 1], "end of line"))
+    } else {
+        Ok(())
+    }
+}
+
+// Parses the instruction in code, which has to be a slice of line so that errors point at the
+// right column. Besides the shared opcodes, it takes the ones in extra, which a dialect lists
+// along with what kind of operands they have.
+fn parse_instruction(line : &str, code : &str, extra : &[(&'static str, &'static str)]) -> Result<Instruction, ParseError> {
+    let fields = code.split_whitespace().collect::<Vec<&str>>();
+    let name = fields[0];
+    if !OPCODES.contains(&name) {
+        let &(name, kinds) = extra.iter().find(|&&(extra_name, _)| extra_name == name).ok_or_else(|| {
+            ParseError::in_line(line, name, "an instruction")
+        })?;
+
+        check_arity(line, code, &fields, kinds.len())?;
+        let operands = fields[1 ..].iter().zip(kinds.chars()).map(|(field, kind)| {
+            match kind {
+                'r' => parse_register(line, field).map(RegisterOrValue::Reg),
+                _ => parse_operand(line, field),
+            }
+        }).collect::<Result<Vec<RegisterOrValue>, ParseError>>()?;
+
+        return Ok(Instruction::Custom(name, operands));
+    }
+
+    let arity = match name {
+        "snd" | "rcv" => 1,
+        _ => 2,
+    };
+
+    check_arity(line, code, &fields, arity)?;
+    Ok(match name {
+        "snd" => Instruction::Snd(parse_operand(line, fields[1])?),
+        "rcv" => Instruction::Rcv(parse_register(line, fields[1])?),
+        "set" => Instruction::Set(parse_register(line, fields[1])?, parse_operand(line, fields[2])?),
+        "add" => Instruction::Add(parse_register(line, fields[1])?, parse_operand(line, fields[2])?),
+        "sub" => Instruction::Sub(parse_register(line, fields[1])?, parse_operand(line, fields[2])?),
+        "mul" => Instruction::Mul(parse_register(line, fields[1])?, parse_operand(line, fields[2])?),
+        "mod" => Instruction::Mod(parse_register(line, fields[1])?, parse_operand(line, fields[2])?),
+        "jgz" => Instruction::Jgz(parse_operand(line, fields[1])?, parse_operand(line, fields[2])?),
+        _ => Instruction::Jnz(parse_operand(line, fields[1])?, parse_operand(line, fields[2])?),
+    })
+}
+
+// Where the instruction on a line of text is, if there is one, leaving out indentation and any
+// comment.
+fn code_span(text : &str) -> Option<(usize, usize)> {
+    let before_comment = &text[.. text.find(COMMENT).unwrap_or(text.len())];
+    let code = before_comment.trim();
+    if code.is_empty() {
+        None
+    } else {
+        let start = before_comment.len() - before_comment.trim_start().len();
+        Some((start, start + code.len()))
+    }
+}
+
+// The instruction on a line of text, or None for a blank line or one with only a comment.
+fn parse_line(text : &str, extra : &[(&'static str, &'static str)]) -> Result<Option<Instruction>, ParseError> {
+    match code_span(text) {
+        Some((start, end)) => parse_instruction(text, &text[start .. end], extra).map(Some),
+        None => Ok(None),
+    }
+}
+
+impl Source {
+    // Takes only the opcodes that every dialect shares.
+    pub fn parse(input : &str) -> Result<Source, ParseError> {
+        Source::parse_for(input, &Plain)
+    }
+
+    // Also takes the opcodes that only the given dialect has.
+    pub fn parse_for<D : Dialect>(input : &str, dialect : &D) -> Result<Source, ParseError> {
+        let mut instructions = vec![];
+        for (i, text) in input.lines().enumerate() {
+            if let Some(inst) = parse_line(text, dialect.own_opcodes()).map_err(|e| e.on_line(i + 1))? {
+                instructions.push(inst);
+            }
+        }
+
+        Ok(Source::with_text(input, Program {
+            instructions,
+        }))
+    }
+
+    // Pairs a program with text that has one instruction line for each of its instructions,
+    // without parsing the text again.
+    fn with_text(text : &str, program : Program) -> Source {
+        let lines = text.lines().map(|text| {
+            Line {
+                text : String::from(text),
+                code : code_span(text),
+            }
+        }).collect::<Vec<Line>>();
+
+        let line_of_ip = lines.iter().enumerate().filter(|&(_, line)| line.code.is_some()).map(|(i, _)| i + 1).collect();
+
+        Source {
+            lines,
+            line_of_ip,
+            trailing_newline : text.ends_with('\n'),
+            program,
+        }
+    }
+
+    // Writes out a program with no comments, one instruction per line.
+    pub fn from_program(program : Program) -> Source {
+        let text = program.instructions.iter().map(|inst| format!("{}\n", inst)).collect::<String>();
+        Source::with_text(&text, program)
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    // Lets go of the text.
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    // The 1-based line that the instruction at ip came from.
+    pub fn line_of(&self, ip : usize) -> Option<usize> {
+        self.line_of_ip.get(ip).cloned()
+    }
+
+    // The instruction on a 1-based line, if there is one.
+    pub fn ip_at(&self, line : usize) -> Option<usize> {
+        self.line_of_ip.iter().position(|&l| l == line)
+    }
+
+    // Puts a transformed program in place of this one, keeping every comment and blank line.
+    // Each instruction is written over the one at the same instruction pointer. If there are
+    // fewer instructions than before, the lines left over lose their instructions but keep their
+    // comments, and if there are more, the extra ones go after the last instruction, indented the
+    // same way.
+    pub fn replace_program(&self, program : Program) -> Source {
+        let last_code = self.lines.iter().rposition(|line| line.code.is_some());
+        let old_len = self.program.instructions.len();
+        let extra = program.instructions.iter().skip(old_len);
+
+        let mut ip = 0;
+        let mut lines = vec![];
+        for (i, line) in self.lines.iter().enumerate() {
+            match line.code {
+                Some((start, end)) => {
+                    let indent = &line.text[.. start];
+                    if let Some(inst) = program.instructions.get(ip) {
+                        lines.push(format!("{}{}{}", indent, inst, &line.text[end ..]));
+                    } else {
+                        let comment = line.text[end ..].trim_start();
+                        if !comment.is_empty() {
+                            lines.push(format!("{}{}", indent, comment));
+                        }
+                    }
+
+                    ip += 1;
+                    if Some(i) == last_code {
+                        lines.extend(extra.clone().map(|inst| format!("{}{}", indent, inst)));
+                    }
+                },
+                None => lines.push(line.text.clone()),
+            }
+        }
+
+        if last_code.is_none() {
+            lines.extend(extra.map(|inst| inst.to_string()));
+        }
+
+        let mut text = lines.join("\n");
+        if self.trailing_newline || (last_code.is_none() && !text.is_empty()) {
+            text.push('\n');
+        }
+
+        Source::with_text(&text, program)
+    }
+
+    // Adds a comment line above each of the given instructions, indented the same way, and keeps
+    // everything else as it was. Notes are pairs of an instruction pointer and the comment.
+    pub fn annotate(&self, notes : &[(usize, String)]) -> Source {
+        let mut ip = 0;
+        let mut lines = vec![];
+        for line in self.lines.iter() {
+            if let Some((start, _)) = line.code {
+                for (_, note) in notes.iter().filter(|&&(at, _)| at == ip) {
+                    lines.push(format!("{}{} {}", &line.text[.. start], COMMENT, note));
+                }
+
+                ip += 1;
+            }
+
+            lines.push(line.text.clone());
+        }
+
+        let mut text = lines.join("\n");
+        if self.trailing_newline {
+            text.push('\n');
+        }
+
+        Source::with_text(&text, self.program.clone())
+    }
+}
+
+// The text exactly as it was parsed.
+impl fmt::Display for Source {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", line.text)?;
+        }
+
+        if self.trailing_newline {
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ANNOTATED : &str =
+r"# counts down from 3
+set a 3
+
+	# the loop
+  sub a 1   # step
+jgz a -1
+snd a
+";
+
+    #[test]
+    fn round_trip() {
+        let source = Source::parse(ANNOTATED).unwrap();
+        assert_eq!(source.to_string(), ANNOTATED);
+        assert_eq!(source.program().instructions.len(), 4);
+        assert_eq!(source.line_of(1), Some(5));
+        assert_eq!(source.line_of(4), None);
+        assert_eq!(source.ip_at(6), Some(2));
+        assert_eq!(source.ip_at(4), None);
+        assert_eq!(source.program().to_string(), Program::load("set a 3\nsub a 1\njgz a -1\nsnd a").unwrap().to_string());
+
+        let unterminated = "snd 1\n\nrcv a";
+        assert_eq!(Source::parse(unterminated).unwrap().to_string(), unterminated);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Source::parse("set a 1\nfoo a").err(), Some(ParseError::new(2, 1, "an instruction", "foo")));
+        assert_eq!(Source::parse("  add ab 1").err(), Some(ParseError::new(1, 7, "a register", "ab")));
+        assert_eq!(Source::parse("jgz a 1x # far").err(), Some(ParseError::new(1, 7, "a number", "1x")));
+        assert_eq!(Source::parse("mul a").err(), Some(ParseError::new(1, 6, "2 operands for mul", "")));
+        assert_eq!(Source::parse("rcv a b").err(), Some(ParseError::new(1, 7, "end of line", "b")));
+        assert_eq!(Source::parse("snd A").err(), Some(ParseError::new(1, 5, "a register or a number", "A")));
+    }
+
+    #[test]
+    fn rewrite() {
+        let source = Source::parse(ANNOTATED).unwrap();
+        let mut program = Program::load("set a 3\nsub a 1\njgz a -1\nsnd a").unwrap();
+        program.instructions[1] = Instruction::Add('a', RegisterOrValue::Val(-1));
+
+        let rewritten = source.replace_program(program);
+        assert_eq!(rewritten.to_string(), ANNOTATED.replace("sub a 1", "add a -1"));
+        assert_eq!(rewritten.line_of(1), Some(5));
+
+        let plain = Source::from_program(Program::load("set a 3\njgz a -1").unwrap());
+        assert_eq!(plain.to_string(), "set a 3\njgz a -1\n");
+        assert_eq!(plain.line_of(1), Some(2));
+    }
+
+    #[test]
+    fn rewrite_resized() {
+        let source = Source::parse(ANNOTATED).unwrap();
+        let shorter = source.replace_program(Program::load("set a 0").unwrap());
+        assert_eq!(shorter.to_string(), "# counts down from 3\nset a 0\n\n\t# the loop\n  # step\n");
+        assert_eq!(shorter.line_of(1), None);
+
+        let longer = Source::parse("# setup\n  set a 1 # one\n# end").unwrap().replace_program(Program::load("set a 1\nset b 2\nsnd b").unwrap());
+        assert_eq!(longer.to_string(), "# setup\n  set a 1 # one\n  set b 2\n  snd b\n# end");
+        assert_eq!(longer.line_of(2), Some(4));
+
+        let empty = Source::parse("# nothing yet\n").unwrap().replace_program(Program::load("snd 1").unwrap());
+        assert_eq!(empty.to_string(), "# nothing yet\nsnd 1\n");
+    }
+
+    #[test]
+    fn lines() {
+        assert!(parse_line("  # just a comment", &[]).unwrap().is_none());
+        assert_eq!(parse_line("snd 1 # play", &[]).unwrap().map(|inst| inst.to_string()), Some(String::from("snd 1")));
+        assert_eq!(parse_line("blah", &[]).err(), Some(ParseError::new(1, 1, "an instruction", "blah")));
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use aocisa::{Instruction, Program, RegisterOrValue};
+use aocisa::cfg::Block;
+use aocisa::decompile::Test;
+
+// Something a value can be built out of that isn't a number.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Atom {
+    // What the register held before the program started.
+    Reg(char),
+    // The value taken by the numbered rcv, counting from 0.
+    Received(usize),
+    // A remainder that can't be worked out yet. There's no way to simplify these in general, so
+    // they're kept whole.
+    Mod(Box<Value>, Box<Value>),
+}
+
+// A register's value as a polynomial over atoms. Each term is a product of atoms, with repeats
+// for powers, and its coefficient. Terms are kept in a canonical form with no zero coefficients,
+// so two values are equal exactly when they're the same polynomial.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct Value {
+    terms : BTreeMap<Vec<Atom>, i64>,
+}
+
+// A jump whose outcome depends on something unknown, or that the caller asked about.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Branch {
+    pub ip : usize,
+    // The jump is taken when the condition passes this test.
+    pub test : Test,
+    pub condition : Value,
+    pub offset : Value,
+}
+
+// Why a symbolic run stopped.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Stop {
+    // The instruction pointer left the program.
+    Halted,
+    // At a jump that can't be followed without knowing more.
+    Branch(Branch),
+    // Took every step that was asked for.
+    Limit,
+    // At an opcode that only some dialect has, so there's no telling what it does.
+    Custom(usize),
+}
+
+// What a straight-line stretch of the program does, in terms of the registers' values going in.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Summary {
+    // Every register the block leaves with something other than what it started with.
+    pub updates : Vec<(char, Value)>,
+    pub sends : Vec<Value>,
+    // The jump that ends the block, if it ends with one.
+    pub branch : Option<Branch>,
+}
+
+// Runs a program with registers holding expressions of their starting values instead of
+// numbers. Jumps are followed as long as their condition and offset work out to constants.
+pub struct Symbolic<'p> {
+    program : &'p Program,
+    registers : BTreeMap<char, Value>,
+    ip : usize,
+    steps : u64,
+    sends : Vec<Value>,
+    receives : usize,
+}
+
+impl Value {
+    pub fn constant(value : i64) -> Value {
+        Value::from_term(vec![], value)
+    }
+
+    pub fn register(register : char) -> Value {
+        Value::from_term(vec![Atom::Reg(register)], 1)
+    }
+
+    fn from_term(atoms : Vec<Atom>, coefficient : i64) -> Value {
+        let mut value = Value::default();
+        value.add_term(atoms, coefficient);
+        value
+    }
+
+    fn add_term(&mut self, atoms : Vec<Atom>, coefficient : i64) {
+        let sum = self.terms.get(&atoms).cloned().unwrap_or(0).wrapping_add(coefficient);
+        if sum == 0 {
+            self.terms.remove(&atoms);
+        } else {
+            self.terms.insert(atoms, sum);
+        }
+    }
+
+    // The number this is, if it doesn't depend on anything.
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.terms.iter().next() {
+            None => Some(0),
+            Some((atoms, &coefficient)) if atoms.is_empty() && self.terms.len() == 1 => Some(coefficient),
+            _ => None,
+        }
+    }
+
+    pub fn add(&self, other : &Value) -> Value {
+        let mut sum = self.clone();
+        for (atoms, &coefficient) in other.terms.iter() {
+            sum.add_term(atoms.clone(), coefficient);
+        }
+        sum
+    }
+
+    pub fn sub(&self, other : &Value) -> Value {
+        self.add(&other.mul(&Value::constant(-1)))
+    }
+
+    pub fn mul(&self, other : &Value) -> Value {
+        let mut product = Value::default();
+        for (left, &a) in self.terms.iter() {
+            for (right, &b) in other.terms.iter() {
+                let mut atoms = left.iter().chain(right.iter()).cloned().collect::<Vec<Atom>>();
+                atoms.sort();
+                product.add_term(atoms, a.wrapping_mul(b));
+            }
+        }
+        product
+    }
+
+    // Same as the interpreter's %, where the sign follows the left side.
+    pub fn rem(&self, other : &Value) -> Value {
+        match (self.as_constant(), other.as_constant()) {
+            (Some(0), _) => Value::constant(0),
+            (_, Some(1)) | (_, Some(-1)) => Value::constant(0),
+            (Some(a), Some(b)) if b != 0 => Value::constant(a.wrapping_rem(b)),
+            _ => Value::from_term(vec![Atom::Mod(Box::new(self.clone()), Box::new(other.clone()))], 1),
+        }
+    }
+
+    // Whether a jump on this value passes the test, if that can be known.
+    pub fn passes(&self, test : Test) -> Option<bool> {
+        self.as_constant().map(|value| {
+            match test {
+                Test::NonZero => value != 0,
+                Test::Zero => value == 0,
+                Test::Positive => value > 0,
+                Test::NotPositive => value <= 0,
+            }
+        })
+    }
+
+    // Parenthesized if it has more than one term, for showing inside something else.
+    fn grouped(&self) -> String {
+        if self.terms.len() > 1 {
+            format!("({})", self)
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+impl Branch {
+    // Where the jump goes when taken, if the offset is a constant that stays in the program.
+    pub fn target(&self) -> Option<usize> {
+        self.offset.as_constant().and_then(|offset| {
+            let target = (self.ip as i64).wrapping_add(offset);
+            if target >= 0 { Some(target as usize) } else { None }
+        })
+    }
+}
+
+impl<'p> Symbolic<'p> {
+    pub fn new(program : &'p Program) -> Symbolic<'p> {
+        Symbolic::starting_at(program, 0)
+    }
+
+    fn starting_at(program : &'p Program, ip : usize) -> Symbolic<'p> {
+        Symbolic {
+            program,
+            registers : BTreeMap::new(),
+            ip,
+            steps : 0,
+            sends : vec![],
+            receives : 0,
+        }
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.ip >= self.program.instructions.len()
+    }
+
+    pub fn get(&self, register : char) -> Value {
+        self.registers.get(&register).cloned().unwrap_or_else(|| Value::register(register))
+    }
+
+    // For pinning down a register before running, like day 23 part b's a = 1.
+    pub fn set(&mut self, register : char, value : Value) {
+        self.registers.insert(register, value);
+    }
+
+    pub fn sends(&self) -> &[Value] {
+        &self.sends
+    }
+
+    // Every register that no longer holds its starting value, in order.
+    pub fn updates(&self) -> Vec<(char, Value)> {
+        self.registers.iter().filter(|&(&r, value)| *value != Value::register(r)).map(|(&r, value)| (r, value.clone())).collect()
+    }
+
+    fn value(&self, rv : &RegisterOrValue) -> Value {
+        match *rv {
+            RegisterOrValue::Reg(r) => self.get(r),
+            RegisterOrValue::Val(v) => Value::constant(v),
+        }
+    }
+
+    fn branch(&self, inst : &Instruction) -> Option<Branch> {
+        let (test, x, y) = match *inst {
+            Instruction::Jgz(ref x, ref y) => (Test::Positive, x, y),
+            Instruction::Jnz(ref x, ref y) => (Test::NonZero, x, y),
+            _ => return None,
+        };
+
+        Some(Branch {
+            ip : self.ip,
+            test,
+            condition : self.value(x),
+            offset : self.value(y),
+        })
+    }
+
+    // Executes everything but jumps.
+    fn execute(&mut self, inst : &Instruction) {
+        let (r, value) = match *inst {
+            Instruction::Set(r, ref x) => (r, self.value(x)),
+            Instruction::Add(r, ref x) => (r, self.get(r).add(&self.value(x))),
+            Instruction::Sub(r, ref x) => (r, self.get(r).sub(&self.value(x))),
+            Instruction::Mul(r, ref x) => (r, self.get(r).mul(&self.value(x))),
+            Instruction::Mod(r, ref x) => (r, self.get(r).rem(&self.value(x))),
+            Instruction::Rcv(r) => {
+                self.receives += 1;
+                (r, Value::from_term(vec![Atom::Received(self.receives - 1)], 1))
+            },
+            Instruction::Snd(ref x) => {
+                let value = self.value(x);
+                self.sends.push(value);
+                return;
+            },
+            Instruction::Jgz(..) |
+            Instruction::Jnz(..) |
+            Instruction::Custom(..) => return,
+        };
+
+        self.registers.insert(r, value);
+    }
+
+    // Executes one instruction, or says why it can't.
+    pub fn step(&mut self) -> Option<Stop> {
+        if self.is_halted() {
+            return Some(Stop::Halted);
+        }
+
+        let inst = &self.program.instructions[self.ip];
+        if let Instruction::Custom(..) = inst {
+            return Some(Stop::Custom(self.ip));
+        }
+
+        let mut next_ip = self.ip + 1;
+        match self.branch(inst) {
+            Some(branch) => {
+                match branch.condition.passes(branch.test) {
+                    Some(false) => {},
+                    Some(true) if branch.offset.as_constant().is_some() => {
+                        // Same arithmetic as the interpreter, so jumping off the front halts.
+                        next_ip = ((self.ip as i64) + branch.offset.as_constant().unwrap()) as usize;
+                    },
+                    _ => return Some(Stop::Branch(branch)),
+                }
+            },
+            None => self.execute(inst),
+        }
+
+        self.ip = next_ip;
+        self.steps += 1;
+        None
+    }
+
+    pub fn run(&mut self, max_steps : u64) -> Stop {
+        for _ in 0 .. max_steps {
+            if let Some(stop) = self.step() {
+                return stop;
+            }
+        }
+
+        if self.is_halted() {
+            Stop::Halted
+        } else {
+            Stop::Limit
+        }
+    }
+}
+
+impl Summary {
+    // A basic block only jumps at its end, so it can be run start to finish with every register
+    // unknown.
+    pub fn of_block(program : &Program, block : &Block) -> Summary {
+        let mut symbolic = Symbolic::starting_at(program, block.start);
+        let mut branch = None;
+
+        for ip in block.start .. block.end {
+            symbolic.ip = ip;
+            let inst = &program.instructions[ip];
+            if ip + 1 == block.end {
+                branch = symbolic.branch(inst);
+            }
+
+            symbolic.execute(inst);
+        }
+
+        Summary {
+            updates : symbolic.updates(),
+            sends : symbolic.sends,
+            branch,
+        }
+    }
+
+    pub fn update(&self, register : char) -> Option<&Value> {
+        self.updates.iter().find(|&&(r, _)| r == register).map(|(_, value)| value)
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Atom::Reg(r) => write!(f, "{}", r),
+            Atom::Received(n) => write!(f, "rcv{}", n),
+            Atom::Mod(ref a, ref b) => write!(f, "({} % {})", a.grouped(), b.grouped()),
+        }
+    }
+}
+
+// Higher powers come first and the constant last, so b*c - b + 1 reads the usual way.
+impl fmt::Display for Value {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+
+        let mut terms = self.terms.iter().collect::<Vec<(&Vec<Atom>, &i64)>>();
+        terms.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(b.0)));
+
+        for (i, (atoms, &coefficient)) in terms.into_iter().enumerate() {
+            let product = atoms.iter().map(|atom| atom.to_string()).collect::<Vec<String>>().join("*");
+            let magnitude = coefficient.unsigned_abs();
+            let term = if atoms.is_empty() {
+                magnitude.to_string()
+            } else if magnitude == 1 {
+                product
+            } else {
+                format!("{}*{}", magnitude, product)
+            };
+
+            match (i, coefficient < 0) {
+                (0, false) => write!(f, "{}", term)?,
+                (0, true) => write!(f, "-{}", term)?,
+                (_, false) => write!(f, " + {}", term)?,
+                (_, true) => write!(f, " - {}", term)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aocisa::cfg::Cfg;
+
+    // Day 23's program.
+    const COPROCESSOR : &str =
+r"set b 81
+set c b
+jnz a 2
+jnz 1 5
+mul b 100
+sub b -100000
+set c b
+sub c -17000
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -17
+jnz 1 -23";
+
+    #[test]
+    fn values() {
+        let b = Value::register('b');
+        let one = Value::constant(1);
+        assert_eq!(b.add(&one).mul(&b.sub(&one)).to_string(), "b*b - 1");
+        assert_eq!(b.sub(&b), Value::constant(0));
+        assert_eq!(Value::constant(17).rem(&Value::constant(5)).as_constant(), Some(2));
+        assert_eq!(Value::constant(-17).rem(&Value::constant(5)).as_constant(), Some(-2));
+        assert_eq!(b.rem(&Value::constant(5)).mul(&Value::constant(-3)).add(&one).to_string(), "-3*(b % 5) + 1");
+        assert_eq!(b.passes(Test::Positive), None);
+        assert_eq!(b.sub(&b).passes(Test::Zero), Some(true));
+    }
+
+    #[test]
+    fn blocks() {
+        let program = Program::load(COPROCESSOR).unwrap();
+        let cfg = Cfg::build(&program);
+        let block = cfg.block_of(11).unwrap();
+        let summary = Summary::of_block(&program, &cfg.blocks[block]);
+
+        assert_eq!(summary.updates.len(), 1);
+        assert_eq!(summary.update('g').unwrap().to_string(), "d*e - b");
+        let branch = summary.branch.unwrap();
+        assert_eq!((branch.test, branch.target()), (Test::NonZero, Some(16)));
+        assert_eq!(branch.condition.to_string(), "d*e - b");
+
+        // Multiplying the input's b by 100 and adding 100000.
+        let summary = Summary::of_block(&program, &cfg.blocks[cfg.block_of(4).unwrap()]);
+        assert_eq!(summary.update('b').unwrap().to_string(), "100*b + 100000");
+        assert_eq!(summary.update('c').unwrap().to_string(), "100*b + 117000");
+        assert_eq!((summary.updates.len(), summary.branch), (2, None));
+    }
+
+    #[test]
+    fn run() {
+        let program = Program::load("set f b\nmul f c\nrcv d\nadd d f\nsnd d\nmod d 3\njgz d -1\nsub f f").unwrap();
+        let mut symbolic = Symbolic::new(&program);
+        let branch = match symbolic.run(100) {
+            Stop::Branch(branch) => branch,
+            stop => panic!("unexpected stop {:?}", stop),
+        };
+
+        assert_eq!(branch.ip, 6);
+        assert_eq!(branch.condition.to_string(), "((b*c + rcv0) % 3)");
+        assert_eq!(symbolic.get('f').to_string(), "b*c");
+        assert_eq!(symbolic.sends(), &[Value::register('b').mul(&Value::register('c')).add(&Value::from_term(vec![Atom::Received(0)], 1))]);
+
+        let program = Program::load(COPROCESSOR).unwrap();
+        let mut symbolic = Symbolic::new(&program);
+        match symbolic.run(100) {
+            Stop::Branch(branch) => assert_eq!((branch.ip, branch.condition), (2, Value::register('a'))),
+            stop => panic!("unexpected stop {:?}", stop),
+        }
+
+        // With a pinned down, everything else follows from constants.
+        let mut symbolic = Symbolic::new(&program);
+        symbolic.set('a', Value::constant(1));
+        assert_eq!(symbolic.run(10), Stop::Limit);
+        assert_eq!(symbolic.get('c').as_constant(), Some(125100));
+    }
+}
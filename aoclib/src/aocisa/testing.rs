@@ -0,0 +1,45 @@
+// Helpers for the tests that check one way of running programs against another.
+
+// A small xorshift generator, so the random programs are the same on every run.
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next(&mut self, bound : u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+
+    pub fn value(&mut self, low : i64, high : i64) -> i64 {
+        low + self.next((high - low + 1) as u64) as i64
+    }
+
+    fn register(&mut self, registers : &str) -> char {
+        registers.chars().nth(self.next(registers.len() as u64) as usize).unwrap()
+    }
+
+    fn operand(&mut self, registers : &str) -> String {
+        if self.next(2) == 0 {
+            self.register(registers).to_string()
+        } else {
+            self.value(-3, 3).to_string()
+        }
+    }
+}
+
+// A program using only the given opcodes and registers. Operands that could overflow or divide by
+// zero are kept to small constants, since the interpreter would panic on those.
+pub fn random_program(random : &mut Random, opcodes : &[&str], registers : &str, length : usize) -> String {
+    (0 .. length).map(|_| {
+        let r = random.register(registers);
+        match opcodes[random.next(opcodes.len() as u64) as usize] {
+            "snd" => format!("snd {}", random.operand(registers)),
+            "rcv" => format!("rcv {}", r),
+            "mul" => format!("mul {} {}", r, random.value(-2, 2)),
+            "mod" => format!("mod {} {}", r, random.value(1, 5)),
+            jump @ "jgz" | jump @ "jnz" => format!("{} {} {}", jump, random.operand(registers), random.value(-4, 4)),
+            op => format!("{} {} {}", op, r, random.operand(registers)),
+        }
+    }).collect::<Vec<String>>().join("\n")
+}
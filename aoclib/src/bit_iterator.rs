@@ -0,0 +1,53 @@
+// The bits of a byte as 0s and 1s, starting from the least significant. Reverse it to start from
+// the most significant.
+pub struct BitIterator {
+    byte : u8,
+    low : u8,
+    high : u8,
+}
+
+impl BitIterator {
+    pub fn new(byte : u8) -> BitIterator {
+        BitIterator {
+            byte,
+            low : 0,
+            high : 8,
+        }
+    }
+}
+
+impl Iterator for BitIterator {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.low < self.high {
+            let bit = (self.byte >> self.low) & 1;
+            self.low += 1;
+            Some(bit)
+        } else {
+            None
+        }
+    }
+}
+
+impl DoubleEndedIterator for BitIterator {
+    fn next_back(&mut self) -> Option<u8> {
+        if self.low < self.high {
+            self.high -= 1;
+            Some((self.byte >> self.high) & 1)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bits() {
+        assert_eq!(BitIterator::new(0b1101).collect::<Vec<u8>>(), vec![1, 0, 1, 1, 0, 0, 0, 0]);
+        assert_eq!(BitIterator::new(0b1101).rev().collect::<Vec<u8>>(), vec![0, 0, 0, 0, 1, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter;
+
+// Cycles are described as (prefix length, cycle length): the number of states seen before the
+// first state that repeats, and how many states it takes to come back around to it. For the
+// sequence 0, 1, 2, 3, 1, 2, 3, ... that's (1, 3).
+
+// Finds the cycle in a sequence of states by remembering every state seen so far. Returns None if
+// the sequence ends before any state repeats.
+pub fn find_in<T, I>(states : I) -> Option<(usize, usize)>
+    where T : Clone + Eq + Hash, I : IntoIterator<Item = T> {
+    let mut first_seen : HashMap<T, usize> = HashMap::new();
+
+    for (i, state) in states.into_iter().enumerate() {
+        if let Some(&prefix_length) = first_seen.get(&state) {
+            return Some((prefix_length, i - prefix_length));
+        }
+
+        first_seen.insert(state, i);
+    }
+
+    None
+}
+
+// Finds the cycle in the states produced by repeatedly applying step to start. This never returns
+// if the states don't repeat.
+pub fn find<T, F>(start : T, mut step : F) -> (usize, usize)
+    where T : Clone + Eq + Hash, F : FnMut(&T) -> T {
+    find_in(iter::successors(Some(start), |state| Some(step(state)))).unwrap()
+}
+
+// Floyd's tortoise and hare. Keeps only a couple of states around, at the cost of stepping
+// roughly three times as often as find.
+pub fn floyd<T, F>(start : T, mut step : F) -> (usize, usize)
+    where T : Eq, F : FnMut(&T) -> T {
+    // Find a meeting point inside the cycle, where the hare has gone twice as far.
+    let mut tortoise = step(&start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+    }
+
+    // The distance from the start to the cycle equals the distance from the meeting point to the
+    // cycle, so walking both at the same speed meets at the first repeated state.
+    let mut prefix_length = 0;
+    tortoise = start;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_length += 1;
+    }
+
+    let mut cycle_length = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        cycle_length += 1;
+    }
+
+    (prefix_length, cycle_length)
+}
+
+// Brent's algorithm. Like floyd, it keeps only a couple of states around, but usually takes fewer
+// steps.
+pub fn brent<T, F>(start : T, mut step : F) -> (usize, usize)
+    where T : Clone + Eq, F : FnMut(&T) -> T {
+    // Teleport the tortoise to the hare at each power of two until the hare catches it, which
+    // gives the cycle length directly.
+    let mut power = 1;
+    let mut cycle_length = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == cycle_length {
+            tortoise = hare.clone();
+            power *= 2;
+            cycle_length = 0;
+        }
+
+        hare = step(&hare);
+        cycle_length += 1;
+    }
+
+    // With the hare a whole cycle ahead of the tortoise, they meet at the first repeated state.
+    tortoise = start.clone();
+    hare = start;
+    for _ in 0 .. cycle_length {
+        hare = step(&hare);
+    }
+
+    let mut prefix_length = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_length += 1;
+    }
+
+    (prefix_length, cycle_length)
+}
+
+// The smallest number of steps that ends in the same state as taking the given number of steps.
+pub fn equivalent_steps(steps : usize, (prefix_length, cycle_length) : (usize, usize)) -> usize {
+    if steps < prefix_length {
+        steps
+    } else {
+        prefix_length + (steps - prefix_length) % cycle_length
+    }
+}
+
+// The state after applying step to start the given number of times. Once a state repeats, whole
+// cycles are skipped rather than simulated.
+pub fn state_after<T, F>(start : T, mut step : F, steps : usize) -> T
+    where T : Clone + Eq + Hash, F : FnMut(&T) -> T {
+    let mut history = vec![start.clone()];
+    let mut first_seen : HashMap<T, usize> = HashMap::new();
+    first_seen.insert(start, 0);
+
+    while history.len() <= steps {
+        let next = step(history.last().unwrap());
+
+        if let Some(&prefix_length) = first_seen.get(&next) {
+            let cycle_length = history.len() - prefix_length;
+            return history.swap_remove(equivalent_steps(steps, (prefix_length, cycle_length)));
+        }
+
+        first_seen.insert(next.clone(), history.len());
+        history.push(next);
+    }
+
+    history.pop().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0, 1, 2, ..., tail - 1, then around a cycle of the given length forever.
+    fn rho(tail : u32, length : u32) -> impl Fn(&u32) -> u32 {
+        move |&n| {
+            if n + 1 < tail + length {
+                n + 1
+            } else {
+                tail
+            }
+        }
+    }
+
+    #[test]
+    fn hashed() {
+        assert_eq!(find_in(vec![0, 1, 2, 3, 1, 2, 3]), Some((1, 3)));
+        assert_eq!(find_in(vec![5, 5]), Some((0, 1)));
+        assert_eq!(find_in(vec![0, 1, 2]), None);
+        assert_eq!(find(0, rho(4, 7)), (4, 7));
+    }
+
+    #[test]
+    fn memory_light() {
+        for &(tail, length) in [(0, 1), (0, 5), (1, 1), (3, 4), (10, 3), (7, 16)].iter() {
+            assert_eq!(floyd(0, rho(tail, length)), (tail as usize, length as usize));
+            assert_eq!(brent(0, rho(tail, length)), (tail as usize, length as usize));
+        }
+    }
+
+    #[test]
+    fn skip_cycles() {
+        assert_eq!(equivalent_steps(2, (3, 4)), 2);
+        assert_eq!(equivalent_steps(3, (3, 4)), 3);
+        assert_eq!(equivalent_steps(8, (3, 4)), 4);
+        assert_eq!(state_after(0, rho(3, 4), 0), 0);
+        assert_eq!(state_after(0, rho(3, 4), 5), 5);
+        assert_eq!(state_after(0, rho(3, 4), 1000000000), 3 + (1000000000 - 3) % 4);
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+
+pub mod regions;
+pub mod sparse;
+pub mod symmetry;
+
+// The top left and bottom right corners of a rectangle, both inside it.
+pub type Corners<C> = ((C, C), (C, C));
+
+// What Grid and SparseGrid have in common, so code that only looks at and changes cells can work
+// on either. Grid's coordinates are usizes and run out at its edges, while SparseGrid's are i32s
+// and go on forever.
+pub trait Cells {
+    type Cell;
+    type Coord : Copy;
+
+    fn get(&self, x : Self::Coord, y : Self::Coord) -> Option<&Self::Cell>;
+    fn get_mut(&mut self, x : Self::Coord, y : Self::Coord) -> Option<&mut Self::Cell>;
+
+    // Panics if the grid has an edge and (x, y) is past it.
+    fn set(&mut self, x : Self::Coord, y : Self::Coord, value : Self::Cell);
+
+    // The top left and bottom right corners of the cells worth looking at, or None if there
+    // aren't any.
+    fn bounds(&self) -> Option<Corners<Self::Coord>>;
+}
+
+// A rectangle of cells, stored row by row. (0, 0) is the top left, and y grows downward.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid<T> {
+    rows : Vec<Vec<T>>,
+}
+
+// Walks the cells row by row from the top, along with their coordinates.
+pub struct GridEnumerator<'t, T : 't> {
+    grid : &'t Grid<T>,
+    x : usize,
+    y : usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new() -> Grid<T> {
+        Grid {
+            rows : vec![],
+        }
+    }
+
+    pub fn from_rows(rows : Vec<Vec<T>>) -> Grid<T> {
+        Grid {
+            rows,
+        }
+    }
+
+    pub fn add_row(&mut self, row : Vec<T>) {
+        self.rows.push(row);
+    }
+
+    pub fn size_x(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    pub fn size_y(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn get(&self, x : usize, y : usize) -> Option<&T> {
+        self.rows.get(y).and_then(|row| row.get(x))
+    }
+
+    pub fn get_mut(&mut self, x : usize, y : usize) -> Option<&mut T> {
+        self.rows.get_mut(y).and_then(|row| row.get_mut(x))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.rows.iter().flat_map(|row| row.iter())
+    }
+
+    pub fn enumerate(&self) -> GridEnumerator<'_, T> {
+        GridEnumerator {
+            grid : self,
+            x : 0,
+            y : 0,
+        }
+    }
+}
+
+impl<T> Cells for Grid<T> {
+    type Cell = T;
+    type Coord = usize;
+
+    fn get(&self, x : usize, y : usize) -> Option<&T> {
+        Grid::get(self, x, y)
+    }
+
+    fn get_mut(&mut self, x : usize, y : usize) -> Option<&mut T> {
+        Grid::get_mut(self, x, y)
+    }
+
+    fn set(&mut self, x : usize, y : usize, value : T) {
+        self.rows[y][x] = value;
+    }
+
+    // Every cell.
+    fn bounds(&self) -> Option<Corners<usize>> {
+        if self.size_x() == 0 || self.size_y() == 0 {
+            None
+        } else {
+            Some(((0, 0), (self.size_x() - 1, self.size_y() - 1)))
+        }
+    }
+}
+
+impl<T : Clone> Grid<T> {
+    pub fn add_row_slice(&mut self, row : &[T]) {
+        self.rows.push(row.to_vec());
+    }
+
+    // A copy turned a quarter turn clockwise.
+    pub fn rotate_right(&self) -> Grid<T> {
+        let size_y = self.size_y();
+        Grid {
+            rows : (0 .. self.size_x()).map(|x| {
+                (0 .. size_y).map(|y| self.rows[size_y - 1 - y][x].clone()).collect()
+            }).collect(),
+        }
+    }
+
+    // A mirror image copy, with left and right swapped.
+    pub fn flip_across_y(&self) -> Grid<T> {
+        Grid {
+            rows : self.rows.iter().map(|row| row.iter().rev().cloned().collect()).collect(),
+        }
+    }
+
+    // Copies every cell onto other, with this grid's top left corner at (offset_x, offset_y).
+    pub fn stamp_onto(&self, other : &mut Grid<T>, offset_x : usize, offset_y : usize) {
+        for ((x, y), value) in self.enumerate() {
+            other.rows[offset_y + y][offset_x + x] = value.clone();
+        }
+    }
+}
+
+impl<T : PartialEq> Grid<T> {
+    // Whether every cell of this grid matches other, with this grid's top left corner at
+    // (offset_x, offset_y).
+    pub fn matches_on(&self, other : &Grid<T>, offset_x : usize, offset_y : usize) -> bool {
+        self.enumerate().all(|((x, y), value)| {
+            other.get(offset_x + x, offset_y + y) == Some(value)
+        })
+    }
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Grid<T> {
+        Grid::new()
+    }
+}
+
+impl<T : fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows.iter() {
+            for value in row.iter() {
+                write!(f, "{}", value)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'t, T> Iterator for GridEnumerator<'t, T> {
+    type Item = ((usize, usize), &'t T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(row) = self.grid.rows.get(self.y) {
+            if let Some(value) = row.get(self.x) {
+                let ret = ((self.x, self.y), value);
+                self.x += 1;
+                return Some(ret);
+            }
+
+            self.x = 0;
+            self.y += 1;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input : &str) -> Grid<char> {
+        let mut grid = Grid::new();
+        for line in input.split('/') {
+            grid.add_row(line.chars().collect());
+        }
+        grid
+    }
+
+    #[test]
+    fn transforms() {
+        let grid = parse("ab/cd/ef");
+        assert_eq!(grid.size_x(), 2);
+        assert_eq!(grid.size_y(), 3);
+        assert_eq!(grid.rotate_right().to_string(), "eca\nfdb\n");
+        assert_eq!(grid.flip_across_y().to_string(), "ba\ndc\nfe\n");
+        assert_eq!(grid.enumerate().nth(3), Some(((1, 1), &'d')));
+    }
+
+    // Written against Cells, so it works the same on either kind of grid.
+    fn draw_corners<G : Cells<Cell = char>>(grid : &mut G) {
+        let ((min_x, min_y), (max_x, max_y)) = grid.bounds().unwrap();
+        grid.set(min_x, min_y, '#');
+        *grid.get_mut(max_x, max_y).unwrap() = '#';
+    }
+
+    #[test]
+    fn cells() {
+        let mut grid = parse(".../.../...");
+        draw_corners(&mut grid);
+        assert_eq!(grid.to_string(), "#..\n...\n..#\n");
+        assert_eq!(Cells::get(&grid, 3, 0), None);
+        assert_eq!(Grid::<char>::new().bounds(), None);
+
+        let mut sparse = sparse::SparseGrid::new('.');
+        sparse.set(-1, 4, 'a');
+        sparse.set(2, -3, 'b');
+        draw_corners(&mut sparse);
+        assert_eq!(sparse.to_string(), "#..b\n....\n....\n....\n....\n....\n....\na..#\n");
+    }
+
+    #[test]
+    fn stamp() {
+        let mut grid = parse(".../.../...");
+        let stamp = parse("ab");
+        stamp.stamp_onto(&mut grid, 1, 2);
+        assert_eq!(grid.to_string(), "...\n...\n.ab\n");
+        assert!(stamp.matches_on(&grid, 1, 2));
+        assert!(!stamp.matches_on(&grid, 0, 2));
+        assert!(!stamp.matches_on(&grid, 2, 2));
+    }
+}
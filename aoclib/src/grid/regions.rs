@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+
+use grid::Grid;
+
+// Which cells count as touching.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Connectivity {
+    // Up, down, left, and right.
+    Four,
+    // Diagonals too.
+    Eight,
+}
+
+const FOUR : [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const EIGHT : [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+// One connected group of cells.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Component {
+    pub size : usize,
+    // The top left and bottom right corners of the smallest rectangle holding every cell.
+    pub min : (usize, usize),
+    pub max : (usize, usize),
+}
+
+pub struct Components {
+    // The component each cell belongs to, as an index into regions, or None for cells that
+    // weren't included.
+    pub labels : Grid<Option<usize>>,
+    // In the order their first cell turns up, reading row by row from the top.
+    pub regions : Vec<Component>,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [(i32, i32)] {
+        match *self {
+            Connectivity::Four => &FOUR,
+            Connectivity::Eight => &EIGHT,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    // The cells next to (x, y) that are inside the grid, clockwise starting from the one above.
+    pub fn neighbors(&self, x : usize, y : usize, connectivity : Connectivity) -> impl Iterator<Item = (usize, usize)> {
+        let (size_x, size_y) = (self.size_x() as i64, self.size_y() as i64);
+        connectivity.offsets().iter().filter_map(move |&(dx, dy)| {
+            let (nx, ny) = (x as i64 + dx as i64, y as i64 + dy as i64);
+            if nx >= 0 && nx < size_x && ny >= 0 && ny < size_y {
+                Some((nx as usize, ny as usize))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn neighbors4(&self, x : usize, y : usize) -> impl Iterator<Item = (usize, usize)> {
+        self.neighbors(x, y, Connectivity::Four)
+    }
+
+    pub fn neighbors8(&self, x : usize, y : usize) -> impl Iterator<Item = (usize, usize)> {
+        self.neighbors(x, y, Connectivity::Eight)
+    }
+
+    // Every cell reachable from (x, y) by stepping only onto cells that include accepts, nearest
+    // first. Empty if include doesn't accept the starting cell. Uses a queue rather than
+    // recursion, so big regions can't overflow the stack.
+    pub fn region<F>(&self, x : usize, y : usize, connectivity : Connectivity, include : F) -> Vec<(usize, usize)>
+        where F : Fn(&T) -> bool {
+        let mut seen = vec![vec![false; self.size_x()]; self.size_y()];
+        self.region_marking(x, y, connectivity, &include, &mut seen)
+    }
+
+    fn region_marking<F>(&self, x : usize, y : usize, connectivity : Connectivity, include : &F, seen : &mut [Vec<bool>]) -> Vec<(usize, usize)>
+        where F : Fn(&T) -> bool {
+        let mut cells = vec![];
+        match self.get(x, y) {
+            Some(value) if include(value) && !seen[y][x] => {},
+            _ => return cells,
+        }
+
+        let mut frontier = VecDeque::new();
+        seen[y][x] = true;
+        frontier.push_back((x, y));
+
+        while let Some((cx, cy)) = frontier.pop_front() {
+            cells.push((cx, cy));
+            for (nx, ny) in self.neighbors(cx, cy, connectivity) {
+                if !seen[ny][nx] && include(self.get(nx, ny).unwrap()) {
+                    seen[ny][nx] = true;
+                    frontier.push_back((nx, ny));
+                }
+            }
+        }
+
+        cells
+    }
+
+    // Labels every group of connected cells that include accepts.
+    pub fn components<F>(&self, connectivity : Connectivity, include : F) -> Components
+        where F : Fn(&T) -> bool {
+        let mut seen = vec![vec![false; self.size_x()]; self.size_y()];
+        let mut labels = vec![vec![None; self.size_x()]; self.size_y()];
+        let mut regions = vec![];
+
+        for y in 0 .. self.size_y() {
+            for x in 0 .. self.size_x() {
+                let cells = self.region_marking(x, y, connectivity, &include, &mut seen);
+                if cells.is_empty() {
+                    continue;
+                }
+
+                let mut component = Component {
+                    size : cells.len(),
+                    min : (x, y),
+                    max : (x, y),
+                };
+
+                for &(cx, cy) in cells.iter() {
+                    labels[cy][cx] = Some(regions.len());
+                    component.min = (component.min.0.min(cx), component.min.1.min(cy));
+                    component.max = (component.max.0.max(cx), component.max.1.max(cy));
+                }
+
+                regions.push(component);
+            }
+        }
+
+        let mut label_grid = Grid::new();
+        for row in labels {
+            label_grid.add_row(row);
+        }
+
+        Components {
+            labels : label_grid,
+            regions,
+        }
+    }
+}
+
+impl<T : Clone + PartialEq> Grid<T> {
+    // Replaces the cell at (x, y) and every connected cell with the same value. Returns how many
+    // cells changed.
+    pub fn flood_fill(&mut self, x : usize, y : usize, connectivity : Connectivity, value : T) -> usize {
+        let old = match self.get(x, y) {
+            Some(old) if *old != value => old.clone(),
+            _ => return 0,
+        };
+
+        let cells = self.region(x, y, connectivity, |v| *v == old);
+        for &(cx, cy) in cells.iter() {
+            *self.get_mut(cx, cy).unwrap() = value.clone();
+        }
+
+        cells.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input : &str) -> Grid<char> {
+        let mut grid = Grid::new();
+        for line in input.lines() {
+            grid.add_row(line.chars().collect());
+        }
+        grid
+    }
+
+    const BLOBS : &str =
+r"##..#
+#..#.
+..##.
+#....";
+
+    #[test]
+    fn neighbors() {
+        let grid = parse(BLOBS);
+        assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors8(4, 3).collect::<Vec<_>>(), vec![(4, 2), (3, 3), (3, 2)]);
+        assert_eq!(grid.neighbors8(2, 1).count(), 8);
+    }
+
+    #[test]
+    fn fill() {
+        let mut grid = parse(BLOBS);
+        assert_eq!(grid.region(2, 2, Connectivity::Four, |&c| c == '#'), vec![(2, 2), (3, 2), (3, 1)]);
+        assert_eq!(grid.region(2, 2, Connectivity::Eight, |&c| c == '#').len(), 4);
+        assert!(grid.region(1, 1, Connectivity::Four, |&c| c == '#').is_empty());
+
+        assert_eq!(grid.flood_fill(2, 0, Connectivity::Four, 'o'), 12);
+        assert_eq!(grid.flood_fill(2, 0, Connectivity::Four, 'o'), 0);
+        assert_eq!(grid.to_string(), "##oo#\n#oo#o\noo##o\n#oooo\n");
+    }
+
+    #[test]
+    fn components() {
+        let grid = parse(BLOBS);
+        let four = grid.components(Connectivity::Four, |&c| c == '#');
+        assert_eq!(four.regions.len(), 4);
+        assert_eq!(four.regions[0], Component { size : 3, min : (0, 0), max : (1, 1) });
+        assert_eq!(four.regions[2], Component { size : 3, min : (2, 1), max : (3, 2) });
+        assert_eq!(four.labels.get(3, 2), Some(&Some(2)));
+        assert_eq!(four.labels.get(2, 0), Some(&None));
+
+        let eight = grid.components(Connectivity::Eight, |&c| c == '#');
+        assert_eq!(eight.regions.iter().map(|r| r.size).collect::<Vec<_>>(), vec![3, 4, 1]);
+        assert_eq!(eight.regions[1], Component { size : 4, min : (2, 0), max : (4, 2) });
+    }
+}
@@ -0,0 +1,307 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+
+use grid::{Cells, Corners};
+
+// Chunks are square, this many cells on a side as a power of two.
+const CHUNK_BITS : u32 = 4;
+const CHUNK_SIDE : i32 = 1 << CHUNK_BITS;
+const CHUNK_MASK : i32 = CHUNK_SIDE - 1;
+
+type Bounds = Option<Corners<i32>>;
+
+// A grid with signed coordinates and no edges. Every cell starts out holding the fill value, and
+// only the parts that get written to take any memory. Cells are stored in dense chunks, so that
+// walking around a neighborhood mostly stays within one allocation.
+//
+// As with Grid, y grows downward when displayed.
+#[derive(Clone)]
+pub struct SparseGrid<T> {
+    chunks : HashMap<(i32, i32), Chunk<T>>,
+    fill : T,
+}
+
+#[derive(Clone)]
+struct Chunk<T> {
+    cells : Vec<T>,
+
+    // How many cells aren't the fill value, and where they are. set keeps this up to date where
+    // it can, but get_mut can't see what's done with the cell it hands out, so it throws it away
+    // and the next len or bounds counts the chunk again.
+    summary : Cell<Option<Summary>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Summary {
+    count : usize,
+    bounds : Bounds,
+}
+
+fn chunk_of(x : i32, y : i32) -> (i32, i32) {
+    (x >> CHUNK_BITS, y >> CHUNK_BITS)
+}
+
+fn index_in_chunk(x : i32, y : i32) -> usize {
+    (((y & CHUNK_MASK) << CHUNK_BITS) | (x & CHUNK_MASK)) as usize
+}
+
+fn position_in_chunk((cx, cy) : (i32, i32), i : usize) -> (i32, i32) {
+    ((cx << CHUNK_BITS) + (i as i32 & CHUNK_MASK), (cy << CHUNK_BITS) + (i as i32 >> CHUNK_BITS))
+}
+
+fn add_to_bounds(bounds : Bounds, (x, y) : (i32, i32)) -> Bounds {
+    Some(match bounds {
+        None => ((x, y), (x, y)),
+        Some(((min_x, min_y), (max_x, max_y))) => ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y))),
+    })
+}
+
+fn merge_bounds(bounds : Bounds, other : Bounds) -> Bounds {
+    match other {
+        None => bounds,
+        Some((top_left, bottom_right)) => add_to_bounds(add_to_bounds(bounds, top_left), bottom_right),
+    }
+}
+
+impl<T : PartialEq> Chunk<T> {
+    fn summary(&self, key : (i32, i32), fill : &T) -> Summary {
+        if let Some(summary) = self.summary.get() {
+            return summary;
+        }
+
+        let mut summary = Summary {
+            count : 0,
+            bounds : None,
+        };
+
+        for (i, _) in self.cells.iter().enumerate().filter(|&(_, value)| value != fill) {
+            summary.count += 1;
+            summary.bounds = add_to_bounds(summary.bounds, position_in_chunk(key, i));
+        }
+
+        self.summary.set(Some(summary));
+        summary
+    }
+}
+
+impl<T : Clone + PartialEq> SparseGrid<T> {
+    pub fn new(fill : T) -> SparseGrid<T> {
+        SparseGrid {
+            chunks : HashMap::new(),
+            fill,
+        }
+    }
+
+    pub fn fill(&self) -> &T {
+        &self.fill
+    }
+
+    // Like get, but there's no edge to fall off of, so there's no need for an Option.
+    pub fn at(&self, x : i32, y : i32) -> &T {
+        match self.chunks.get(&chunk_of(x, y)) {
+            Some(chunk) => &chunk.cells[index_in_chunk(x, y)],
+            None => &self.fill,
+        }
+    }
+
+    pub fn at_mut(&mut self, x : i32, y : i32) -> &mut T {
+        let chunk = self.chunk_mut(x, y);
+        chunk.summary.set(None);
+        &mut chunk.cells[index_in_chunk(x, y)]
+    }
+
+    fn chunk_mut(&mut self, x : i32, y : i32) -> &mut Chunk<T> {
+        let fill = &self.fill;
+        self.chunks.entry(chunk_of(x, y)).or_insert_with(|| {
+            Chunk {
+                cells : vec![fill.clone(); (CHUNK_SIDE * CHUNK_SIDE) as usize],
+                summary : Cell::new(Some(Summary {
+                    count : 0,
+                    bounds : None,
+                })),
+            }
+        })
+    }
+
+    // Every cell that holds something other than the fill value, row by row from the top.
+    pub fn enumerate(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
+        let mut cells = vec![];
+        for (&key, chunk) in self.chunks.iter() {
+            for (i, value) in chunk.cells.iter().enumerate().filter(|&(_, value)| *value != self.fill) {
+                cells.push((position_in_chunk(key, i), value));
+            }
+        }
+
+        cells.sort_by_key(|&((x, y), _)| (y, x));
+        cells.into_iter()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks.values().flat_map(|chunk| chunk.cells.iter()).filter(move |&value| *value != self.fill)
+    }
+
+    // How many cells hold something other than the fill value.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|(&key, chunk)| chunk.summary(key, &self.fill).count).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Puts the fill value back in every cell that keep rejects, and frees chunks that end up
+    // holding nothing else.
+    pub fn retain<F>(&mut self, mut keep : F)
+        where F : FnMut((i32, i32), &T) -> bool {
+        let fill = self.fill.clone();
+        for (&key, chunk) in self.chunks.iter_mut() {
+            chunk.summary.set(None);
+            for (i, value) in chunk.cells.iter_mut().enumerate() {
+                if *value != fill && !keep(position_in_chunk(key, i), value) {
+                    *value = fill.clone();
+                }
+            }
+        }
+
+        self.chunks.retain(|&key, chunk| chunk.summary(key, &fill).count > 0);
+    }
+}
+
+impl<T : Clone + PartialEq> Cells for SparseGrid<T> {
+    type Cell = T;
+    type Coord = i32;
+
+    // Always Some.
+    fn get(&self, x : i32, y : i32) -> Option<&T> {
+        Some(self.at(x, y))
+    }
+
+    // Always Some.
+    fn get_mut(&mut self, x : i32, y : i32) -> Option<&mut T> {
+        Some(self.at_mut(x, y))
+    }
+
+    fn set(&mut self, x : i32, y : i32, value : T) {
+        if value == self.fill && !self.chunks.contains_key(&chunk_of(x, y)) {
+            return;
+        }
+
+        let fill = self.fill.clone();
+        let chunk = self.chunk_mut(x, y);
+        let cell = &mut chunk.cells[index_in_chunk(x, y)];
+        let was_fill = *cell == fill;
+        let is_fill = value == fill;
+        *cell = value;
+
+        // Filling in a cell can only grow the bounds, but clearing one might shrink them, which
+        // is left for the next count.
+        chunk.summary.set(match chunk.summary.get() {
+            Some(summary) if was_fill && !is_fill => Some(Summary {
+                count : summary.count + 1,
+                bounds : add_to_bounds(summary.bounds, (x, y)),
+            }),
+            Some(summary) if was_fill == is_fill => Some(summary),
+            _ => None,
+        });
+    }
+
+    // The smallest rectangle holding every cell that isn't the fill value.
+    fn bounds(&self) -> Bounds {
+        self.chunks.iter().fold(None, |bounds, (&key, chunk)| merge_bounds(bounds, chunk.summary(key, &self.fill).bounds))
+    }
+}
+
+// Shows the bounds, one row per line, with the fill value in between.
+impl<T : Clone + PartialEq + fmt::Display> fmt::Display for SparseGrid<T> {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        if let Some(((min_x, min_y), (max_x, max_y))) = self.bounds() {
+            for y in min_y ..= max_y {
+                for x in min_x ..= max_x {
+                    write!(f, "{}", self.at(x, y))?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn get_set() {
+        let mut grid = SparseGrid::new(0);
+        assert_eq!(*grid.at(-1000, 5000), 0);
+        assert_eq!(grid.get(-1000, 5000), Some(&0));
+        grid.set(-17, 3, 4);
+        *grid.at_mut(16, -1) += 2;
+        *grid.get_mut(16, -1).unwrap() += 2;
+        grid.set(0, 0, 1);
+        assert_eq!(*grid.at(-17, 3), 4);
+        assert_eq!(*grid.at(16, -1), 4);
+        assert_eq!(*grid.at(-16, 3), 0);
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.enumerate().collect::<Vec<_>>(), vec![((16, -1), &4), ((0, 0), &1), ((-17, 3), &4)]);
+        assert_eq!(grid.bounds(), Some(((-17, -1), (16, 3))));
+
+        // Clearing the corner cells shrinks the bounds.
+        grid.set(-17, 3, 0);
+        *grid.at_mut(16, -1) = 0;
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.bounds(), Some(((0, 0), (0, 0))));
+
+        // Setting the fill value somewhere untouched doesn't make a chunk.
+        grid.set(1000, 1000, 0);
+        assert_eq!(grid.chunks.len(), 3);
+    }
+
+    #[test]
+    fn tracked() {
+        let mut grid = SparseGrid::new('.');
+        for i in 0 .. 100 {
+            grid.set(i % 37 - 18, i / 5, '#');
+            assert_eq!(grid.len(), grid.iter().count());
+            assert_eq!(grid.bounds(), grid.enumerate().fold(None, |bounds, (pos, _)| add_to_bounds(bounds, pos)));
+        }
+
+        for i in 0 .. 100 {
+            if i % 3 == 0 {
+                grid.set(i % 37 - 18, i / 5, '.');
+            } else {
+                *grid.at_mut(i % 37 - 18, i / 5) = '+';
+            }
+
+            assert_eq!(grid.len(), grid.iter().count());
+            assert_eq!(grid.bounds(), grid.enumerate().fold(None, |bounds, (pos, _)| add_to_bounds(bounds, pos)));
+        }
+    }
+
+    #[test]
+    fn retain() {
+        let mut grid = SparseGrid::new(0);
+        for x in -40 .. 40 {
+            grid.set(x, x / 3, x.abs() + 1);
+        }
+
+        grid.retain(|(x, _), _| x.abs() < 2);
+        assert_eq!(grid.iter().cloned().sum::<i32>(), 2 + 1 + 2);
+        assert_eq!(grid.chunks.len(), 2);
+
+        grid.retain(|_, _| false);
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(grid.to_string(), "");
+    }
+
+    #[test]
+    fn display() {
+        let mut grid = SparseGrid::new('.');
+        grid.set(-1, -1, '#');
+        grid.set(1, 0, '#');
+        assert_eq!(grid.to_string(), "#..\n..#\n");
+    }
+}
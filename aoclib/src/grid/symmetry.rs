@@ -0,0 +1,152 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use grid::Grid;
+
+// Walks through the 8 ways of rotating and reflecting a grid: the four clockwise rotations
+// starting with the grid itself, then the same four of its mirror image. A grid with some
+// symmetry of its own will turn up more than once.
+pub struct Symmetries<T> {
+    next : Grid<T>,
+    count : usize,
+}
+
+impl<T : Clone> Iterator for Symmetries<T> {
+    type Item = Grid<T>;
+
+    fn next(&mut self) -> Option<Grid<T>> {
+        if self.count == 8 {
+            return None;
+        }
+
+        self.count += 1;
+        let following = if self.count == 4 {
+            self.next.rotate_right().flip_across_y()
+        } else {
+            self.next.rotate_right()
+        };
+
+        Some(std::mem::replace(&mut self.next, following))
+    }
+}
+
+// The same for a grid and every rotation or reflection of it, and different for anything else,
+// so it can be used to look patterns up in a HashMap regardless of orientation.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct SymmetryKey<K> {
+    size_y : usize,
+    size_x : usize,
+    cells : Vec<K>,
+}
+
+impl<T : Clone> Grid<T> {
+    pub fn symmetries(&self) -> Symmetries<T> {
+        Symmetries {
+            next : self.clone(),
+            count : 0,
+        }
+    }
+
+    // The symmetry key of the variant whose cells, mapped through key and read row by row from
+    // the top, come first in order. Taller grids sort after shorter ones.
+    pub fn symmetry_key_by<K, F>(&self, key : F) -> SymmetryKey<K>
+        where K : Ord, F : Fn(&T) -> K {
+        self.symmetries().map(|variant| {
+            SymmetryKey {
+                size_y : variant.size_y(),
+                size_x : variant.size_x(),
+                cells : variant.iter().map(&key).collect(),
+            }
+        }).min().unwrap()
+    }
+}
+
+impl<T : Clone + PartialEq> Grid<T> {
+    // Like symmetries, but each variant only shows up once.
+    pub fn distinct_symmetries(&self) -> Vec<Grid<T>> {
+        let mut variants : Vec<Grid<T>> = vec![];
+        for variant in self.symmetries() {
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        variants
+    }
+}
+
+impl<T : Clone + Ord> Grid<T> {
+    pub fn symmetry_key(&self) -> SymmetryKey<T> {
+        self.symmetry_key_by(T::clone)
+    }
+
+    // The variant with the smallest symmetry key. Any two grids that are rotations or
+    // reflections of each other have the same canonical form.
+    pub fn canonical(&self) -> Grid<T> {
+        let key = self.symmetry_key();
+        let mut canonical = Grid::new();
+        for row in key.cells.chunks(key.size_x.max(1)) {
+            canonical.add_row(row.to_vec());
+        }
+        canonical
+    }
+}
+
+impl<T : Clone + Ord + Hash> Grid<T> {
+    // A hash that doesn't change when the grid is rotated or reflected.
+    pub fn symmetry_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.symmetry_key().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input : &str) -> Grid<char> {
+        let mut grid = Grid::new();
+        for line in input.split('/') {
+            grid.add_row(line.chars().collect());
+        }
+        grid
+    }
+
+    #[test]
+    fn symmetries() {
+        let grid = parse("ab/cd");
+        let variants = grid.symmetries().map(|variant| variant.to_string()).collect::<Vec<String>>();
+        assert_eq!(variants, vec![
+            "ab\ncd\n", "ca\ndb\n", "dc\nba\n", "bd\nac\n",
+            "ba\ndc\n", "db\nca\n", "cd\nab\n", "ac\nbd\n"]);
+
+        assert_eq!(parse("../..").distinct_symmetries().len(), 1);
+        assert_eq!(parse("#./..").distinct_symmetries().len(), 4);
+        assert_eq!(parse("##./.../..#").distinct_symmetries().len(), 8);
+        assert_eq!(parse("abc").distinct_symmetries().len(), 4);
+    }
+
+    #[test]
+    fn canonical() {
+        let grid = parse(".#./..#/###");
+        let canonical = grid.canonical();
+        assert_eq!(canonical.to_string(), "###\n#..\n.#.\n");
+        for variant in grid.symmetries() {
+            assert!(variant.canonical() == canonical);
+            assert_eq!(variant.symmetry_key(), grid.symmetry_key());
+            assert_eq!(variant.symmetry_hash(), grid.symmetry_hash());
+        }
+
+        assert_ne!(grid.symmetry_key(), parse(".#./#.#/###").symmetry_key());
+        assert_eq!(parse("abc").canonical().to_string(), "abc\n");
+        assert_eq!(parse("cba").symmetry_key(), parse("a/b/c").symmetry_key());
+    }
+
+    #[test]
+    fn key_by() {
+        let grid = parse("#./..");
+        let other = parse("../.#");
+        assert_eq!(grid.symmetry_key_by(|&c| c == '#'), other.symmetry_key_by(|&c| c == '#'));
+        assert_ne!(grid.symmetry_key_by(|&c| c == '#'), parse("#./.#").symmetry_key_by(|&c| c == '#'));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use solution::Part;
+
+// Where a day's puzzle input comes from. Named inputs are looked up in a directory of
+// "<name>.txt" files, so a day can keep its puzzle examples next to the real input.
+#[derive(Clone, PartialEq, Debug)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+    Named(String),
+}
+
+// A loaded input, along with the name it's reported under.
+pub struct Input {
+    pub name : String,
+    pub contents : String,
+}
+
+pub const INPUT_EXTENSION : &str = "txt";
+
+// Most days want surrounding whitespace stripped, but a few depend on it.
+fn finish(contents : String, trim : bool) -> String {
+    if trim {
+        String::from(contents.trim())
+    } else {
+        contents
+    }
+}
+
+pub fn read_all_file(path : &Path) -> io::Result<String> {
+    read_all_file_notrim(path).map(|contents| finish(contents, true))
+}
+
+pub fn read_all_file_notrim(path : &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    fs::File::open(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+pub fn named_input_path(dir : &Path, name : &str) -> PathBuf {
+    dir.join(format!("{}.{}", name, INPUT_EXTENSION))
+}
+
+// Puzzle examples are often only meaningful for one half of the puzzle, so a named input ending
+// in "_a" or "_b" applies only to that part. Anything else applies to both.
+pub fn named_input_part(name : &str) -> Option<Part> {
+    let mut split = name.rsplitn(2, '_');
+    let suffix = split.next().unwrap();
+    split.next().and_then(|_| Part::parse(suffix))
+}
+
+// Lists the names of all inputs in a directory, sorted so that runs are repeatable.
+pub fn find_named_inputs(dir : &Path) -> io::Result<Vec<String>> {
+    let mut names = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == INPUT_EXTENSION) {
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(String::from(stem));
+            }
+        }
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+impl InputSource {
+    // Named inputs are resolved relative to named_dir.
+    pub fn load(&self, named_dir : &Path, trim : bool) -> io::Result<Input> {
+        let (name, contents) = match *self {
+            InputSource::Stdin => {
+                let mut contents = String::new();
+                io::stdin().read_to_string(&mut contents)?;
+                (String::from("stdin"), contents)
+            },
+            InputSource::File(ref path) => {
+                (path.display().to_string(), read_all_file_notrim(path)?)
+            },
+            InputSource::Named(ref name) => {
+                (name.clone(), read_all_file_notrim(&named_input_path(named_dir, name))?)
+            },
+        };
+
+        Ok(Input {
+            name,
+            contents : finish(contents, trim),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use std::io::Write;
+
+    fn make_inputs_dir(test_name : &str, files : &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("aoclib-input-{}-{}", test_name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for &(file_name, contents) in files {
+            fs::File::create(dir.join(file_name)).unwrap().write_all(contents.as_bytes()).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn named_inputs_sorted() {
+        let dir = make_inputs_dir("sorted", &[("input.txt", "1"), ("example.txt", "2"), ("notes.md", "3")]);
+        assert_eq!(find_named_inputs(&dir).unwrap(), vec!["example", "input"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn named_part() {
+        assert_eq!(named_input_part("input"), None);
+        assert_eq!(named_input_part("example_a"), Some(Part::A));
+        assert_eq!(named_input_part("example_b"), Some(Part::B));
+        assert_eq!(named_input_part("big_example"), None);
+        assert_eq!(named_input_part("b"), None);
+    }
+
+    #[test]
+    fn load_trims() {
+        let dir = make_inputs_dir("trim", &[("example.txt", "  abc \n")]);
+        let source = InputSource::Named(String::from("example"));
+
+        let trimmed = source.load(&dir, true).unwrap();
+        assert_eq!(trimmed.name, "example");
+        assert_eq!(trimmed.contents, "abc");
+
+        assert_eq!(source.load(&dir, false).unwrap().contents, "  abc \n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_missing() {
+        let dir = make_inputs_dir("missing", &[]);
+        assert!(InputSource::Named(String::from("nope")).load(&dir, true).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
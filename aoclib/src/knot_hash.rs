@@ -0,0 +1,63 @@
+const SUFFIX : [usize; 5] = [17, 31, 73, 47, 23];
+const ROUNDS : usize = 64;
+const BLOCK_SIZE : usize = 16;
+
+// The circular list of numbers from day 10 that gets twisted to make a knot hash.
+pub struct Ring {
+    items : Vec<u32>,
+    position : usize,
+    skip : usize,
+}
+
+impl Ring {
+    pub fn new(size : u32) -> Ring {
+        Ring {
+            items : (0 .. size).collect(),
+            position : 0,
+            skip : 0,
+        }
+    }
+
+    // Reverses the next len items and moves on. Returns the product of the first two items, which
+    // is the answer to day 10 part a.
+    pub fn advance(&mut self, len : usize) -> usize {
+        let size = self.items.len();
+        for i in 0 .. len / 2 {
+            self.items.swap((self.position + i) % size, (self.position + len - 1 - i) % size);
+        }
+
+        self.position = (self.position + len + self.skip) % size;
+        self.skip += 1;
+        (self.items[0] * self.items[1]) as usize
+    }
+}
+
+pub fn knot_hash(input : &str) -> Vec<u8> {
+    let lengths = input.bytes().map(|b| b as usize).chain(SUFFIX.iter().cloned()).collect::<Vec<usize>>();
+
+    let mut ring = Ring::new(256);
+    for _ in 0 .. ROUNDS {
+        for &len in lengths.iter() {
+            ring.advance(len);
+        }
+    }
+
+    ring.items.chunks(BLOCK_SIZE).map(|block| {
+        block.iter().fold(0, |sofar, item| sofar ^ item) as u8
+    }).collect()
+}
+
+pub fn knot_hash_as_hex(input : &str) -> String {
+    knot_hash(input).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hex() {
+        assert_eq!(knot_hash_as_hex(""), "a2582a3a0e66e6e86e3812dcb672a272");
+        assert_eq!(knot_hash_as_hex("AoC 2017"), "33efeb34ea91902bb2f59c9920caa6cd");
+    }
+}
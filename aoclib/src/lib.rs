@@ -0,0 +1,47 @@
+use std::io;
+use std::io::Read;
+use std::str::FromStr;
+
+#[macro_use] pub mod log;
+pub mod aocisa;
+pub mod bit_iterator;
+pub mod cycle;
+pub mod direction;
+pub mod grid;
+pub mod input;
+pub mod knot_hash;
+pub mod onoffpixel;
+pub mod parse_error;
+pub mod simulation;
+pub mod solution;
+
+pub fn read_all_stdin() -> String {
+    String::from(read_all_stdin_notrim().trim())
+}
+
+// For the days where leading or trailing whitespace in the input matters.
+pub fn read_all_stdin_notrim() -> String {
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents).expect("failed to read stdin");
+    contents
+}
+
+// Runs an iterator to the end for its side effects.
+pub fn consume_iterator<I : Iterator>(iter : &mut I) {
+    for _ in iter {
+    }
+}
+
+// Every whitespace-separated word that parses as a T, skipping the rest.
+pub fn parse_nums<'a, T : FromStr + 'a>(input : &'a str) -> impl Iterator<Item = T> + 'a {
+    input.split_whitespace().filter_map(|word| word.parse::<T>().ok())
+}
+
+pub fn any_eq<T : PartialEq>(iter : impl Iterator<Item = T>, item : &T) -> bool {
+    position_eq(iter, item).is_some()
+}
+
+// Where item first shows up in iter, if it does.
+pub fn position_eq<T : PartialEq>(mut iter : impl Iterator<Item = T>, item : &T) -> Option<usize> {
+    iter.position(|x| x == *item)
+}
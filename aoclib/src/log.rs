@@ -0,0 +1,195 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Tracing is off unless the runner turns it on, either for everything or only for some days.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+const LEVELS : [Level; 6] = [Level::Off, Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+
+impl Level {
+    pub fn parse(input : &str) -> Option<Level> {
+        let input = input.to_lowercase();
+        LEVELS.iter().find(|level| level.name() == input).cloned()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Level::Off => "off",
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Which levels are enabled, overall and per day.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Filter {
+    default : Level,
+    days : Vec<(u32, Level)>,
+}
+
+impl Filter {
+    pub const fn new() -> Filter {
+        Filter {
+            default : Level::Off,
+            days : Vec::new(),
+        }
+    }
+
+    pub fn set_default(&mut self, level : Level) {
+        self.default = level;
+    }
+
+    pub fn set_day(&mut self, day : u32, level : Level) {
+        self.days.retain(|&(d, _)| d != day);
+        self.days.push((day, level));
+    }
+
+    pub fn level(&self, day : Option<u32>) -> Level {
+        day.and_then(|day| self.days.iter().find(|&&(d, _)| d == day).map(|&(_, level)| level))
+            .unwrap_or(self.default)
+    }
+
+    pub fn max_level(&self) -> Level {
+        self.days.iter().map(|&(_, level)| level).fold(self.default, |max, level| max.max(level))
+    }
+
+    pub fn enabled(&self, day : Option<u32>, level : Level) -> bool {
+        level != Level::Off && level <= self.level(day)
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter::new()
+    }
+}
+
+static FILTER : Mutex<Filter> = Mutex::new(Filter::new());
+
+// Checked before taking the lock, so that disabled tracing in hot loops stays cheap.
+static MAX_LEVEL : AtomicUsize = AtomicUsize::new(Level::Off as usize);
+
+pub fn set_filter(filter : Filter) {
+    MAX_LEVEL.store(filter.max_level() as usize, Ordering::Relaxed);
+    *FILTER.lock().unwrap() = filter;
+}
+
+// Each day lives in a crate named after it, like p07, so that's where the day number comes from.
+// Anything else, such as aoclib itself, only follows the default level.
+pub fn day_from_module_path(module_path : &str) -> Option<u32> {
+    let krate = module_path.split("::").next().unwrap();
+    krate.strip_prefix('p').and_then(|day| day.parse::<u32>().ok())
+}
+
+pub fn enabled(module_path : &str, level : Level) -> bool {
+    if (level as usize) > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+
+    FILTER.lock().unwrap().enabled(day_from_module_path(module_path), level)
+}
+
+pub fn write(module_path : &str, level : Level, args : fmt::Arguments) {
+    let krate = module_path.split("::").next().unwrap();
+    eprintln!("[{} {}] {}", krate, level, args);
+}
+
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::log::enabled(module_path!(), $level) {
+            $crate::log::write(module_path!(), $level, format_args!($($arg)+));
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => { log!($crate::log::Level::Error, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => { log!($crate::log::Level::Warn, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => { log!($crate::log::Level::Info, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => { log!($crate::log::Level::Debug, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => { log!($crate::log::Level::Trace, $($arg)+) };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn level_parse() {
+        assert_eq!(Level::parse("trace"), Some(Level::Trace));
+        assert_eq!(Level::parse("Debug"), Some(Level::Debug));
+        assert_eq!(Level::parse("off"), Some(Level::Off));
+        assert_eq!(Level::parse("loud"), None);
+    }
+
+    #[test]
+    fn module_path_day() {
+        assert_eq!(day_from_module_path("p07"), Some(7));
+        assert_eq!(day_from_module_path("p18::test"), Some(18));
+        assert_eq!(day_from_module_path("aoclib::grid"), None);
+        assert_eq!(day_from_module_path("parser"), None);
+    }
+
+    #[test]
+    fn filter_default_off() {
+        let filter = Filter::new();
+        assert!(!filter.enabled(Some(1), Level::Error));
+        assert!(!filter.enabled(None, Level::Error));
+        assert_eq!(filter.max_level(), Level::Off);
+    }
+
+    #[test]
+    fn filter_per_day() {
+        let mut filter = Filter::new();
+        filter.set_default(Level::Warn);
+        filter.set_day(18, Level::Trace);
+        filter.set_day(20, Level::Off);
+
+        assert!(filter.enabled(Some(1), Level::Warn));
+        assert!(!filter.enabled(Some(1), Level::Info));
+        assert!(filter.enabled(Some(18), Level::Trace));
+        assert!(!filter.enabled(Some(20), Level::Error));
+        assert!(filter.enabled(None, Level::Error));
+        assert_eq!(filter.max_level(), Level::Trace);
+
+        filter.set_day(18, Level::Info);
+        assert!(!filter.enabled(Some(18), Level::Debug));
+    }
+}
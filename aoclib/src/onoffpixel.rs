@@ -0,0 +1,27 @@
+use std::fmt;
+
+// A pixel in the puzzles' usual picture format, where # is on and . is off.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum OnOffPixel {
+    On,
+    Off,
+}
+
+impl OnOffPixel {
+    pub fn parse(ch : char) -> OnOffPixel {
+        if ch == '#' {
+            OnOffPixel::On
+        } else {
+            OnOffPixel::Off
+        }
+    }
+}
+
+impl fmt::Display for OnOffPixel {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match *self {
+            OnOffPixel::On => '#',
+            OnOffPixel::Off => '.',
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Describes where puzzle input failed to parse and what was wrong there. Lines and columns count
+// from 1, the way an editor shows them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub line : usize,
+    pub column : usize,
+    pub expected : String,
+    pub found : String,
+}
+
+impl ParseError {
+    pub fn new(line : usize, column : usize, expected : &str, found : &str) -> ParseError {
+        ParseError {
+            line,
+            column,
+            expected : String::from(expected),
+            found : String::from(found),
+        }
+    }
+
+    // For parsers that look at one line at a time. found should be a slice of line, so that its
+    // column can be worked out. The line number is filled in later with on_line.
+    pub fn in_line(line : &str, found : &str, expected : &str) -> ParseError {
+        ParseError::new(1, column_of(line, found), expected, found)
+    }
+
+    // Places an error from a parser that only saw part of a line at its column in the whole line.
+    pub fn within(mut self, line : &str, part : &str) -> ParseError {
+        self.column += column_of(line, part) - 1;
+        self
+    }
+
+    // Places an error from a single-line parser at its line in the whole input.
+    pub fn on_line(mut self, line_number : usize) -> ParseError {
+        self.line = line_number;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: expected {}, found ", self.line, self.column, self.expected)?;
+        if self.found.is_empty() {
+            write!(f, "end of line")
+        } else {
+            write!(f, "\"{}\"", self.found)
+        }
+    }
+}
+
+// The 1-based column where part starts in line, if part was sliced out of line. Otherwise there's
+// no way to know, so it's reported at the start of the line.
+pub fn column_of(line : &str, part : &str) -> usize {
+    let line_start = line.as_ptr() as usize;
+    let part_start = part.as_ptr() as usize;
+
+    if part_start >= line_start && part_start + part.len() <= line_start + line.len() {
+        line[.. part_start - line_start].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+pub fn parse_field<T>(line : &str, field : &str, expected : &str) -> Result<T, ParseError>
+    where T : FromStr {
+    field.parse::<T>().map_err(|_| ParseError::in_line(line, field, expected))
+}
+
+// Parses each line of the input on its own, stopping at the first error.
+pub fn parse_lines<'t, T, F>(input : &'t str, mut parse_line : F) -> Result<Vec<T>, ParseError>
+    where F : FnMut(&'t str) -> Result<T, ParseError> {
+    input.lines().enumerate().map(|(i, line)| {
+        parse_line(line).map_err(|e| e.on_line(i + 1))
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn column() {
+        let line = "abc def";
+        assert_eq!(column_of(line, &line[4..]), 5);
+        assert_eq!(column_of(line, &line[0..3]), 1);
+        assert_eq!(column_of(line, &line[7..]), 8);
+        assert_eq!(column_of(line, "def"), 1);
+    }
+
+    #[test]
+    fn nested() {
+        let line = "p=<1,2,x>";
+        let vector = &line[3..8];
+        let error = parse_field::<i32>(vector, &vector[4..], "a number").unwrap_err();
+        assert_eq!(error.column, 5);
+        assert_eq!(error.within(line, vector).on_line(4), ParseError::new(4, 8, "a number", "x"));
+    }
+
+    #[test]
+    fn field() {
+        let line = "set a 12x";
+        assert_eq!(parse_field::<i32>(line, &line[6..], "a number"), Err(ParseError::new(1, 7, "a number", "12x")));
+        assert_eq!(parse_field::<i32>(line, &line[6..8], "a number"), Ok(12));
+    }
+
+    #[test]
+    fn lines() {
+        let parse = |line : &str| parse_field::<u32>(line, line, "a number");
+        assert_eq!(parse_lines("1\n2\n3", parse), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_lines("1\n2\nx", parse), Err(ParseError::new(3, 1, "a number", "x")));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ParseError::new(3, 5, "a register", "Q").to_string(), "line 3, column 5: expected a register, found \"Q\"");
+        assert_eq!(ParseError::new(1, 9, "a number", "").to_string(), "line 1, column 9: expected a number, found end of line");
+    }
+}
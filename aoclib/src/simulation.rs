@@ -0,0 +1,196 @@
+// Something that advances one step at a time, like a program being executed or a grid being
+// transformed. The state is whatever the puzzle cares about at the current step, often just a
+// count.
+pub trait Simulation {
+    type State;
+
+    // Advances by one step. Returns false if the simulation has already finished, in which case
+    // nothing changes.
+    fn step(&mut self) -> bool;
+
+    fn state(&self) -> Self::State;
+
+    // Takes up to the given number of steps, returning how many were actually taken.
+    fn run_for(&mut self, steps : usize) -> usize {
+        let mut taken = 0;
+        while taken < steps && self.step() {
+            taken += 1;
+        }
+        taken
+    }
+
+    // Steps until the state satisfies pred, which is checked before every step, including the
+    // first. Returns how many steps were taken, or None if the simulation finished first.
+    fn run_until<P>(&mut self, mut pred : P) -> Option<usize>
+        where P : FnMut(&Self::State) -> bool {
+        let mut taken = 0;
+        loop {
+            if pred(&self.state()) {
+                return Some(taken);
+            }
+
+            if !self.step() {
+                return None;
+            }
+
+            taken += 1;
+        }
+    }
+
+    // Steps until the simulation finishes, returning how many steps were taken.
+    fn run_to_end(&mut self) -> usize {
+        self.run_for(usize::MAX)
+    }
+
+    // The state after each step, for anything that wants to look at the whole history, like
+    // cycle detection.
+    fn states<'s>(&'s mut self) -> States<'s, Self>
+        where Self : Sized {
+        States {
+            simulation : self,
+        }
+    }
+
+    // Wraps the simulation so that it keeps track of how many steps it has taken in total, no
+    // matter how they were taken.
+    fn counted(self) -> Counted<Self>
+        where Self : Sized {
+        Counted {
+            simulation : self,
+            steps : 0,
+        }
+    }
+}
+
+pub struct Counted<S> {
+    simulation : S,
+    steps : usize,
+}
+
+impl<S> Counted<S> {
+    // How many steps have been taken since the simulation was wrapped.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.simulation
+    }
+
+    pub fn into_inner(self) -> S {
+        self.simulation
+    }
+}
+
+impl<S> Simulation for Counted<S>
+    where S : Simulation {
+    type State = S::State;
+
+    fn step(&mut self) -> bool {
+        let stepped = self.simulation.step();
+        if stepped {
+            self.steps += 1;
+        }
+        stepped
+    }
+
+    fn state(&self) -> S::State {
+        self.simulation.state()
+    }
+}
+
+pub struct States<'s, S : 's> {
+    simulation : &'s mut S,
+}
+
+impl<'s, S> Iterator for States<'s, S>
+    where S : Simulation {
+    type Item = S::State;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.simulation.step() {
+            Some(self.simulation.state())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cycle;
+
+    // Counts up to a limit, wrapping around to zero at the given modulus.
+    struct Counter {
+        value : u32,
+        modulus : u32,
+        remaining : usize,
+    }
+
+    impl Counter {
+        fn new(modulus : u32, remaining : usize) -> Counter {
+            Counter {
+                value : 0,
+                modulus,
+                remaining,
+            }
+        }
+    }
+
+    impl Simulation for Counter {
+        type State = u32;
+
+        fn step(&mut self) -> bool {
+            if self.remaining > 0 {
+                self.remaining -= 1;
+                self.value = (self.value + 1) % self.modulus;
+                true
+            } else {
+                false
+            }
+        }
+
+        fn state(&self) -> u32 {
+            self.value
+        }
+    }
+
+    #[test]
+    fn run_for() {
+        let mut counter = Counter::new(100, 10);
+        assert_eq!(counter.run_for(4), 4);
+        assert_eq!(counter.state(), 4);
+        assert_eq!(counter.run_for(10), 6);
+        assert_eq!(counter.state(), 10);
+        assert_eq!(counter.run_to_end(), 0);
+    }
+
+    #[test]
+    fn run_until() {
+        let mut counter = Counter::new(100, 10);
+        assert_eq!(counter.run_until(|&value| value == 0), Some(0));
+        assert_eq!(counter.run_until(|&value| value == 7), Some(7));
+        assert_eq!(counter.run_until(|&value| value == 10), Some(3));
+        assert_eq!(counter.run_until(|&value| value == 11), None);
+    }
+
+    #[test]
+    fn states() {
+        let mut counter = Counter::new(3, 5);
+        assert_eq!(counter.states().collect::<Vec<u32>>(), vec![1, 2, 0, 1, 2]);
+        assert_eq!(cycle::find_in(Counter::new(3, 100).states()), Some((0, 3)));
+    }
+
+    #[test]
+    fn counted() {
+        let mut counter = Counter::new(100, 10).counted();
+        counter.run_for(3);
+        assert!(counter.step());
+        assert_eq!(counter.run_until(|&value| value == 6), Some(2));
+        assert_eq!(counter.states().count(), 4);
+        assert!(!counter.step());
+        assert_eq!(counter.steps(), 10);
+        assert_eq!(counter.into_inner().state(), 10);
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+use std::collections::BTreeMap;
+
+use parse_error::ParseError;
+
+// Every puzzle has two halves, and most days share parsing and simulation code between them.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Part {
+    A,
+    B,
+}
+
+impl Part {
+    pub fn parse(input : &str) -> Option<Part> {
+        match input {
+            "a" | "A" => Some(Part::A),
+            "b" | "B" => Some(Part::B),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Part::A => "a",
+            Part::B => "b",
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// The answer to one part of a puzzle. Besides the answer itself, which is what gets submitted,
+// some puzzles compute other interesting values along the way that tooling can report.
+pub trait Answer : fmt::Display {
+    fn secondary_values(&self) -> Vec<(&'static str, String)> {
+        vec![]
+    }
+}
+
+macro_rules! impl_plain_answer {
+    ($($t:ty),*) => {
+        $(impl Answer for $t {})*
+    };
+}
+
+impl_plain_answer!(u32, u64, usize, i32, i64, String);
+
+// Wraps a plain answer with some secondary values, for days that don't need their own answer type.
+pub struct Annotated<T> {
+    pub answer : T,
+    pub secondary : Vec<(&'static str, String)>,
+}
+
+impl<T> Annotated<T> {
+    pub fn new(answer : T) -> Annotated<T> {
+        Annotated {
+            answer,
+            secondary : vec![],
+        }
+    }
+
+    pub fn with<V>(mut self, name : &'static str, value : V) -> Annotated<T>
+        where V : fmt::Display {
+        self.secondary.push((name, value.to_string()));
+        self
+    }
+}
+
+impl<T> fmt::Display for Annotated<T>
+    where T : fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.answer.fmt(f)
+    }
+}
+
+impl<T> Answer for Annotated<T>
+    where T : fmt::Display {
+    fn secondary_values(&self) -> Vec<(&'static str, String)> {
+        self.secondary.clone()
+    }
+}
+
+// An answer after type erasure.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Solved {
+    pub answer : String,
+    pub secondary : Vec<(&'static str, String)>,
+}
+
+impl Solved {
+    fn from<A>(answer : A) -> Solved
+        where A : Answer {
+        Solved {
+            answer : answer.to_string(),
+            secondary : answer.secondary_values(),
+        }
+    }
+}
+
+// Why a day couldn't come up with an answer.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SolveError {
+    // The input was malformed.
+    Parse(ParseError),
+
+    // The input was fine, but it has no answer, such as a program that never does what the
+    // puzzle asks about. Describes what was missing.
+    NoAnswer(String),
+}
+
+impl From<ParseError> for SolveError {
+    fn from(error : ParseError) -> SolveError {
+        SolveError::Parse(error)
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Parse(error) => write!(f, "parse error: {}", error),
+            SolveError::NoAnswer(missing) => write!(f, "no answer: {}", missing),
+        }
+    }
+}
+
+// Implemented once per day. The answer types are whatever is natural for the puzzle; they only
+// need to be printable so that tooling can show and compare them. Malformed input is reported as a
+// SolveError rather than a panic.
+pub trait Solution {
+    type AnswerA : Answer;
+    type AnswerB : Answer;
+
+    fn day(&self) -> u32;
+
+    // Most days want surrounding whitespace stripped from the input, but some (like day 19's
+    // ASCII-art routing table) depend on leading spaces.
+    fn trims_input(&self) -> bool {
+        true
+    }
+
+    fn solve_a(&self, input : &str) -> Result<Self::AnswerA, SolveError>;
+    fn solve_b(&self, input : &str) -> Result<Self::AnswerB, SolveError>;
+}
+
+// Solution can't be made into a trait object because every day has different answer types, so
+// this is the type-erased view of it that the registry stores.
+pub trait AnySolution {
+    fn day(&self) -> u32;
+    fn trims_input(&self) -> bool;
+    fn solve_detailed(&self, part : Part, input : &str) -> Result<Solved, SolveError>;
+
+    fn solve(&self, part : Part, input : &str) -> Result<String, SolveError> {
+        self.solve_detailed(part, input).map(|solved| solved.answer)
+    }
+}
+
+impl<S> AnySolution for S
+    where S : Solution {
+    fn day(&self) -> u32 {
+        Solution::day(self)
+    }
+
+    fn trims_input(&self) -> bool {
+        Solution::trims_input(self)
+    }
+
+    fn solve_detailed(&self, part : Part, input : &str) -> Result<Solved, SolveError> {
+        match part {
+            Part::A => self.solve_a(input).map(Solved::from),
+            Part::B => self.solve_b(input).map(Solved::from),
+        }
+    }
+}
+
+pub struct Registry {
+    solutions : BTreeMap<u32, Box<dyn AnySolution>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry {
+            solutions : BTreeMap::new(),
+        }
+    }
+
+    pub fn register<S>(&mut self, solution : S)
+        where S : Solution + 'static {
+        let day = Solution::day(&solution);
+        if self.solutions.insert(day, Box::new(solution)).is_some() {
+            panic!("day {} registered twice", day);
+        }
+    }
+
+    pub fn get(&self, day : u32) -> Option<&dyn AnySolution> {
+        self.solutions.get(&day).map(|solution| solution.as_ref())
+    }
+
+    // Iterates in order of day number.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn AnySolution> {
+        self.solutions.values().map(|solution| solution.as_ref())
+    }
+
+    pub fn days(&self) -> Vec<u32> {
+        self.solutions.keys().cloned().collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Doubler;
+
+    impl Solution for Doubler {
+        type AnswerA = u32;
+        type AnswerB = Annotated<String>;
+
+        fn day(&self) -> u32 {
+            7
+        }
+
+        fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+            let num = input.parse::<u32>().map_err(|_| ParseError::new(1, 1, "a number", input))?;
+            if num == 0 {
+                return Err(SolveError::NoAnswer(String::from("a nonzero number")));
+            }
+
+            Ok(num * 2)
+        }
+
+        fn solve_b(&self, input : &str) -> Result<Annotated<String>, SolveError> {
+            Ok(Annotated::new(format!("{}{}", input, input)).with("length", input.len() * 2))
+        }
+    }
+
+    #[test]
+    fn part_parse() {
+        assert_eq!(Part::parse("a"), Some(Part::A));
+        assert_eq!(Part::parse("B"), Some(Part::B));
+        assert_eq!(Part::parse("c"), None);
+    }
+
+    #[test]
+    fn registry_dispatch() {
+        let mut registry = Registry::new();
+        registry.register(Doubler);
+
+        assert_eq!(registry.days(), vec![7]);
+        assert!(registry.get(8).is_none());
+
+        let solution = registry.get(7).unwrap();
+        assert_eq!(solution.solve(Part::A, "21"), Ok(String::from("42")));
+        assert_eq!(solution.solve(Part::A, "x"), Err(SolveError::Parse(ParseError::new(1, 1, "a number", "x"))));
+        assert_eq!(solution.solve(Part::A, "0"), Err(SolveError::NoAnswer(String::from("a nonzero number"))));
+        assert_eq!(solution.solve(Part::B, "21"), Ok(String::from("2121")));
+        assert_eq!(solution.solve_detailed(Part::A, "21").unwrap().secondary, vec![]);
+        assert_eq!(solution.solve_detailed(Part::B, "21"), Ok(Solved {
+            answer : String::from("2121"),
+            secondary : vec![("length", String::from("4"))],
+        }));
+        assert!(solution.trims_input());
+    }
+
+    #[test]
+    #[should_panic]
+    fn registry_duplicate() {
+        let mut registry = Registry::new();
+        registry.register(Doubler);
+        registry.register(Doubler);
+    }
+}
@@ -1,6 +1,7 @@
 extern crate aoclib;
+use aoclib::solution::{Solution, SolveError};
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     let (sum, _) = input.chars().fold((0, input.chars().last().expect("empty string")), |(running_sum, last_char), c| {
         //println!("sum: {}, last: {:?}", running_sum, last_char);
 
@@ -16,7 +17,7 @@ fn solve_a(input : &str) -> u32 {
     sum
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     let digits : Vec<u8> = input.chars().map(|c| { c.to_digit(10).expect("failed to parse digit") as u8 }).collect();
     let lookahead_offset = digits.len() / 2;
 
@@ -29,17 +30,23 @@ fn solve_b(input : &str) -> u32 {
     })
 }
 
-fn main() {
-    let input = aoclib::read_all_stdin();
+pub struct Solver;
 
-    let answer =
-        if aoclib::should_solve_puzzle_a() {
-            solve_a(&input)
-        } else {
-            solve_b(&input)
-        };
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        1
+    }
 
-    println!("answer: {}", answer);
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
+    }
 }
 
 #[cfg(test)]
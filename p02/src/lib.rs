@@ -1,7 +1,7 @@
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::solution::{Solution, SolveError};
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     input.lines().fold(0u32, |sum, line| {
         let (min, max) = aoclib::parse_nums::<u32>(&line)
             .fold((None, None), |(min_opt, max_opt), num| {
@@ -22,20 +22,20 @@ fn solve_a(input : &str) -> u32 {
         let min = min.expect("somehow got None min");
         let max = max.expect("somehow got None max");
 
-        eprintln!("min: {}, max: {}", min, max);
+        trace!("min: {}, max: {}", min, max);
 
         sum + (max - min)
     })
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     input.lines().fold(0u32, |sum, line| {
         let row_nums : Vec<u32> = aoclib::parse_nums::<u32>(&line).collect();
         let divided = row_nums.iter().enumerate().fold(None, |divided_opt, (i, &num1)| {
             match divided_opt {
                 None => {
                     match row_nums.iter().skip(i + 1).find(|&num2| {
-                        eprintln!("checking {} divisible by {}", num1, num2);
+                        trace!("checking {} divisible by {}", num1, num2);
                         num1 % num2 == 0 || num2 % num1 == 0
                     }) {
                         None => None,
@@ -50,20 +50,28 @@ fn solve_b(input : &str) -> u32 {
             }
         }).expect("didn't find divided num");
 
-        eprintln!("div: {}", divided);
+        trace!("div: {}", divided);
 
         sum + divided
     })
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        2
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -1,8 +1,9 @@
 extern crate aoclib;
-use aoclib::*;
+use aoclib::grid::Cells;
+use aoclib::grid::sparse::SparseGrid;
+use aoclib::solution::{Solution, SolveError};
 
 use std::f32;
-use std::collections::HashMap;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct Pos {
@@ -67,7 +68,7 @@ impl Iterator for SpiralWalker {
 }
 
 struct MemoryFiller {
-    mem : HashMap<(i32, i32), u32>,
+    mem : SparseGrid<u32>,
     walker : SpiralWalker,
     max_x : i32,
 }
@@ -75,7 +76,7 @@ struct MemoryFiller {
 impl MemoryFiller {
     fn new() -> MemoryFiller {
         let ret = MemoryFiller {
-            mem : HashMap::<(i32, i32), u32>::new(),
+            mem : SparseGrid::new(0),
             walker : pos(1, 0, 0).spiral_iter(),
             max_x : 0,
         };
@@ -87,16 +88,16 @@ impl MemoryFiller {
     }
 
     fn get(&self, p : &(i32, i32)) -> &u32 {
-        self.mem.get(p).unwrap_or(&0)
+        self.mem.at(p.0, p.1)
     }
 
     fn set(&mut self, p : &(i32, i32), v : u32) {
-        let _ = self.mem.insert(p.clone(), v);
+        self.mem.set(p.0, p.1, v);
     }
 
     fn purge_from_ring_start(&mut self, p : &(i32, i32)) {
         assert!(p.0 > 0);
-        self.mem.retain(|&(x, _), _| {
+        self.mem.retain(|(x, _), _| {
             x.abs() < p.0
         });
     }
@@ -171,13 +172,13 @@ fn get_position_of_address(address : u32) -> Pos {
 // 42 21  22  23  24  25 26
 // 43 44  45  46  47  48 49
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     let address = aoclib::parse_nums::<u32>(&input).nth(0).expect("failed to parse input");
     let p = get_position_of_address(address);
     (p.x.abs() + p.y.abs()) as u32
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     let target_value = aoclib::parse_nums::<u32>(&input).nth(0).expect("failed to parse input");
     let mut mem = MemoryFiller::new();
     mem.find(|&(ref _pos, stored_value)| {
@@ -185,14 +186,22 @@ fn solve_b(input : &str) -> u32 {
     }).unwrap().1
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        3
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -1,5 +1,7 @@
+use std::fmt;
+
 extern crate aoclib;
-use aoclib::*;
+use aoclib::solution::{Answer, Solution, SolveError};
 
 fn are_words_anagrams(word1 : &str, word2 : &str) -> bool {
     let mut w1 : Vec<char> = word1.chars().collect();
@@ -42,30 +44,62 @@ fn solve<F>(input : &str, is_valid_line : F) -> (u32, u32)
     })
 }
 
-fn solve_a(input : &str) -> (u32, u32) {
+pub fn solve_a(input : &str) -> (u32, u32) {
     solve(input, |line| {
         !has_two_same_words(line)
     })
 }
 
-fn solve_b(input : &str) -> (u32, u32) {
+pub fn solve_b(input : &str) -> (u32, u32) {
     solve(input, |line| {
         !has_anagrams(line)
     })
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+// The puzzle answer is the number of valid passphrases, but the total is handy context.
+pub struct PassphraseCount {
+    pub total : u32,
+    pub valid : u32,
+}
 
-    let (total, valid) =
-        if aoclib::should_solve_puzzle_a() {
-            solve_a(&input)
-        } else {
-            solve_b(&input)
-        };
+impl PassphraseCount {
+    fn from((total, valid) : (u32, u32)) -> PassphraseCount {
+        PassphraseCount {
+            total,
+            valid,
+        }
+    }
+}
 
-    println!("{} out of {} are valid", valid, total);
+impl fmt::Display for PassphraseCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.valid)
+    }
+}
+
+impl Answer for PassphraseCount {
+    fn secondary_values(&self) -> Vec<(&'static str, String)> {
+        vec![("total", self.total.to_string())]
+    }
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = PassphraseCount;
+    type AnswerB = PassphraseCount;
+
+    fn day(&self) -> u32 {
+        4
+    }
+
+    fn solve_a(&self, input : &str) -> Result<PassphraseCount, SolveError> {
+        Ok(PassphraseCount::from(solve_a(input)))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<PassphraseCount, SolveError> {
+        Ok(PassphraseCount::from(solve_b(input)))
+    }
 }
 
 #[cfg(test)]
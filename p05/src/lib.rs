@@ -1,5 +1,6 @@
 extern crate aoclib;
-use aoclib::*;
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
 
 struct Instruction {
     offset : i32,
@@ -55,50 +56,56 @@ impl Program {
     }
 }
 
-impl Iterator for Program {
-    type Item = i32;
+impl Simulation for Program {
+    type State = i32;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn step(&mut self) -> bool {
         if self.ip >= 0 && self.ip < (self.instructions.len() as i32) {
-            {
-                let instruction = self.instructions.get_mut(self.ip as usize).unwrap();
-                self.ip = self.ip +
-                    if self.mode_a {
-                        instruction.take_jump_a()
-                    } else {
-                        instruction.take_jump_b()
-                    };
-            }
-
-            if self.ip >= 0 && self.ip < (self.instructions.len() as i32) {
-                Some(self.ip)
-            } else {
-                None
-            }
+            let instruction = self.instructions.get_mut(self.ip as usize).unwrap();
+            self.ip = self.ip +
+                if self.mode_a {
+                    instruction.take_jump_a()
+                } else {
+                    instruction.take_jump_b()
+                };
+
+            true
         } else {
-            None
+            false
         }
     }
+
+    fn state(&self) -> i32 {
+        self.ip
+    }
 }
 
-fn solve_a(input : &str) -> u32 {
-    let program = Program::from_input(input, true);
-    (program.count() + 1) as u32
+pub fn solve_a(input : &str) -> u32 {
+    let mut program = Program::from_input(input, true);
+    program.run_to_end() as u32
 }
 
-fn solve_b(input : &str) -> u32 {
-    let program = Program::from_input(input, false);
-    (program.count() + 1) as u32
+pub fn solve_b(input : &str) -> u32 {
+    let mut program = Program::from_input(input, false);
+    program.run_to_end() as u32
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        5
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
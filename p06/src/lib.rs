@@ -1,7 +1,8 @@
 extern crate aoclib;
-use aoclib::*;
+use aoclib::cycle;
+use aoclib::solution::{Solution, SolveError};
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 struct State {
     mem : Vec<u32>,
 }
@@ -37,41 +38,32 @@ impl State {
     }
 }
 
-fn solve_a(input : &str) -> u32 {
-    let mut states : Vec<State> = vec![State::from_input(input)];
-
-    while !aoclib::any_eq(states.iter().take(states.len() - 1), &states.last().unwrap()) {
-        let redist = states.last().unwrap().redistribute();
-        states.push(redist);
-    }
+pub fn solve_a(input : &str) -> u32 {
+    let (prefix_length, cycle_length) = cycle::find(State::from_input(input), State::redistribute);
+    (prefix_length + cycle_length) as u32
+}
 
-    (states.len() - 1) as u32
+pub fn solve_b(input : &str) -> u32 {
+    let (_, cycle_length) = cycle::find(State::from_input(input), State::redistribute);
+    cycle_length as u32
 }
 
-fn solve_b(input : &str) -> u32 {
-    let mut states : Vec<State> = vec![State::from_input(input)];
+pub struct Solver;
 
-    let mut pos = aoclib::position_eq(states.iter().take(states.len() - 1), &states.last().unwrap());
-    while pos.is_none() {
-        //eprintln!("{:?}", states.last().unwrap());
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
 
-        let redist = states.last().unwrap().redistribute();
-        states.push(redist);
-        pos = aoclib::position_eq(states.iter().take(states.len() - 1), &states.last().unwrap());
+    fn day(&self) -> u32 {
+        6
     }
 
-    //eprintln!("{:?}", states.last().unwrap());
-    (states.len() - pos.unwrap() - 1) as u32
-}
-
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
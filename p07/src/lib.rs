@@ -9,8 +9,9 @@ use std::fmt;
 extern crate regex;
 use regex::Regex;
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::{ParseError, parse_field, parse_lines};
+use aoclib::solution::{Annotated, Solution, SolveError};
 
 // Stores just the raw parsed data from the input, not directly linked to
 // supported programs.
@@ -162,14 +163,14 @@ impl<'t> ProgDb<'t> {
         }
     }
 
-    fn from_input(input : &'t str) -> ProgDb<'t> {
+    fn from_input(input : &'t str) -> Result<ProgDb<'t>, ParseError> {
         let mut pdb = ProgDb::new();
 
-        for line in input.lines() {
-            pdb.load_prog(parse_prog(line))
+        for prog_info in parse_lines(input, parse_prog)? {
+            pdb.load_prog(prog_info)
         }
 
-        pdb
+        Ok(pdb)
     }
 
     // Take a ProgInfo and add it to the map, either creating a new object or
@@ -232,28 +233,33 @@ impl<'t> ProgDb<'t> {
     }
 }
 
-fn parse_prog<'t>(line : &'t str) -> ProgInfo<'t> {
+fn parse_prog<'t>(line : &'t str) -> Result<ProgInfo<'t>, ParseError> {
     lazy_static! {
         static ref RE_PROG_INFO : regex::Regex = Regex::new(r"^(\w+) \((\d+)\)").expect("failed to compile regex");
         static ref RE_PROG_CHILDREN : regex::Regex = Regex::new(r",? (\w+)").expect("failed to compile regex");
     }
 
-    let prog_info_captures = RE_PROG_INFO.captures_iter(line).nth(0).unwrap();
+    let prog_info_captures = RE_PROG_INFO.captures(line).ok_or_else(|| {
+        ParseError::in_line(line, line, "a program like \"name (weight)\"")
+    })?;
+
+    let weight = prog_info_captures.get(2).unwrap().as_str();
 
     let children_captures_iter = RE_PROG_CHILDREN.captures_iter(line);
 
-    ProgInfo {
+    Ok(ProgInfo {
         name : prog_info_captures.get(1).unwrap().as_str(),
-        weight : prog_info_captures[2].parse::<u32>().unwrap(),
+        weight : parse_field(line, weight, "a weight")?,
         child_names : children_captures_iter.map(|caps| {
             caps.get(1).unwrap().as_str()
         }).collect(),
-    }
+    })
 }
 
-fn solve_a<'t>(input : &'t str) -> &'t str {
-    let db = ProgDb::from_input(input);
-    db.get_root().borrow().name
+pub fn solve_a<'t>(input : &'t str) -> Result<&'t str, ParseError> {
+    let db = ProgDb::from_input(input)?;
+    let name = db.get_root().borrow().name;
+    Ok(name)
 }
 
 // Searches for an unbalanced program under this one. It only picks from a child program or
@@ -264,12 +270,12 @@ fn find_unbalanced_child_program<'t>(program : &RcRefProg<'t>, current_depth : u
     // to balance each one.
     let (subtree0, subtree1) = program.borrow().find_unbalanced_subtrees(current_depth, weight_adjustment);
 
-    eprintln!("find_unbalanced_child_program {}, depth {}, adj {}, trees ({}, {})", program.borrow(), current_depth, weight_adjustment, subtree0.is_some(), subtree1.is_some());
+    trace!("find_unbalanced_child_program {}, depth {}, adj {}, trees ({}, {})", program.borrow(), current_depth, weight_adjustment, subtree0.is_some(), subtree1.is_some());
 
     // Helper function to search a subtree only if the weight adjustment would balance the tree if
     // applied to it.
     let find_unbalanced_program_in_subtree = |subtree : FoundProg<'t>| {
-        eprintln!("find_unbalanced_program_in_subtree {}, adj {}", subtree.prog.borrow(), subtree.weight_adjustment);
+        trace!("find_unbalanced_program_in_subtree {}, adj {}", subtree.prog.borrow(), subtree.weight_adjustment);
         if current_depth == 0 || subtree.weight_adjustment == weight_adjustment {
             // If the weight adjustment would fix this subtree, then either we pick something
             // deeper in the subtree or that program itself.
@@ -302,32 +308,40 @@ fn find_unbalanced_child_program<'t>(program : &RcRefProg<'t>, current_depth : u
     }
 }
 
-fn solve_b_with_program<'t>(input : &'t str) -> (MaybeFoundProg<'t>, u32) {
-    let db = ProgDb::from_input(input);
+fn solve_b_with_program<'t>(input : &'t str) -> Result<(FoundProg<'t>, u32), SolveError> {
+    let db = ProgDb::from_input(input)?;
     let root = db.get_root();
 
-    find_unbalanced_child_program(&root, 0, 0).map_or_else(|| {
-        panic!("failed to find unbalanced program");
-    },
-    |found_program| {
+    find_unbalanced_child_program(&root, 0, 0).map(|found_program| {
         let weight = ((found_program.prog.borrow().weight as i32) + found_program.weight_adjustment) as u32;
-        eprintln!("Found unbalanced program as {} at depth {}, with weight adjustment {}", found_program.prog.borrow(), found_program.depth, found_program.weight_adjustment);
-        (Some(found_program), weight)
-    })
+        debug!("Found unbalanced program as {} at depth {}, with weight adjustment {}", found_program.prog.borrow(), found_program.depth, found_program.weight_adjustment);
+        (found_program, weight)
+    }).ok_or_else(|| SolveError::NoAnswer(String::from("an unbalanced program")))
 }
 
-fn solve_b(input : &str) -> u32 {
-    solve_b_with_program(input).1
+pub fn solve_b(input : &str) -> Result<u32, SolveError> {
+    solve_b_with_program(input).map(|(_, weight)| weight)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = String;
+    type AnswerB = Annotated<u32>;
+
+    fn day(&self) -> u32 {
+        7
+    }
+
+    fn solve_a(&self, input : &str) -> Result<String, SolveError> {
+        Ok(String::from(solve_a(input)?))
+    }
+
+    // Also report which program had to be reweighted and how deep in the tower it is.
+    fn solve_b(&self, input : &str) -> Result<Annotated<u32>, SolveError> {
+        let (found_program, weight) = solve_b_with_program(input)?;
+        let name = found_program.prog.borrow().name;
+        Ok(Annotated::new(weight).with("program", name).with("depth", found_program.depth))
     }
 }
 
@@ -338,21 +352,32 @@ mod test {
     #[test]
     fn parse_no_children() {
         let input = "pbga (66)";
-        assert_eq!(parse_prog(&input), ProgInfo {
+        assert_eq!(parse_prog(&input), Ok(ProgInfo {
             name: "pbga",
             weight: 66,
             child_names: vec![],
-        });
+        }));
     }
 
     #[test]
     fn parse_with_children() {
         let input = "fwft (72) -> ktlj, cntj, xhth";
-        assert_eq!(parse_prog(&input), ProgInfo {
+        assert_eq!(parse_prog(&input), Ok(ProgInfo {
             name: "fwft",
             weight: 72,
             child_names: vec!["ktlj", "cntj", "xhth"],
-        });
+        }));
+    }
+
+    #[test]
+    fn parse_bad_weight() {
+        let input = "a (1) -> b\nb (99999999999)";
+        assert_eq!(ProgDb::from_input(input).err(), Some(ParseError::new(2, 4, "a weight", "99999999999")));
+    }
+
+    #[test]
+    fn parse_missing_weight() {
+        assert_eq!(parse_prog("fwft -> ktlj"), Err(ParseError::new(1, 1, "a program like \"name (weight)\"", "fwft -> ktlj")));
     }
 
     #[test]
@@ -362,7 +387,7 @@ r"b (1)
 c (2)
 d (3)
 a (100) -> b, c, d";
-        let db = ProgDb::from_input(input);
+        let db = ProgDb::from_input(input).unwrap();
         assert_eq!(db.get("a").unwrap().borrow().weight, 100);
         assert_eq!(db.get("b").unwrap().borrow().weight, 1);
         assert_eq!(db.get("c").unwrap().borrow().weight, 2);
@@ -376,7 +401,7 @@ r"a (100) -> b, c, d
 b (1)
 c (2)
 d (3)";
-        let db = ProgDb::from_input(input);
+        let db = ProgDb::from_input(input).unwrap();
         assert_eq!(db.get("a").unwrap().borrow().weight, 100);
         assert_eq!(db.get("b").unwrap().borrow().weight, 1);
         assert_eq!(db.get("c").unwrap().borrow().weight, 2);
@@ -391,7 +416,7 @@ r"a (100) -> b, c, d
 b (1)
 c (2)
 d (3)";
-        let db = ProgDb::from_input(input);
+        let db = ProgDb::from_input(input).unwrap();
         assert_eq!(db.get("a").unwrap().borrow().get_subtree_weight(), 106);
         assert_eq!(db.get("b").unwrap().borrow().get_subtree_weight(), 1);
         assert_eq!(db.get("c").unwrap().borrow().get_subtree_weight(), 2);
@@ -407,7 +432,7 @@ c (3) -> d
 d (4) -> e
 e (5) -> f
 f (6)";
-        let db = ProgDb::from_input(input);
+        let db = ProgDb::from_input(input).unwrap();
         assert_eq!(db.get("a").unwrap().borrow().get_subtree_weight(), 21);
         assert_eq!(db.get("b").unwrap().borrow().get_subtree_weight(), 20);
         assert_eq!(db.get("f").unwrap().borrow().get_subtree_weight(), 6);
@@ -420,7 +445,7 @@ r"a (100) -> b, c, d
 b (1)
 c (2)
 d (3)";
-        assert_eq!(solve_a(input), "a");
+        assert_eq!(solve_a(input), Ok("a"));
     }
 
     #[test]
@@ -430,7 +455,7 @@ r"b (1)
 c (2)
 d (3)
 a (100) -> b, c, d";
-        assert_eq!(solve_a(input), "a");
+        assert_eq!(solve_a(input), Ok("a"));
     }
 
     #[test]
@@ -442,7 +467,7 @@ d (4) -> e
 c (3) -> d
 b (2) -> c
 a (1) -> b";
-        assert_eq!(solve_a(input), "a");
+        assert_eq!(solve_a(input), Ok("a"));
     }
 
     #[test]
@@ -454,12 +479,12 @@ c (3) -> d
 d (4) -> e
 e (5) -> f
 f (6)";
-        assert_eq!(solve_a(input), "a");
+        assert_eq!(solve_a(input), Ok("a"));
     }
 
     fn solve_b_test(input : &str, expected_program_name : &str, expected_weight : u32) {
-        let (found_prog, weight) = solve_b_with_program(input);
-        assert_eq!(found_prog.unwrap().prog.borrow().name, expected_program_name);
+        let (found_prog, weight) = solve_b_with_program(input).unwrap();
+        assert_eq!(found_prog.prog.borrow().name, expected_program_name);
         assert_eq!(weight, expected_weight);
     }
 
@@ -700,4 +725,13 @@ abbb (1)
 ac (11)";
         solve_b_test(&input, "aa", 5);
     }
+
+    #[test]
+    fn b_balanced() {
+        let input =
+r"a (1) -> aa, ab
+aa (2)
+ab (2)";
+        assert_eq!(solve_b(input), Err(SolveError::NoAnswer(String::from("an unbalanced program"))));
+    }
 }
@@ -7,8 +7,10 @@ use std::fmt;
 extern crate regex;
 use regex::Regex;
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::{ParseError, parse_field, parse_lines};
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
 
 enum ModifyOperation {
     Inc,
@@ -16,11 +18,11 @@ enum ModifyOperation {
 }
 
 impl ModifyOperation {
-    fn from(s : &str) -> ModifyOperation {
+    fn from(s : &str) -> Result<ModifyOperation, ParseError> {
         match s {
-            "inc" => ModifyOperation::Inc,
-            "dec" => ModifyOperation::Dec,
-            _ => panic!("unknown modify operation {}", s)
+            "inc" => Ok(ModifyOperation::Inc),
+            "dec" => Ok(ModifyOperation::Dec),
+            _ => Err(ParseError::in_line(s, s, "inc or dec")),
         }
     }
 }
@@ -45,15 +47,15 @@ enum ConditionOperation {
 }
 
 impl ConditionOperation {
-    fn from(s : &str) -> ConditionOperation {
+    fn from(s : &str) -> Result<ConditionOperation, ParseError> {
         match s {
-            "<" => ConditionOperation::LessThan,
-            ">" => ConditionOperation::GreaterThan,
-            "==" => ConditionOperation::Equal,
-            "<=" => ConditionOperation::LessThanEqual,
-            ">=" => ConditionOperation::GreaterThanEqual,
-            "!=" => ConditionOperation::NotEqual,
-            _ => panic!("unknown condition operation {}", s)
+            "<" => Ok(ConditionOperation::LessThan),
+            ">" => Ok(ConditionOperation::GreaterThan),
+            "==" => Ok(ConditionOperation::Equal),
+            "<=" => Ok(ConditionOperation::LessThanEqual),
+            ">=" => Ok(ConditionOperation::GreaterThanEqual),
+            "!=" => Ok(ConditionOperation::NotEqual),
+            _ => Err(ParseError::in_line(s, s, "a comparison")),
         }
     }
 
@@ -95,20 +97,25 @@ struct Instruction<'t> {
 }
 
 impl<'t> Instruction<'t> {
-    fn from(line : &'t str) -> Instruction<'t> {
+    fn from(line : &'t str) -> Result<Instruction<'t>, ParseError> {
         lazy_static! {
-            static ref RE_INSTRUCTION : regex::Regex = Regex::new(r"^(\w+) (inc|dec) (-?\d+) if (\w+) ([<>=!]=?) (-?\d+)$").expect("failed to compile regex");
+            static ref RE_INSTRUCTION : regex::Regex = Regex::new(r"^(\w+) (\w+) (\S+) if (\w+) (\S+) (\S+)$").expect("failed to compile regex");
         }
 
-        let captures = RE_INSTRUCTION.captures_iter(line).nth(0).unwrap();
-        Instruction {
-            modified_register : captures.get(1).unwrap().as_str(),
-            modify_op : ModifyOperation::from(captures.get(2).unwrap().as_str()),
-            modify_by : captures[3].parse::<i32>().unwrap(),
-            condition_register : captures.get(4).unwrap().as_str(),
-            condition_op : ConditionOperation::from(captures.get(5).unwrap().as_str()),
-            condition_val : captures[6].parse::<i32>().unwrap(),
-        }
+        let captures = RE_INSTRUCTION.captures(line).ok_or_else(|| {
+            ParseError::in_line(line, line, "an instruction like \"a inc 1 if b > 0\"")
+        })?;
+
+        let field = |i| captures.get(i).unwrap().as_str();
+
+        Ok(Instruction {
+            modified_register : field(1),
+            modify_op : ModifyOperation::from(field(2)).map_err(|e| e.within(line, field(2)))?,
+            modify_by : parse_field(line, field(3), "a number")?,
+            condition_register : field(4),
+            condition_op : ConditionOperation::from(field(5)).map_err(|e| e.within(line, field(5)))?,
+            condition_val : parse_field(line, field(6), "a number")?,
+        })
     }
 }
 impl<'t> fmt::Display for Instruction<'t> {
@@ -122,12 +129,10 @@ struct Program<'t> {
 }
 
 impl<'t> Program<'t> {
-    fn from(input : &'t str) -> Program<'t> {
-        Program {
-            instructions : input.lines().map(|line| {
-                Instruction::from(line)
-            }).collect(),
-        }
+    fn from(input : &'t str) -> Result<Program<'t>, ParseError> {
+        Ok(Program {
+            instructions : parse_lines(input, Instruction::from)?,
+        })
     }
 
     fn run<'a>(&'a self) -> ProgramExecution<'a, 't> {
@@ -167,10 +172,10 @@ impl<'a, 't> ProgramExecution<'a, 't> {
     }
 }
 
-impl<'a, 't> Iterator for ProgramExecution<'a, 't> {
-    type Item = i32;
+impl<'a, 't> Simulation for ProgramExecution<'a, 't> {
+    type State = i32;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn step(&mut self) -> bool {
         self.instructions.next().map(|instruction| {
             let condition_reg_value = self.registers.get(instruction.condition_register).unwrap_or(&0);
             if instruction.condition_op.apply(*condition_reg_value, instruction.condition_val) {
@@ -181,32 +186,44 @@ impl<'a, 't> Iterator for ProgramExecution<'a, 't> {
 
                 self.inc(instruction.modified_register, by);
             }
+        }).is_some()
+    }
 
-            self.max_value()
-        })
+    fn state(&self) -> i32 {
+        self.max_value()
     }
 }
 
-fn solve_a(input : &str) -> i32 {
-    let prog = Program::from(input);
-    eprintln!("{}", prog);
-    prog.run().last().unwrap_or(i32::min_value())
+pub fn solve_a(input : &str) -> Result<i32, ParseError> {
+    let prog = Program::from(input)?;
+    debug!("{}", prog);
+    let mut execution = prog.run();
+    execution.run_to_end();
+    Ok(execution.state())
 }
 
-fn solve_b(input : &str) -> i32 {
-    let prog = Program::from(input);
-    eprintln!("{}", prog);
-    prog.run().max().unwrap_or(i32::min_value())
+pub fn solve_b(input : &str) -> Result<i32, ParseError> {
+    let prog = Program::from(input)?;
+    debug!("{}", prog);
+    Ok(prog.run().states().max().unwrap_or(i32::min_value()))
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = i32;
+    type AnswerB = i32;
+
+    fn day(&self) -> u32 {
+        8
+    }
+
+    fn solve_a(&self, input : &str) -> Result<i32, SolveError> {
+        Ok(solve_a(input)?)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<i32, SolveError> {
+        Ok(solve_b(input)?)
     }
 }
 
@@ -221,7 +238,7 @@ r"b inc 5 if a > 1
 a inc 1 if b < 5
 c dec -10 if a >= 1
 c inc -20 if c == 10";
-        assert_eq!(solve_a(&input), 1);
+        assert_eq!(solve_a(&input), Ok(1));
     }
 
     #[test]
@@ -230,7 +247,7 @@ c inc -20 if c == 10";
 r"b inc 1 if b <= 1
 b inc 1 if b <= 1
 b inc 1 if b <= 1";
-        assert_eq!(solve_a(&input), 2);
+        assert_eq!(solve_a(&input), Ok(2));
     }
 
     #[test]
@@ -239,7 +256,7 @@ b inc 1 if b <= 1";
 r"b inc 1 if b != 1
 b inc 5 if b != 1
 b inc 6 if b != 0";
-        assert_eq!(solve_a(&input), 7);
+        assert_eq!(solve_a(&input), Ok(7));
     }
 
     #[test]
@@ -249,6 +266,14 @@ r"b inc 5 if a > 1
 a inc 1 if b < 5
 c dec -10 if a >= 1
 c inc -20 if c == 10";
-        assert_eq!(solve_b(&input), 10);
+        assert_eq!(solve_b(&input), Ok(10));
+    }
+
+    #[test]
+    fn bad_instructions() {
+        assert_eq!(solve_a("a inc 1 if b > 0\nb mul 2 if a > 0"), Err(ParseError::new(2, 3, "inc or dec", "mul")));
+        assert_eq!(solve_a("a inc 1 if b > 0\nb inc x if a > 0"), Err(ParseError::new(2, 7, "a number", "x")));
+        assert_eq!(solve_a("a inc 1 if b =< 0"), Err(ParseError::new(1, 14, "a comparison", "=<")));
+        assert_eq!(solve_b("a inc 1"), Err(ParseError::new(1, 1, "an instruction like \"a inc 1 if b > 0\"", "a inc 1")));
     }
 }
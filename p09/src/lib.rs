@@ -1,5 +1,5 @@
 extern crate aoclib;
-use aoclib::*;
+use aoclib::solution::{Annotated, Solution, SolveError};
 
 enum State {
     Normal,
@@ -71,24 +71,42 @@ impl<'t> Iterator for GroupCounter<'t> {
     }
 }
 
-fn solve_a(input : &str) -> u32 {
+fn count_groups(input : &str) -> (u32, u32) {
     let counter = GroupCounter::over(input);
-    counter.last().unwrap_or((0, 0)).0
+    counter.last().unwrap_or((0, 0))
 }
 
-fn solve_b(input : &str) -> u32 {
-    let counter = GroupCounter::over(input);
-    counter.last().unwrap_or((0, 0)).1
+pub fn solve_a(input : &str) -> u32 {
+    count_groups(input).0
+}
+
+pub fn solve_b(input : &str) -> u32 {
+    count_groups(input).1
+}
+
+// Both parts come out of the same pass over the stream, so each reports the other as well.
+fn annotate(answer : u32, (score, garbage_count) : (u32, u32)) -> Annotated<u32> {
+    Annotated::new(answer).with("score", score).with("garbage_count", garbage_count)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = Annotated<u32>;
+    type AnswerB = Annotated<u32>;
+
+    fn day(&self) -> u32 {
+        9
+    }
+
+    fn solve_a(&self, input : &str) -> Result<Annotated<u32>, SolveError> {
+        let counts = count_groups(input);
+        Ok(annotate(counts.0, counts))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<Annotated<u32>, SolveError> {
+        let counts = count_groups(input);
+        Ok(annotate(counts.1, counts))
     }
 }
 
@@ -1,7 +1,9 @@
 extern crate aoclib;
-use aoclib::*;
+use aoclib::solution::{Solution, SolveError};
 
-fn solve_a(input : &str, ring_size : u32) -> u32 {
+const RING_SIZE : u32 = 256;
+
+pub fn solve_a(input : &str, ring_size : u32) -> u32 {
     let mut ring = aoclib::knot_hash::Ring::new(ring_size);
 
     input.split(',').map(|num_str| {
@@ -9,18 +11,26 @@ fn solve_a(input : &str, ring_size : u32) -> u32 {
     }).last().unwrap() as u32
 }
 
-fn solve_b(input : &str) -> String {
+pub fn solve_b(input : &str) -> String {
     aoclib::knot_hash::knot_hash_as_hex(input)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = String;
+
+    fn day(&self) -> u32 {
+        10
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input, RING_SIZE))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input, 256));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<String, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -3,7 +3,7 @@
 use std::cmp::Ordering;
 
 extern crate aoclib;
-use aoclib::*;
+use aoclib::solution::{Solution, SolveError};
 
 struct HexMover<'t> {
     pos : (i32, i32),
@@ -109,7 +109,7 @@ impl Iterator for HexSeeker {
     }
 }
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     let mover = HexMover::new(input);
     let target = mover.last().unwrap();
 
@@ -117,7 +117,7 @@ fn solve_a(input : &str) -> u32 {
     seeker.count() as u32
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     let mover = HexMover::new(input);
 
     mover.map(|pos| {
@@ -125,14 +125,22 @@ fn solve_b(input : &str) -> u32 {
     }).max().or(Some(0)).unwrap()
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        11
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
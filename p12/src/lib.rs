@@ -0,0 +1,240 @@
+#![feature(nll)]
+
+use std::collections::HashSet;
+
+#[macro_use] extern crate lazy_static;
+extern crate regex;
+use regex::Regex;
+
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::{ParseError, parse_field, parse_lines};
+use aoclib::solution::{Solution, SolveError};
+
+// Stores just the raw parsed data from the input, not directly linked to
+// supported programs.
+#[derive(PartialEq, Debug)]
+struct ProgInfo {
+    name : u32,
+    link_names : Vec<u32>,
+}
+
+struct ProgDb {
+    db : Vec<ProgInfo>,
+}
+
+impl ProgDb {
+    fn new() -> ProgDb {
+        ProgDb {
+            db : vec![],
+        }
+    }
+
+    // Programs are looked up by number, so they have to be listed in order from 0, and every link
+    // has to be to one of them.
+    fn from_input(input : &str) -> Result<ProgDb, ParseError> {
+        let num_progs = input.lines().count();
+        if num_progs == 0 {
+            return Err(ParseError::new(1, 1, PROG_EXAMPLE, ""));
+        }
+
+        let mut pdb = ProgDb::new();
+        let mut next_name = 0;
+        pdb.db = parse_lines(input, |line| {
+            let prog = parse_prog(line, next_name, num_progs as u32)?;
+            next_name += 1;
+            Ok(prog)
+        })?;
+        Ok(pdb)
+    }
+
+    fn find_all_connected(&self, prog : u32) -> HashSet<u32> {
+        let mut connected = HashSet::new();
+
+        fn visit(this : &ProgDb, connected : &mut HashSet<u32>, prog : &ProgInfo) {
+            if connected.insert(prog.name) {
+                for link_name in prog.link_names.iter() {
+                    visit(this, connected, &this.db[*link_name as usize]);
+                }
+            }
+        }
+
+        visit(self, &mut connected, &self.db[prog as usize]);
+
+        connected
+    }
+}
+
+const PROG_EXAMPLE : &str = "a program like \"2 <-> 0, 3, 4\"";
+
+fn parse_prog(line : &str, name : u32, num_progs : u32) -> Result<ProgInfo, ParseError> {
+    lazy_static! {
+        static ref RE_PROG_INFO : regex::Regex = Regex::new(r"^(\d+) <->").expect("failed to compile regex");
+        static ref RE_PROG_LINKS : regex::Regex = Regex::new(r",? (\d+)").expect("failed to compile regex");
+    }
+
+    let prog_info_captures = RE_PROG_INFO.captures(line).ok_or_else(|| {
+        ParseError::in_line(line, line, PROG_EXAMPLE)
+    })?;
+
+    let name_field = prog_info_captures.get(1).unwrap().as_str();
+    if parse_field::<u32>(line, name_field, "a program number")? != name {
+        return Err(ParseError::in_line(line, name_field, &format!("program {}", name)));
+    }
+
+    let link_captures_iter = RE_PROG_LINKS.captures_iter(line);
+
+    Ok(ProgInfo {
+        name,
+        link_names : link_captures_iter.map(|caps| {
+            let link_field = caps.get(1).unwrap().as_str();
+            match parse_field(line, link_field, "a program number")? {
+                link if link < num_progs => Ok(link),
+                _ => Err(ParseError::in_line(line, link_field, &format!("a program from 0 to {}", num_progs - 1))),
+            }
+        }).collect::<Result<Vec<u32>, ParseError>>()?,
+    })
+}
+
+fn find_connected_to(input : &str, prog : u32) -> Result<HashSet<u32>, ParseError> {
+    let pdb = ProgDb::from_input(input)?;
+    let connected = pdb.find_all_connected(prog);
+    debug!("connected: {:?}", connected);
+    Ok(connected)
+}
+
+pub fn solve_a(input : &str) -> Result<u32, ParseError> {
+    find_connected_to(input, 0).map(|connected| connected.len() as u32)
+}
+
+pub fn solve_b(input : &str) -> Result<u32, ParseError> {
+    let pdb = ProgDb::from_input(input)?;
+
+    let mut all_sets : Vec<HashSet<u32>> = vec![];
+
+    for prog in pdb.db.iter() {
+        if !all_sets.iter().any(|set| {
+            set.contains(&prog.name)
+        }) {
+            all_sets.push(pdb.find_all_connected(prog.name))
+        }
+    }
+
+    Ok(all_sets.len() as u32)
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        12
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input)?)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_connected_to(input : &str, prog : u32, expected_connected : &Vec<u32>) {
+        use std::iter::FromIterator;
+        let connected = find_connected_to(input, prog).unwrap();
+        let expected_connected = HashSet::from_iter(expected_connected.iter().map(|n| *n));
+        assert_eq!(connected, expected_connected);
+    }
+
+    #[test]
+    fn a_given() {
+        let input =
+r"0 <-> 2
+1 <-> 1
+2 <-> 0, 3, 4
+3 <-> 2, 4
+4 <-> 2, 3, 6
+5 <-> 6
+6 <-> 4, 5";
+
+        test_connected_to(input, 0, &vec![0, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn a_alone() {
+        let input =
+r"0 <-> 0
+1 <-> 1
+2 <-> 3, 4
+3 <-> 2, 4
+4 <-> 2, 3, 6
+5 <-> 6
+6 <-> 4, 5";
+
+        test_connected_to(input, 0, &vec![0]);
+    }
+
+    #[test]
+    fn a_ring() {
+        let input =
+r"0 <-> 1, 5
+1 <-> 0, 2
+2 <-> 1, 3
+3 <-> 2, 4
+4 <-> 3, 5
+5 <-> 0, 4
+6 <-> 6";
+
+        test_connected_to(input, 0, &vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn b_given() {
+        let input =
+r"0 <-> 2
+1 <-> 1
+2 <-> 0, 3, 4
+3 <-> 2, 4
+4 <-> 2, 3, 6
+5 <-> 6
+6 <-> 4, 5";
+        assert_eq!(solve_b(&input), Ok(2));
+    }
+
+    #[test]
+    fn b_singles() {
+        let input =
+r"0 <-> 0
+1 <-> 1
+2 <-> 2";
+        assert_eq!(solve_b(&input), Ok(3));
+    }
+
+    #[test]
+    fn b_pairs() {
+        let input =
+r"0 <-> 1
+1 <-> 0
+2 <-> 3
+3 <-> 2
+4 <-> 5
+5 <-> 4";
+        assert_eq!(solve_b(&input), Ok(3));
+    }
+
+    #[test]
+    fn bad_program() {
+        assert_eq!(solve_b("0 <-> 1\n1 -> 0"), Err(ParseError::new(2, 1, "a program like \"2 <-> 0, 3, 4\"", "1 -> 0")));
+        assert_eq!(solve_b("0 <-> 1\n99999999999 <-> 0"), Err(ParseError::new(2, 1, "a program number", "99999999999")));
+        assert_eq!(solve_a("0 <-> 5"), Err(ParseError::new(1, 7, "a program from 0 to 0", "5")));
+        assert_eq!(solve_a("0 <-> 1\n1 <-> 0, 2"), Err(ParseError::new(2, 10, "a program from 0 to 1", "2")));
+        assert_eq!(solve_b("0 <-> 0\n2 <-> 2\n1 <-> 1"), Err(ParseError::new(2, 1, "program 1", "2")));
+        assert_eq!(solve_a(""), Err(ParseError::new(1, 1, "a program like \"2 <-> 0, 3, 4\"", "")));
+    }
+}
@@ -1,7 +1,9 @@
 #![feature(nll)]
 
 extern crate aoclib;
-use aoclib::*;
+use aoclib::parse_error::{ParseError, parse_field, parse_lines};
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
 
 struct Layer {
     depth : u32,
@@ -19,18 +21,25 @@ struct PacketPasser<'t> {
     caught : bool,
 }
 
-impl Firewall {
-    fn from(input : &str) -> Firewall {
-        let mut layers = vec![];
+impl Layer {
+    fn from(line : &str) -> Result<Layer, ParseError> {
+        let split : Vec<&str> = line.splitn(2, ": ").collect();
+        if split.len() != 2 {
+            return Err(ParseError::in_line(line, line, "a layer like \"depth: range\""));
+        }
 
-        for line in input.lines() {
-            let split : Vec<&str> = line.split(": ").collect();
+        Ok(Layer {
+            depth : parse_field(line, split[0], "a depth")?,
+            range : parse_field(line, split[1], "a range")?,
+        })
+    }
+}
 
-            let layer = Layer {
-                depth : split[0].parse::<u32>().unwrap(),
-                range : split[1].parse::<u32>().unwrap(),
-            };
+impl Firewall {
+    fn from(input : &str) -> Result<Firewall, ParseError> {
+        let mut layers = vec![];
 
+        for layer in parse_lines(input, Layer::from)? {
             while layers.len() < (layer.depth as usize) {
                 layers.push(None);
             }
@@ -38,9 +47,9 @@ impl Firewall {
             layers.push(Some(layer));
         }
 
-        Firewall {
+        Ok(Firewall {
             layers : layers,
-        }
+        })
     }
 
     fn simulate<'t>(&'t self, start_t : u32) -> PacketPasser<'t> {
@@ -53,7 +62,9 @@ impl Firewall {
     }
 
     fn calculate_severity(&self, start_t : u32) -> u32 {
-        self.simulate(start_t).last().unwrap().0
+        let mut passer = self.simulate(start_t);
+        passer.run_to_end();
+        passer.state().0
     }
 
     fn wrap_scanner(t : u32, range : u32) -> u32 {
@@ -77,10 +88,10 @@ impl Firewall {
     }
 }
 
-impl<'t> Iterator for PacketPasser<'t> {
-    type Item = (u32, bool);
+impl<'t> Simulation for PacketPasser<'t> {
+    type State = (u32, bool);
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn step(&mut self) -> bool {
         self.firewall.next().map(|layer| {
             if let Some(layer) = layer.as_ref() {
                 if Firewall::wrap_scanner(self.t, layer.range) == 0 {
@@ -90,36 +101,46 @@ impl<'t> Iterator for PacketPasser<'t> {
             }
 
             self.t += 1;
+        }).is_some()
+    }
 
-            (self.cost, self.caught)
-        })
+    fn state(&self) -> (u32, bool) {
+        (self.cost, self.caught)
     }
 }
 
 
-fn solve_a(input : &str) -> u32 {
-    let firewall = Firewall::from(input);
-    firewall.calculate_severity(0)
+pub fn solve_a(input : &str) -> Result<u32, ParseError> {
+    let firewall = Firewall::from(input)?;
+    Ok(firewall.calculate_severity(0))
 }
 
-fn solve_b(input : &str) -> u32 {
-    let firewall = Firewall::from(input);
+pub fn solve_b(input : &str) -> Result<u32, ParseError> {
+    let firewall = Firewall::from(input)?;
 
-    (0 .. u32::max_value()).find(|&start_t| {
-        !firewall.simulate(start_t).any(|(_, caught)| {
+    Ok((0 .. u32::max_value()).find(|&start_t| {
+        firewall.simulate(start_t).run_until(|&(_, caught)| {
             caught
-        })
-    }).unwrap()
+        }).is_none()
+    }).unwrap())
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        13
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input)?)
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input)?)
     }
 }
 
@@ -164,7 +185,7 @@ r"0: 3
 1: 2
 4: 4
 6: 4";
-        assert_eq!(solve_a(&input), 24);
+        assert_eq!(solve_a(&input), Ok(24));
     }
 
     #[test]
@@ -176,7 +197,7 @@ r"0: 1
 3: 1
 4: 1
 5: 1";
-        assert_eq!(solve_a(&input), 15);
+        assert_eq!(solve_a(&input), Ok(15));
     }
 
     #[test]
@@ -188,7 +209,7 @@ r"0: 1
 3: 4
 4: 5
 5: 6";
-        assert_eq!(solve_a(&input), 0);
+        assert_eq!(solve_a(&input), Ok(0));
     }
 
     #[test]
@@ -198,6 +219,12 @@ r"0: 3
 1: 2
 4: 4
 6: 4";
-        assert_eq!(solve_b(&input), 10);
+        assert_eq!(solve_b(&input), Ok(10));
+    }
+
+    #[test]
+    fn bad_layers() {
+        assert_eq!(solve_a("0: 3\n1 2"), Err(ParseError::new(2, 1, "a layer like \"depth: range\"", "1 2")));
+        assert_eq!(solve_a("0: 3\n1: two"), Err(ParseError::new(2, 4, "a range", "two")));
     }
 }
@@ -1,7 +1,9 @@
 #![feature(nll)]
 
 extern crate aoclib;
-use aoclib::*;
+use aoclib::grid::Grid;
+use aoclib::grid::regions::Connectivity;
+use aoclib::solution::{Solution, SolveError};
 
 struct DiskRows<'t> {
     seed : &'t str,
@@ -83,60 +85,40 @@ impl<'t> Iterator for DiskRowIterator<'t> {
     }
 }
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     let rows = DiskRows::new(input, 128);
     rows.fold(0u32, |bits_used, row| {
         bits_used + row.iter_bits().map(|b| b as u32).sum::<u32>()
     })
 }
 
-fn solve_b(input : &str) -> u32 {
-    let mut grid = aoclib::grid::Grid::<u32>::new();
+pub fn solve_b(input : &str) -> u32 {
+    let mut grid = Grid::<u32>::new();
     let rows = DiskRows::new(input, 128);
 
     for row in rows {
         grid.add_row(row.iter_bits().map(|v| v as u32).collect());
     }
 
-    fn spread(grid : &mut aoclib::grid::Grid<u32>, x : usize, y : usize, old : u32, new : u32) {
-        if *grid.get(x, y).unwrap() == old {
-            *grid.get_mut(x, y).unwrap() = new;
-
-            if y > 0 {
-                spread(grid, x + 0, y - 1, old, new);
-            }
+    grid.components(Connectivity::Four, |&v| v == 1).regions.len() as u32
+}
 
-            if x < grid.size_x() - 1 {
-                spread(grid, x + 1, y + 0, old, new);
-            }
+pub struct Solver;
 
-            if y < grid.size_y() - 1 {
-                spread(grid, x + 0, y + 1, old, new);
-            }
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
 
-            if x > 0 {
-                spread(grid, x - 1, y + 0, old, new);
-            }
-        }
-    };
-
-    let mut num_islands = 1;
-    while let Some(((found_x, found_y), _)) = grid.enumerate().find(|&(_, v)| *v == 1) {
-        num_islands += 1;
-        spread(&mut grid, found_x, found_y, 1, num_islands);
+    fn day(&self) -> u32 {
+        14
     }
 
-    num_islands - 1
-}
-
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -5,7 +5,7 @@ extern crate regex;
 use regex::Regex;
 
 extern crate aoclib;
-use aoclib::*;
+use aoclib::solution::{Solution, SolveError};
 
 const GENERATOR_A_FACTOR : u32 = 16807;
 const GENERATOR_B_FACTOR : u32 = 48271;
@@ -72,22 +72,30 @@ fn count_matches_b(input : &str, num_rounds : usize) -> usize {
     count_matches(gen_a, gen_b, num_rounds)
 }
 
-fn solve_a(input : &str) -> usize {
+pub fn solve_a(input : &str) -> usize {
     count_matches_a(input, NUM_ROUNDS_A as usize)
 }
 
-fn solve_b(input : &str) -> usize {
+pub fn solve_b(input : &str) -> usize {
     count_matches_b(input, NUM_ROUNDS_B as usize)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = usize;
+    type AnswerB = usize;
+
+    fn day(&self) -> u32 {
+        15
+    }
+
+    fn solve_a(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
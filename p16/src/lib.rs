@@ -1,14 +1,15 @@
 #![feature(nll)]
 
 use std::fmt;
-use std::time::Instant;
 
 #[macro_use] extern crate lazy_static;
 extern crate regex;
 use regex::Regex;
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::cycle;
+use aoclib::parse_error::{ParseError, parse_field};
+use aoclib::solution::{Solution, SolveError};
 
 const NUM_DANCERS : u8 = 16;
 const BITS_PER_DANCER : u64 = 4;
@@ -42,10 +43,6 @@ trait Performance : Iterator {
         }
         result
     }
-
-    fn is_starting_positions(&self) -> bool {
-        false
-    }
 }
 
 struct PerformanceString<'t> {
@@ -65,15 +62,26 @@ struct PerformanceInt<'t> {
 }
 
 impl DanceMove {
-    fn dancer_name_to_number(dancer : &str) -> u8 {
-        dancer.bytes().nth(0).unwrap() - ('a' as u8)
+    fn dancer_name_to_number(line : &str, dancer : &str) -> Result<u8, ParseError> {
+        match dancer.as_bytes() {
+            &[name] if name >= b'a' && name < b'a' + NUM_DANCERS => Ok(name - b'a'),
+            _ => Err(ParseError::in_line(line, dancer, "a dancer name from a to p")),
+        }
+    }
+
+    fn position(line : &str, position : &str) -> Result<u8, ParseError> {
+        match parse_field(line, position, "a position")? {
+            number if number < NUM_DANCERS => Ok(number),
+            _ => Err(ParseError::in_line(line, position, "a position from 0 to 15")),
+        }
     }
 
     fn dancer_number_to_name(dancer : u8) -> char {
         (('a' as u8) + dancer) as char
     }
 
-    fn from(input : &str) -> DanceMove {
+    // The whole dance is on one line, so line is used to report where in it a bad move is.
+    fn from(line : &str, input : &str) -> Result<DanceMove, ParseError> {
         lazy_static! {
             static ref RE_SPIN : regex::Regex = Regex::new(r"^s(\d+)$").expect("failed to compile regex");
             static ref RE_EXCHANGE : regex::Regex = Regex::new(r"^x(\d+)/(\d+)$").expect("failed to compile regex");
@@ -81,13 +89,13 @@ impl DanceMove {
         }
 
         if let Some(captures) = RE_SPIN.captures_iter(input).next() {
-            DanceMove::Spin(captures.get(1).unwrap().as_str().parse::<u32>().unwrap())
+            Ok(DanceMove::Spin(parse_field(line, captures.get(1).unwrap().as_str(), "a spin size")?))
         } else if let Some(captures) = RE_EXCHANGE.captures_iter(input).next() {
-            DanceMove::Exchange(captures.get(1).unwrap().as_str().parse::<u8>().unwrap(), captures.get(2).unwrap().as_str().parse::<u8>().unwrap())
+            Ok(DanceMove::Exchange(Self::position(line, captures.get(1).unwrap().as_str())?, Self::position(line, captures.get(2).unwrap().as_str())?))
         } else if let Some(captures) = RE_PARTNER.captures_iter(input).next() {
-            DanceMove::Partner(Self::dancer_name_to_number(captures.get(1).unwrap().as_str()), Self::dancer_name_to_number(captures.get(2).unwrap().as_str()))
+            Ok(DanceMove::Partner(Self::dancer_name_to_number(line, captures.get(1).unwrap().as_str())?, Self::dancer_name_to_number(line, captures.get(2).unwrap().as_str())?))
         } else {
-            panic!("invalid move {}", input);
+            Err(ParseError::in_line(line, input, "a dance move like s1, x3/4, or pe/b"))
         }
     }
 }
@@ -103,8 +111,10 @@ impl fmt::Display for DanceMove {
 }
 
 impl Dance {
-    fn from(moves : &str) -> Dance {
-        let moves = moves.split(',').map(DanceMove::from).filter(|step| {
+    fn from(moves : &str) -> Result<Dance, ParseError> {
+        let moves = moves.split(',').map(|step| DanceMove::from(moves, step)).collect::<Result<Vec<DanceMove>, ParseError>>()?;
+
+        let moves = moves.into_iter().filter(|step| {
             // Omit moves that do nothing
             match step {
                 &DanceMove::Spin(a) => a != 0,
@@ -113,9 +123,9 @@ impl Dance {
             }
         }).collect::<Vec<DanceMove>>();
 
-        Dance {
+        Ok(Dance {
             moves : moves,
-        }
+        })
     }
 
     fn perform(&self, num_dancers : u8) -> PerformanceString {
@@ -130,44 +140,31 @@ impl Dance {
         Self::finish_performance(self.perform(num_dancers), num_times)
     }
 
-    fn get_final_positions_int(&self, num_times : u64) -> String {
-        Self::finish_performance(self.perform_int(), num_times)
-    }
+    // Performing the whole dance many times repeats positions quickly, so most performances can
+    // be skipped.
+    fn get_final_positions_cycled(&self, num_times : u64) -> String {
+        let start = self.perform_int().dancer_at_position;
 
-    fn find_repeat_performance(&self, limit_times : u64) -> u64 {
-        let mut performance = self.perform_int();
-
-        let mut repeats_after_iterations = 1;
-        performance.finish();
-
-        while repeats_after_iterations < limit_times && !performance.is_starting_positions() {
-            repeats_after_iterations += 1;
-            performance.rewind();
+        let final_positions = cycle::state_after(start, |&dancers| {
+            let mut performance = PerformanceInt::from_positions(&self.moves, dancers);
             performance.finish();
-        }
+            performance.dancer_at_position
+        }, num_times as usize);
 
-        repeats_after_iterations
+        PerformanceInt::dancers_u64_to_string(&final_positions)
     }
 
     fn finish_performance<P>(mut performance : P, num_times : u64) -> String
     where P : Performance {
         performance.finish();
 
+        // Timing is left to the runner's bench mode; this just shows progress on long runs.
         const OUTPUT_ITERATIONS : u64 = 100000;
-        let mut iteration_counter = 0;
-        let mut period_time = Instant::now();
 
         //eprintln!("poses after 0: {}", final_positions);
         for i in 1 .. num_times {
-            iteration_counter += 1;
-            if iteration_counter == OUTPUT_ITERATIONS {
-                let elapsed = period_time.elapsed();
-                let elapsed_ms = ((elapsed.as_secs() * 1000) as u32) + (elapsed.subsec_nanos() / 1000000);
-
-                eprintln!("poses after {} of {}: {}. {} iterations per sec", i, num_times, performance.positions(), ((OUTPUT_ITERATIONS * 1000) as f32) / elapsed_ms as f32);
-
-                iteration_counter = 0;
-                period_time = Instant::now();
+            if i % OUTPUT_ITERATIONS == 0 {
+                info!("poses after {} of {}: {}", i, num_times, performance.positions());
             }
 
             performance.rewind();
@@ -206,11 +203,11 @@ impl Dance {
         self.collapse_spins();
 
         let pre_moves = self.moves.len();
-        eprintln!("before: {} moves", self.moves.len());
+        debug!("before: {} moves", self.moves.len());
         self.moves.retain(|step| {
             *step != DanceMove::Spin(0)
         });
-        eprintln!("removed {}: {} moves left", pre_moves - self.moves.len(), self.moves.len());
+        debug!("removed {}: {} moves left", pre_moves - self.moves.len(), self.moves.len());
         // eprintln!("{}", self);
 
         self.moves.len() != pre_moves
@@ -322,6 +319,13 @@ impl<'t> PerformanceInt<'t> {
         }
     }
 
+    fn from_positions(moves : &'t Vec<DanceMove>, dancer_at_position : u64) -> PerformanceInt<'t> {
+        let mut performance = PerformanceInt::new(moves);
+        performance.dancer_at_position = dancer_at_position;
+        performance.recompute_positions_for_dancers();
+        performance
+    }
+
     fn get_shift_for_position(position : u8) -> u32 {
         ((NUM_DANCERS - 1 - position) * (BITS_PER_DANCER as u8)) as u32
     }
@@ -366,10 +370,6 @@ impl<'t> Performance for PerformanceInt<'t> {
     fn rewind(&mut self) {
         self.position = 0;
     }
-
-    fn is_starting_positions(&self) -> bool {
-        self.dancer_at_position == 0x0123456789abcdef
-    }
 }
 
 impl<'t> Iterator for PerformanceInt<'t> {
@@ -402,46 +402,43 @@ impl<'t> Iterator for PerformanceInt<'t> {
     }
 }
 
-fn solve_a(input : &str) -> String {
-    let mut dance = Dance::from(input);
+pub fn solve_a(input : &str) -> Result<String, ParseError> {
+    let mut dance = Dance::from(input)?;
     let before = dance.get_final_positions(NUM_DANCERS, 1);
 
     dance.refine();
 
     let after = dance.get_final_positions(NUM_DANCERS, 1);
 
-    eprintln!("compare: {} and {}", before, after);
-    before
+    debug!("compare: {} and {}", before, after);
+    Ok(before)
 }
 
-fn solve_b(input : &str) -> String {
-    let mut dance = Dance::from(input);
+pub fn solve_b(input : &str) -> Result<String, ParseError> {
+    let mut dance = Dance::from(input)?;
     const NUM_TIMES_B : u64 = 1000000000;
-    const REPEAT_PERFORMANCE_SEARCH_LIMIT : u64 = 100;
 
     dance.refine();
 
-    let num_times;
-    let repeats_after_iterations = dance.find_repeat_performance(REPEAT_PERFORMANCE_SEARCH_LIMIT);
-    if repeats_after_iterations < REPEAT_PERFORMANCE_SEARCH_LIMIT {
-        num_times = NUM_TIMES_B % repeats_after_iterations;
-    } else {
-        num_times = NUM_TIMES_B;
-    }
+    Ok(dance.get_final_positions_cycled(NUM_TIMES_B))
+}
 
-    eprintln!("need to do {} iterations", num_times);
+pub struct Solver;
 
-    dance.get_final_positions_int(num_times)
-}
+impl Solution for Solver {
+    type AnswerA = String;
+    type AnswerB = String;
+
+    fn day(&self) -> u32 {
+        16
+    }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+    fn solve_a(&self, input : &str) -> Result<String, SolveError> {
+        Ok(solve_a(input)?)
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<String, SolveError> {
+        Ok(solve_b(input)?)
     }
 }
 
@@ -450,12 +447,12 @@ mod test {
     use super::*;
 
     fn test_dance_int_repeat(moves : &str, num_times : u64, expected_final_positions : &str) {
-        let dance = Dance::from(moves);
-        assert_eq!(dance.get_final_positions_int(num_times), expected_final_positions);
+        let dance = Dance::from(moves).unwrap();
+        assert_eq!(dance.get_final_positions_cycled(num_times), expected_final_positions);
     }
 
     fn test_dance_repeat(num_dancers : u8, moves : &str, num_times : u64, expected_final_positions : &str) {
-        let dance = Dance::from(moves);
+        let dance = Dance::from(moves).unwrap();
         assert_eq!(dance.get_final_positions(num_dancers, num_times), expected_final_positions);
     }
 
@@ -514,6 +511,14 @@ mod test {
         test_dance_int_repeat("s1,x0/1,pa/b", 3, "bnopacdefghijklm");
     }
 
+    #[test]
+    fn cycled() {
+        let dance = Dance::from("s1,x0/1,pa/b").unwrap();
+        for num_times in 1 .. 40 {
+            assert_eq!(dance.get_final_positions_cycled(num_times), dance.get_final_positions(NUM_DANCERS, num_times));
+        }
+    }
+
     #[test]
     fn a_given() {
         test_dance(5, "s1,x3/4,pe/b", "baedc");
@@ -523,6 +528,17 @@ mod test {
     fn b_given() {
         test_dance_repeat(5, "s1,x3/4,pe/b", 2, "ceadb");
     }
+
+    #[test]
+    fn bad_moves() {
+        assert_eq!(solve_a("s1,y3/4,pe/b").err(), Some(ParseError::new(1, 4, "a dance move like s1, x3/4, or pe/b", "y3/4")));
+        assert_eq!(solve_a("s1,x3/4,pE/b").err(), Some(ParseError::new(1, 10, "a dancer name from a to p", "E")));
+        assert_eq!(solve_a("s1,x3/4,pz/q").err(), Some(ParseError::new(1, 10, "a dancer name from a to p", "z")));
+        assert_eq!(solve_a("s1,x3/4,pa/q").err(), Some(ParseError::new(1, 12, "a dancer name from a to p", "q")));
+        assert_eq!(solve_b("s1,x3/400").err(), Some(ParseError::new(1, 7, "a position", "400")));
+        assert_eq!(solve_a("x3/20").err(), Some(ParseError::new(1, 4, "a position from 0 to 15", "20")));
+        assert_eq!(solve_b("s1,x16/4").err(), Some(ParseError::new(1, 5, "a position from 0 to 15", "16")));
+    }
 }
 
 // abcdefghijklmnop
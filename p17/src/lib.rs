@@ -1,7 +1,7 @@
 #![feature(nll)]
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::solution::{Solution, SolveError};
 
 struct CircularBuffer {
     state : Vec<u32>,
@@ -65,7 +65,7 @@ impl Iterator for CircularBuffer {
             ret = None;
         }
 
-        eprintln!("buf: {:?}", self.state);
+        trace!("buf: {:?}", self.state);
 
         ret
     }
@@ -105,26 +105,34 @@ fn get_num_after_zero(step_size : u32, max_insertions : u32) -> u32 {
     buf.last().unwrap()
 }
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     const MAX_INSERTIONS_A : u32 = 2017;
     let step_size = input.parse::<u32>().unwrap();
     get_num_after(step_size, MAX_INSERTIONS_A)
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     const MAX_INSERTIONS_B : u32 = 50000000;
     let step_size = input.parse::<u32>().unwrap();
     get_num_after_zero(step_size, MAX_INSERTIONS_B)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        17
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -0,0 +1,107 @@
+#![feature(nll)]
+
+#[macro_use] extern crate aoclib;
+use aoclib::aocisa::*;
+use aoclib::aocisa::bytecode::Bytecode;
+use aoclib::aocisa::dialect::{Interpreter, Sound};
+use aoclib::aocisa::scheduler::{Schedule, Scheduler};
+use aoclib::aocisa::source::Source;
+use aoclib::parse_error::ParseError;
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
+
+const NUM_EXECUTIONS_B : usize = 2;
+
+pub fn solve_a(input : &str) -> Result<i64, SolveError> {
+    let source = Source::parse(input)?;
+    debug!("prog: {}", source.program());
+
+    let mut sound = Interpreter::new(&source, Sound::default())?;
+    let steps = sound.run_to_end();
+    trace!("stopped after {} steps", steps);
+
+    // The program might leave without recovering anything, or recover before playing a sound.
+    sound.dialect().recovered.ok_or_else(|| {
+        SolveError::NoAnswer(String::from("a rcv that recovers a sound"))
+    })
+}
+
+pub fn solve_b(input : &str) -> Result<u32, ParseError> {
+    let prog = Program::load(input)?;
+    debug!("prog: {}", prog);
+
+    let code = Bytecode::compile(&prog);
+    let mut duet = Scheduler::ring(&code, NUM_EXECUTIONS_B, Schedule::UntilBlocked);
+    for i in 0 .. NUM_EXECUTIONS_B {
+        duet.machine_mut(i).set('p', i as i64);
+    }
+
+    duet.run();
+    debug!("duet ended: {}", duet.report());
+    Ok(duet.stats(1).sends as u32)
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = i64;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        18
+    }
+
+    fn solve_a(&self, input : &str) -> Result<i64, SolveError> {
+        solve_a(input)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn a_given() {
+        let input =
+r"set a 1
+add a 2
+mul a a
+mod a 5
+snd a
+set a 0
+rcv a
+jgz a -1
+set a 1
+jgz a -2";
+        assert_eq!(solve_a(&input), Ok(4));
+    }
+
+    #[test]
+    fn b_given() {
+        let input =
+r"snd 1
+snd 2
+snd p
+rcv a
+rcv b
+rcv c
+rcv d";
+        assert_eq!(solve_b(&input), Ok(3));
+    }
+
+    #[test]
+    fn a_never_recovers() {
+        let never = Err(SolveError::NoAnswer(String::from("a rcv that recovers a sound")));
+        assert_eq!(solve_a("set a 1\nsnd a"), never);
+        assert_eq!(solve_a("set a 1\nrcv a\nsnd a"), never);
+    }
+
+    #[test]
+    fn a_unsupported() {
+        assert_eq!(solve_a("set a 1\njnz a 2"), Err(SolveError::Parse(ParseError::new(2, 1, "one of snd, set, add, mul, mod, rcv, jgz", "jnz"))));
+    }
+}
@@ -2,9 +2,9 @@
 
 use std::fmt;
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
 use aoclib::direction::Direction;
+use aoclib::solution::{Solution, SolveError};
 
 struct RoutingTable {
     grid : Vec<Vec<char>>,
@@ -44,7 +44,7 @@ impl RoutingTable {
             x
         }).unwrap();
 
-        eprintln!("starting walk at ({}, 0)", starting_x);
+        debug!("starting walk at ({}, 0)", starting_x);
 
         RouteWalker::new(self, (starting_x, 0))
     }
@@ -106,34 +106,34 @@ impl<'t> Iterator for RouteWalker<'t> {
                     match self.position.dir {
                         Direction::Up | Direction::Down => {
                             if self.position.x > 0 && self.is_filled(self.position.x - 1, self.position.y) {
-                                eprintln!("{} -> left", self.position);
+                                trace!("{} -> left", self.position);
                                 self.position.dir = Direction::Left;
                             } else if self.position.x < self.table.grid[0].len() - 1 && self.is_filled(self.position.x + 1, self.position.y) {
-                                eprintln!("{} -> right", self.position);
+                                trace!("{} -> right", self.position);
                                 self.position.dir = Direction::Right;
                             }
                         },
                         Direction::Left | Direction::Right => {
                             if self.position.y > 0 && self.is_filled(self.position.x, self.position.y - 1) {
-                                eprintln!("{} -> up", self.position);
+                                trace!("{} -> up", self.position);
                                 self.position.dir = Direction::Up;
                             } else if self.position.y < self.table.grid.len() - 1 && self.is_filled(self.position.x, self.position.y + 1) {
-                                eprintln!("{} -> down", self.position);
+                                trace!("{} -> down", self.position);
                                 self.position.dir = Direction::Down;
                             }
                         },
                     }
                 },
                 ' ' => {
-                    eprintln!("{}", self.position);
-                    eprintln!("    Stepped on a blank. must be done");
+                    trace!("{}", self.position);
+                    debug!("    Stepped on a blank. must be done");
                     self.position.x = self.table.grid[0].len();
                     self.position.y = self.table.grid.len();
                 },
                 ch => {
                     self.collected_letters.push(ch);
-                    eprintln!("{}", self.position);
-                    eprintln!("    grabbed {}. now {}", ch, self.collected_letters);
+                    trace!("{}", self.position);
+                    debug!("    grabbed {}. now {}", ch, self.collected_letters);
                 }
             }
 
@@ -150,26 +150,39 @@ impl<'t> Iterator for RouteWalker<'t> {
     }
 }
 
-fn solve_a(input : &str) -> String {
+pub fn solve_a(input : &str) -> String {
     let routing_table = RoutingTable::load(input);
     let mut walker = routing_table.walk();
     aoclib::consume_iterator(&mut walker);
     walker.collected_letters.clone()
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     let routing_table = RoutingTable::load(input);
     routing_table.walk().count() as u32
 }
 
-fn main() {
-    let input = read_all_stdin_notrim();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = String;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        19
+    }
+
+    // The routing table is ASCII art, so leading spaces on the first line are significant.
+    fn trims_input(&self) -> bool {
+        false
+    }
+
+    fn solve_a(&self, input : &str) -> Result<String, SolveError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -8,8 +8,10 @@ use std::ops::Index;
 extern crate regex;
 use regex::Regex;
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::{ParseError, parse_field, parse_lines};
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
 
 #[derive(Debug, Copy, Clone)]
 enum Axis {
@@ -37,21 +39,24 @@ struct Particle {
 struct ParticleGroup {
     particles : Vec<Particle>,
     old_particles : Vec<Particle>,
+    finished : bool,
 }
 
 impl V3 {
-    fn from(input : &str) -> V3 {
+    fn from(input : &str) -> Result<V3, ParseError> {
         lazy_static! {
-            static ref RE_V3 : regex::Regex = Regex::new(r"^<(-?\d+),(-?\d+),(-?\d+)>$").expect("failed to compile regex");
+            static ref RE_V3 : regex::Regex = Regex::new(r"^<([^,]*),([^,]*),([^,]*)>$").expect("failed to compile regex");
         }
 
-        let captures = RE_V3.captures_iter(input).next().unwrap();
+        let captures = RE_V3.captures(input).ok_or_else(|| {
+            ParseError::in_line(input, input, "a vector like <1,-2,3>")
+        })?;
 
-        V3 {
-            x : captures.get(1).unwrap().as_str().parse::<i32>().unwrap(),
-            y : captures.get(2).unwrap().as_str().parse::<i32>().unwrap(),
-            z : captures.get(3).unwrap().as_str().parse::<i32>().unwrap(),
-        }
+        Ok(V3 {
+            x : parse_field(input, captures.get(1).unwrap().as_str(), "a number")?,
+            y : parse_field(input, captures.get(2).unwrap().as_str(), "a number")?,
+            z : parse_field(input, captures.get(3).unwrap().as_str(), "a number")?,
+        })
     }
 
     fn abs_sum(&self) -> i32 {
@@ -84,20 +89,27 @@ impl Index<Axis> for V3 {
 }
 
 impl Particle {
-    fn from(num : usize, input : &str) -> Particle {
+    fn from(num : usize, input : &str) -> Result<Particle, ParseError> {
         lazy_static! {
             static ref RE_PARTICLE : regex::Regex = Regex::new(r"^p=(.*), v=(.*), a=(.*)$").expect("failed to compile regex");
         }
 
-        let captures = RE_PARTICLE.captures_iter(input).next().unwrap();
+        let captures = RE_PARTICLE.captures(input).ok_or_else(|| {
+            ParseError::in_line(input, input, "a particle like \"p=<1,2,3>, v=<0,0,0>, a=<-1,0,1>\"")
+        })?;
+
+        let v3 = |i| {
+            let part = captures.get(i).unwrap().as_str();
+            V3::from(part).map_err(|e| e.within(input, part))
+        };
 
-        Particle {
+        Ok(Particle {
             num : num,
             valid : true,
-            p : V3::from(captures.get(1).unwrap().as_str()),
-            v : V3::from(captures.get(2).unwrap().as_str()),
-            a : V3::from(captures.get(3).unwrap().as_str()),
-        }
+            p : v3(1)?,
+            v : v3(2)?,
+            a : v3(3)?,
+        })
     }
 
     fn compare_by_reach_unstable(p1 : &Particle, p2 : &Particle) -> Ordering {
@@ -164,6 +176,7 @@ impl ParticleGroup {
         ParticleGroup {
             particles : particles,
             old_particles : vec![],
+            finished : false,
         }
     }
 
@@ -175,10 +188,15 @@ impl ParticleGroup {
     // impossible for any particle to collide anymore. The assumption is that, given enough time,
     // all particles would either collide and be removed from the simulation or be moving away
     // from each other (visualize a spiky ball, where the spikes are directions of particles
-    // moving).
-    fn next_debug(&mut self, debug : bool) -> Option<usize> {
-        // Always run at least one step of the simulation, to give a final answer to the caller
-        // even if the very first step knows no collisions are possible.
+    // moving). Once that's known, the particles are left where they are and every later step
+    // returns false.
+    fn step_debug(&mut self, debug : bool) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        // Never finish on the very first step, even if it already knows no collisions are
+        // possible.
         let must_ret = self.old_particles.is_empty();
         self.old_particles = self.particles.clone();
 
@@ -196,14 +214,14 @@ impl ParticleGroup {
             // Mark all particles that share the position with colliding_position as invalidated.
             for particle in self.particles.iter_mut().skip(i + 1) {
                 if particle.is_valid() && particle.p == colliding_position {
-                    eprintln!("collision: {}", particle);
+                    debug!("collision: {}", particle);
                     found_collision = true;
                     particle.invalidate();
                 }
             }
 
             if found_collision {
-                eprintln!("collision: {}", self.particles[i]);
+                debug!("collision: {}", self.particles[i]);
                 self.particles[i].invalidate();
             } else {
                 if !any_axis_speed_unstable {
@@ -212,7 +230,7 @@ impl ParticleGroup {
                                               self.particles[i].v.z.abs() < self.old_particles[i].v.z.abs();
 
                     if debug && any_axis_speed_unstable {
-                       eprintln!("slowing down: old: {}, new: {}", self.old_particles[i].v, self.particles[i].v);
+                       trace!("slowing down: old: {}, new: {}", self.old_particles[i].v, self.particles[i].v);
                     }
                 }
             }
@@ -246,7 +264,7 @@ impl ParticleGroup {
 
                     if debug {
                         if possibly_collides {
-                            eprintln!("{} may collide with {}", other_particle, particle);
+                            trace!("{} may collide with {}", other_particle, particle);
                         }
                     }
 
@@ -254,54 +272,57 @@ impl ParticleGroup {
                 })
             });
 
-        if !must_ret && no_collisions_possible {
-            None
-        } else {
-            Some(self.particles.len())
-        }
+        self.finished = !must_ret && no_collisions_possible;
+        true
     }
 }
 
-impl Iterator for ParticleGroup {
-    type Item = usize;
+impl Simulation for ParticleGroup {
+    type State = usize;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.next_debug(false)
+    fn step(&mut self) -> bool {
+        self.step_debug(false)
+    }
+
+    fn state(&self) -> usize {
+        self.particles.len()
     }
 }
 
 
-fn solve_a(input : &str) -> usize {
-    let mut particles = ParticleGroup::new(input.lines().enumerate().map(|(i, line)| {
-        Particle::from(i, line)
-    }).collect::<Vec<Particle>>());
+fn parse_particles(input : &str) -> Result<Vec<Particle>, ParseError> {
+    let mut num = 0;
+    parse_lines(input, |line| {
+        num += 1;
+        Particle::from(num - 1, line)
+    })
+}
+
+pub fn solve_a(input : &str) -> Result<usize, ParseError> {
+    let mut particles = ParticleGroup::new(parse_particles(input)?);
 
     particles.sort_by_reach();
 
     for (i, particle) in particles.particles.iter().enumerate() {
-        eprintln!("{}: {}", i, particle);
+        trace!("{}: {}", i, particle);
     }
 
-    particles.particles[0].num
+    Ok(particles.particles[0].num)
 }
 
-fn solve_b(input : &str) -> usize {
-    let mut particles = ParticleGroup::new(input.lines().enumerate().map(|(i, line)| {
-        Particle::from(i, line)
-    }).collect::<Vec<Particle>>());
+pub fn solve_b(input : &str) -> Result<usize, ParseError> {
+    let mut particles = ParticleGroup::new(parse_particles(input)?);
 
     let mut iterations = 0;
-    let mut final_num_remaining = 0;
-    while let Some(num_remaining) = particles.next_debug(iterations % 200 == 0) {
-        final_num_remaining = num_remaining;
+    while particles.step_debug(iterations % 200 == 0) {
         if iterations > 0 {
             if iterations % 100 == 0 {
-                eprintln!("iter {}: {} left", iterations, particles.particles.len());
+                debug!("iter {}: {} left", iterations, particles.particles.len());
             }
 
             if iterations % 1000 == 0 {
                 for (i, particle) in particles.particles.iter().enumerate() {
-                    eprintln!("iter {}: {}: {}", iterations, i, particle);
+                    trace!("iter {}: {}: {}", iterations, i, particle);
                 }
             }
         }
@@ -309,18 +330,26 @@ fn solve_b(input : &str) -> usize {
         iterations += 1;
     }
 
-    eprintln!("took {} steps", iterations);
-    final_num_remaining
+    debug!("took {} steps", iterations);
+    Ok(particles.state())
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = usize;
+    type AnswerB = usize;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn day(&self) -> u32 {
+        20
+    }
+
+    fn solve_a(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_a(input)?)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_b(input)?)
     }
 }
 
@@ -333,7 +362,7 @@ mod test {
         let input =
 r"p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>
 p=<4,0,0>, v=<0,0,0>, a=<-2,0,0>";
-        assert_eq!(solve_a(&input), 0);
+        assert_eq!(solve_a(&input), Ok(0));
     }
 
     #[test]
@@ -343,7 +372,7 @@ r"p=<-6,0,0>, v=<3,0,0>, a=<0,0,0>
 p=<-4,0,0>, v=<2,0,0>, a=<0,0,0>
 p=<-2,0,0>, v=<1,0,0>, a=<0,0,0>
 p=<3,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 1);
+        assert_eq!(solve_b(&input), Ok(1));
     }
 
     #[test]
@@ -353,7 +382,7 @@ r"p=<-600,0,0>, v=<3,0,0>, a=<0,0,0>
 p=<-400,0,0>, v=<2,0,0>, a=<0,0,0>
 p=<-200,0,0>, v=<1,0,0>, a=<0,0,0>
 p=<300,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 1);
+        assert_eq!(solve_b(&input), Ok(1));
     }
 
     #[test]
@@ -362,7 +391,7 @@ p=<300,0,0>, v=<-1,0,0>, a=<0,0,0>";
 r"p=<0,0,0>, v=<2,0,0>, a=<0,0,0>
 p=<100,0,0>, v=<1,0,0>, a=<0,0,0>
 p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 1);
+        assert_eq!(solve_b(&input), Ok(1));
     }
 
     #[test]
@@ -371,7 +400,7 @@ p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
 r"p=<0,0,0>, v=<0,2,0>, a=<0,0,0>
 p=<0,100,0>, v=<0,1,0>, a=<0,0,0>
 p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 1);
+        assert_eq!(solve_b(&input), Ok(1));
     }
 
     #[test]
@@ -380,7 +409,7 @@ p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
 r"p=<0,0,0>, v=<0,0,2>, a=<0,0,0>
 p=<0,0,100>, v=<0,0,1>, a=<0,0,0>
 p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 1);
+        assert_eq!(solve_b(&input), Ok(1));
     }
 
     #[test]
@@ -389,7 +418,22 @@ p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
 r"p=<0,0,0>, v=<0,2,0>, a=<0,0,0>
 p=<0,100,0>, v=<1,0,0>, a=<0,0,0>
 p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 3);
+        assert_eq!(solve_b(&input), Ok(3));
+    }
+
+    #[test]
+    fn b_stops_moving() {
+        let input =
+r"p=<0,0,0>, v=<0,2,0>, a=<0,0,0>
+p=<0,100,0>, v=<1,0,0>, a=<0,0,0>";
+        let mut particles = ParticleGroup::new(parse_particles(input).unwrap()).counted();
+        let steps = particles.run_to_end();
+        assert_eq!(particles.steps(), steps);
+
+        let positions = particles.inner().particles.iter().map(|particle| particle.p.clone()).collect::<Vec<_>>();
+        assert!(!particles.step());
+        assert!(particles.inner().particles.iter().map(|particle| particle.p.clone()).eq(positions.into_iter()));
+        assert_eq!(particles.steps(), steps);
     }
 
     #[test]
@@ -398,7 +442,7 @@ p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
 r"p=<0,0,0>, v=<0,2,100>, a=<0,0,-1>
 p=<0,50,0>, v=<1,0,0>, a=<0,0,0>
 p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 3);
+        assert_eq!(solve_b(&input), Ok(3));
     }
 
     #[test]
@@ -406,7 +450,7 @@ p=<-200,0,0>, v=<-1,0,0>, a=<0,0,0>";
         let input =
 r"p=<50,0,0>, v=<2,0,0>, a=<0,0,0>
 p=<0,0,0>, v=<1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 2);
+        assert_eq!(solve_b(&input), Ok(2));
     }
 
     #[test]
@@ -414,7 +458,7 @@ p=<0,0,0>, v=<1,0,0>, a=<0,0,0>";
         let input =
 r"p=<1,0,0>, v=<0,1,0>, a=<0,0,0>
 p=<0,0,0>, v=<0,1,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 2);
+        assert_eq!(solve_b(&input), Ok(2));
     }
 
     #[test]
@@ -422,6 +466,13 @@ p=<0,0,0>, v=<0,1,0>, a=<0,0,0>";
         let input =
 r"p=<1,0,0>, v=<1,0,0>, a=<0,0,0>
 p=<0,0,0>, v=<1,0,0>, a=<0,0,0>";
-        assert_eq!(solve_b(&input), 2);
+        assert_eq!(solve_b(&input), Ok(2));
+    }
+
+    #[test]
+    fn bad_particles() {
+        assert_eq!(solve_a("p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>\np=<4,0,0>, v=<0,0,0>, a=<-2,x,0>"), Err(ParseError::new(2, 29, "a number", "x")));
+        assert_eq!(solve_a("p=<3,0,0>, v=<2,0>, a=<-1,0,0>"), Err(ParseError::new(1, 14, "a vector like <1,-2,3>", "<2,0>")));
+        assert_eq!(solve_b("p=<3,0,0>"), Err(ParseError::new(1, 1, "a particle like \"p=<1,2,3>, v=<0,0,0>, a=<-1,0,1>\"", "p=<3,0,0>")));
     }
 }
@@ -1,10 +1,13 @@
 #![feature(nll)]
 
+use std::collections::HashMap;
 use std::fmt;
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::grid::symmetry::SymmetryKey;
 use aoclib::onoffpixel::OnOffPixel;
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
 
 type PixelGrid = aoclib::grid::Grid<OnOffPixel>;
 
@@ -18,14 +21,16 @@ thread_local!(static INITIAL_ART : PixelGrid = PixelGrid::from_rows(
         vec![OnOffPixel::Off, OnOffPixel::Off, OnOffPixel::On],
         vec![OnOffPixel::On, OnOffPixel::On, OnOffPixel::On],]));
 
+type PatternKey = SymmetryKey<bool>;
+
 struct Transformation {
-    input_variants : Vec<PixelGrid>,
+    input : PixelGrid,
     output : PixelGrid,
 }
 
 struct Art<'t> {
     grid : PixelGrid,
-    transformations : &'t [Transformation],
+    transformations : HashMap<PatternKey, &'t PixelGrid>,
 }
 
 impl Transformation {
@@ -40,46 +45,17 @@ impl Transformation {
     fn from(input : &str) -> Transformation {
         let mut sp = input.split(" => ");
 
-        let base_input = sp.next().unwrap();
-        let mut input_grid = Self::parse_to_grid(base_input);
-
-        let mut input_variants = vec![];
-        for _ in 0 .. 4 {
-            let next = input_grid.rotate_right();
-            if !input_variants.iter().any(|already| { *already == input_grid }) {
-                input_variants.push(input_grid);
-            }
-            input_grid = next;
-        }
-
-        input_grid = input_grid.flip_across_y();
-        for _ in 0 .. 4 {
-            let next = input_grid.rotate_right();
-            if !input_variants.iter().any(|already| { *already == input_grid }) {
-                input_variants.push(input_grid.clone());
-            }
-            input_grid = next;
-        }
-
         Transformation {
-            input_variants,
+            input : Self::parse_to_grid(sp.next().unwrap()),
             output : Self::parse_to_grid(sp.next().unwrap()),
         }
     }
+}
 
-    fn input_size(&self) -> usize {
-        self.input_variants[0].size_x()
-    }
-
-    fn output_size(&self) -> usize {
-        self.output.size_x()
-    }
-
-    fn matches_on(&self, other : &PixelGrid, offset_in_other_x : usize, offset_in_other_y : usize) -> bool {
-        self.input_variants.iter().any(|variant| {
-            variant.matches_on(other, offset_in_other_x, offset_in_other_y)
-        })
-    }
+// Every rotation and reflection of a pattern has the same key, so a block only needs to be looked
+// up once rather than compared against each variant of each pattern.
+fn pattern_key(grid : &PixelGrid) -> PatternKey {
+    grid.symmetry_key_by(|pixel| *pixel == OnOffPixel::On)
 }
 
 impl<'t> Art<'t> {
@@ -88,10 +64,19 @@ impl<'t> Art<'t> {
             grid : INITIAL_ART.with(|init| {
                 init.clone()
             }),
-            transformations
+            transformations : transformations.iter().map(|transformation| {
+                (pattern_key(&transformation.input), &transformation.output)
+            }).collect(),
         }
     }
 
+    // The size by size square of the grid with its top left corner at (x, y).
+    fn block(&self, x : usize, y : usize, size : usize) -> PixelGrid {
+        PixelGrid::from_rows((y .. y + size).map(|row| {
+            (x .. x + size).map(|col| self.grid.get(col, row).unwrap().clone()).collect()
+        }).collect())
+    }
+
     fn num_on(&self) -> usize {
         self.grid.iter().fold(0, |sofar, item| {
             sofar + if *item == OnOffPixel::On { 1 } else { 0 }
@@ -105,11 +90,11 @@ impl<'t> fmt::Display for Art<'t> {
     }
 }
 
-impl<'t> Iterator for Art<'t> {
-    type Item = usize;
+impl<'t> Simulation for Art<'t> {
+    type State = usize;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        eprintln!("grid size {}", self.grid.size_x());
+    fn step(&mut self) -> bool {
+        debug!("grid size {}", self.grid.size_x());
 
         let input_transform_size = if self.grid.size_x() % 2 == 0 {
             2
@@ -133,15 +118,9 @@ impl<'t> Iterator for Art<'t> {
 
         for base_y in 0 .. self.grid.size_y() / input_transform_size {
             for base_x in 0 .. self.grid.size_x() / input_transform_size {
-                let input_x = base_x * input_transform_size;
-                let input_y = base_y * input_transform_size;
-
-                for transformation in self.transformations.iter().filter(|transformation| {
-                    transformation.input_size() == input_transform_size
-                }) {
-                    if transformation.matches_on(&self.grid, input_x, input_y) {
-                        transformation.output.stamp_onto(&mut output_grid, base_x * transformation.output_size(), base_y * transformation.output_size());
-                    }
+                let block = self.block(base_x * input_transform_size, base_y * input_transform_size, input_transform_size);
+                if let Some(output) = self.transformations.get(&pattern_key(&block)) {
+                    output.stamp_onto(&mut output_grid, base_x * output.size_x(), base_y * output.size_y());
                 }
             }
         }
@@ -150,32 +129,45 @@ impl<'t> Iterator for Art<'t> {
 
         //let _ = eprintln!("{}", self);
 
-        Some(self.num_on())
+        true
+    }
+
+    fn state(&self) -> usize {
+        self.num_on()
     }
 }
 
 fn count_pixels_on_after_iterations(input : &str, num_iterations : usize) -> usize {
     let transformations = input.lines().map(Transformation::from).collect::<Vec<Transformation>>();
-    let art = Art::new(transformations.as_slice());
-    art.take(num_iterations).last().unwrap()
+    let mut art = Art::new(transformations.as_slice());
+    art.run_for(num_iterations);
+    art.state()
 }
 
-fn solve_a(input : &str) -> usize {
+pub fn solve_a(input : &str) -> usize {
     count_pixels_on_after_iterations(input, 5)
 }
 
-fn solve_b(input : &str) -> usize {
+pub fn solve_b(input : &str) -> usize {
     count_pixels_on_after_iterations(input, 18)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = usize;
+    type AnswerB = usize;
+
+    fn day(&self) -> u32 {
+        21
+    }
+
+    fn solve_a(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_a(input))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -187,14 +179,15 @@ mod test {
     fn transform_variants_1() {
         let input = "../.. => ../..";
         let transform = Transformation::from(&input);
-        assert_eq!(transform.input_variants.len(), 1);
+        assert_eq!(transform.input.distinct_symmetries().len(), 1);
     }
 
     #[test]
     fn transform_variants_2() {
         let input = "##./.../..# => ../..";
         let transform = Transformation::from(&input);
-        assert_eq!(transform.input_variants.len(), 8);
+        assert_eq!(transform.input.distinct_symmetries().len(), 8);
+        assert_eq!(pattern_key(&transform.input), pattern_key(&Transformation::parse_to_grid("#../.../.##")));
     }
 
     #[test]
@@ -1,11 +1,12 @@
 #![feature(nll)]
 
-use std::collections::HashMap;
-
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
 use aoclib::onoffpixel::OnOffPixel;
 use aoclib::direction::Direction;
+use aoclib::grid::Cells;
+use aoclib::grid::sparse::SparseGrid;
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
 
 #[derive(PartialEq, Clone, Debug)]
 enum InfectionState {
@@ -15,7 +16,7 @@ enum InfectionState {
     Flagged,
 }
 
-type InfectionGrid = HashMap<(i32, i32), InfectionState>;
+type InfectionGrid = SparseGrid<InfectionState>;
 
 struct WormProgress {
     pixels : InfectionGrid,
@@ -30,7 +31,7 @@ impl WormProgress {
         let offset = ((input.lines().nth(0).unwrap().len() - 1) / 2) as i32;
 
         let mut ret = WormProgress {
-            pixels : InfectionGrid::new(),
+            pixels : InfectionGrid::new(InfectionState::Clean),
             pos : (0, 0),
             dir : Direction::Up,
             num_infected : 0,
@@ -43,8 +44,8 @@ impl WormProgress {
                 if value == OnOffPixel::On {
                     // y has to be flipped
                     let pos = ((x as i32) - offset, offset - (y as i32));
-                    eprintln!("on at {:?}", pos);
-                    ret.pixels.insert(pos, InfectionState::Infected);
+                    trace!("on at {:?}", pos);
+                    ret.pixels.set(pos.0, pos.1, InfectionState::Infected);
                 }
             }
         }
@@ -53,44 +54,34 @@ impl WormProgress {
     }
 
     fn advance_a(&mut self) -> InfectionState {
-        let ret;
-        if let Some(val) = self.pixels.get_mut(&self.pos) {
-            ret = val.clone();
-            if *val == InfectionState::Infected {
-                *val = InfectionState::Clean;
-            } else {
-                *val = InfectionState::Infected;
-            }
+        let val = self.pixels.at_mut(self.pos.0, self.pos.1);
+        let ret = val.clone();
+        if *val == InfectionState::Infected {
+            *val = InfectionState::Clean;
         } else {
-            ret = InfectionState::Clean;
-            self.pixels.insert(self.pos, InfectionState::Infected);
+            *val = InfectionState::Infected;
         }
         ret
     }
 
     fn advance_b(&mut self) -> InfectionState {
-        let ret;
-        if let Some(val) = self.pixels.get_mut(&self.pos) {
-            ret = val.clone();
-
-            *val = match val {
-                &mut InfectionState::Clean => InfectionState::Weakened,
-                &mut InfectionState::Weakened => InfectionState::Infected,
-                &mut InfectionState::Infected => InfectionState::Flagged,
-                &mut InfectionState::Flagged => InfectionState::Clean,
-            };
-        } else {
-            ret = InfectionState::Clean;
-            self.pixels.insert(self.pos, InfectionState::Weakened);
-        }
+        let val = self.pixels.at_mut(self.pos.0, self.pos.1);
+        let ret = val.clone();
+
+        *val = match val {
+            &mut InfectionState::Clean => InfectionState::Weakened,
+            &mut InfectionState::Weakened => InfectionState::Infected,
+            &mut InfectionState::Infected => InfectionState::Flagged,
+            &mut InfectionState::Flagged => InfectionState::Clean,
+        };
         ret
     }
 }
 
-impl Iterator for WormProgress {
-    type Item = u32;
+impl Simulation for WormProgress {
+    type State = u32;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn step(&mut self) -> bool {
         if self.puzzle_a {
             if self.advance_a() == InfectionState::Infected {
                 self.dir = self.dir.turn_right();
@@ -122,12 +113,17 @@ impl Iterator for WormProgress {
         let offset = self.dir.step_offset();
         self.pos = (self.pos.0 + offset.0, self.pos.1 + offset.1);
 
-        Some(self.num_infected)
+        true
+    }
+
+    fn state(&self) -> u32 {
+        self.num_infected
     }
 }
 
-fn count_infected(worm : WormProgress, iterations : usize) -> u32 {
-    worm.take(iterations).last().unwrap()
+fn count_infected(mut worm : WormProgress, iterations : usize) -> u32 {
+    worm.run_for(iterations);
+    worm.state()
 }
 
 fn count_infected_a(input : &str, iterations : usize) -> u32 {
@@ -140,22 +136,30 @@ fn count_infected_b(input : &str, iterations : usize) -> u32 {
     count_infected(worm, iterations)
 }
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     count_infected_a(input, 10000)
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     count_infected_b(input, 10000000)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        22
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input))
+    }
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
     }
 }
 
@@ -0,0 +1,71 @@
+#![feature(nll)]
+
+#[macro_use] extern crate aoclib;
+use aoclib::aocisa::*;
+use aoclib::aocisa::dialect::{Coprocessor, Interpreter};
+use aoclib::aocisa::optimize::OptimizedProgram;
+use aoclib::aocisa::profile::Profiler;
+use aoclib::aocisa::source::Source;
+use aoclib::parse_error::ParseError;
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
+
+pub fn solve_a(input : &str) -> Result<u64, ParseError> {
+    let source = Source::parse(input)?;
+    let mut profiler = Profiler::with_interpreter(Interpreter::new(&source, Coprocessor)?);
+    profiler.run_to_end();
+    Ok(profiler.opcode_count("mul"))
+}
+
+pub fn solve_b(input : &str) -> Result<i64, ParseError> {
+    let program = Program::load(input)?;
+    let program = OptimizedProgram::optimize(&program);
+    debug!("replaced {} loops", program.macro_count());
+    let mut registers = RegisterHolder::new();
+    *registers.get_reg_mut('a') = 1;
+    program.run(&mut registers);
+    Ok(*registers.get_reg('h'))
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u64;
+    type AnswerB = i64;
+
+    fn day(&self) -> u32 {
+        23
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u64, SolveError> {
+        Ok(solve_a(input)?)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<i64, SolveError> {
+        Ok(solve_b(input)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn a_1() {
+        let input =
+r"mul a 1
+mul a 1
+mul a 1";
+        assert_eq!(solve_a(&input), Ok(3));
+    }
+
+    #[test]
+    fn a_unsupported() {
+        assert_eq!(solve_a("set a 1\n\n# plays a\nsnd a"), Err(ParseError::new(4, 1, "one of set, sub, mul, jnz", "snd")));
+    }
+
+    #[test]
+    fn b_malformed() {
+        assert_eq!(solve_b("set b 1\nblah"), Err(ParseError::new(2, 1, "an instruction", "blah")));
+    }
+}
@@ -4,8 +4,9 @@ use std::fmt;
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::{ParseError, parse_field, parse_lines};
+use aoclib::solution::{Solution, SolveError};
 
 #[derive(Clone)]
 struct Component {
@@ -22,13 +23,18 @@ struct Bridge<'p> {
 }
 
 impl Component {
-    fn parse(line : &str, num : usize) -> Component {
-        let mut split = line.split('/');
-        Component {
+    fn parse(line : &str, num : usize) -> Result<Component, ParseError> {
+        let mut split = line.splitn(2, '/');
+        let port1 = split.next().unwrap();
+        let port2 = split.next().ok_or_else(|| {
+            ParseError::in_line(line, line, "a component like \"0/2\"")
+        })?;
+
+        Ok(Component {
             num,
-            port1 : split.next().unwrap().parse::<u32>().unwrap(),
-            port2 : split.next().unwrap().parse::<u32>().unwrap(),
-        }
+            port1 : parse_field(line, port1, "a port number")?,
+            port2 : parse_field(line, port2, "a port number")?,
+        })
     }
 
     fn get_strength(&self) -> u32 {
@@ -113,10 +119,18 @@ impl<'p> fmt::Display for Bridge<'p> {
     }
 }
 
-fn get_strongest_bridge_strength(input : &str) -> u32 {
-    let components = input.lines().enumerate().map(|(i, line)| {
-        Component::parse(line, i)
-    }).collect::<HashSet<_>>();
+fn parse_components(input : &str) -> Result<HashSet<Component>, ParseError> {
+    let mut num = 0;
+    let components = parse_lines(input, |line| {
+        num += 1;
+        Component::parse(line, num - 1)
+    })?;
+
+    Ok(components.into_iter().collect())
+}
+
+fn get_strongest_bridge_strength(input : &str) -> Result<u32, ParseError> {
+    let components = parse_components(input)?;
 
     let mut frontier = vec![Bridge::new(&components)];
     let mut current_explore_index = 0;
@@ -126,7 +140,7 @@ fn get_strongest_bridge_strength(input : &str) -> u32 {
 
         let current_strength = frontier[current_explore_index].get_strength();
         if current_strength > strongest {
-            eprintln!("strongest upgraded to {} ({}). frontier size is {}", frontier[current_explore_index], current_strength, frontier.len());
+            debug!("strongest upgraded to {} ({}). frontier size is {}", frontier[current_explore_index], current_strength, frontier.len());
             strongest = current_strength;
         }
 
@@ -139,13 +153,11 @@ fn get_strongest_bridge_strength(input : &str) -> u32 {
         //eprintln!("moving on to explore {}", current_explore_index);
     }
 
-    strongest
+    Ok(strongest)
 }
 
-fn get_longest_bridge_strength(input : &str) -> u32 {
-    let components = input.lines().enumerate().map(|(i, line)| {
-        Component::parse(line, i)
-    }).collect::<HashSet<_>>();
+fn get_longest_bridge_strength(input : &str) -> Result<u32, ParseError> {
+    let components = parse_components(input)?;
 
     let mut frontier = vec![Bridge::new(&components)];
     let mut current_explore_index = 0;
@@ -158,12 +170,12 @@ fn get_longest_bridge_strength(input : &str) -> u32 {
         if current_length > longest_length {
             longest_length = current_length;
             longest_strength = frontier[current_explore_index].get_strength();
-            eprintln!("longest upgraded to {} ({}/{}). frontier size is {}", frontier[current_explore_index], longest_length, longest_strength, frontier.len());
+            debug!("longest upgraded to {} ({}/{}). frontier size is {}", frontier[current_explore_index], longest_length, longest_strength, frontier.len());
         } else if current_length == longest_length {
             let current_strength = frontier[current_explore_index].get_strength();
             if current_strength > longest_strength {
                 longest_strength = current_strength;
-                eprintln!("strongest upgraded to {} ({}/{}). frontier size is {}", frontier[current_explore_index], longest_length, longest_strength, frontier.len());
+                debug!("strongest upgraded to {} ({}/{}). frontier size is {}", frontier[current_explore_index], longest_length, longest_strength, frontier.len());
             }
         }
 
@@ -176,25 +188,33 @@ fn get_longest_bridge_strength(input : &str) -> u32 {
         //eprintln!("moving on to explore {}", current_explore_index);
     }
 
-    longest_strength
+    Ok(longest_strength)
 }
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> Result<u32, ParseError> {
     get_strongest_bridge_strength(input)
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> Result<u32, ParseError> {
     get_longest_bridge_strength(input)
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+    fn day(&self) -> u32 {
+        24
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_a(input)?)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input)?)
     }
 }
 
@@ -213,7 +233,7 @@ r"0/2
 0/1
 10/1
 9/10";
-        assert_eq!(solve_a(&input), 31);
+        assert_eq!(solve_a(&input), Ok(31));
     }
 
     #[test]
@@ -221,7 +241,7 @@ r"0/2
         let input =
 r"0/1000
 0/100";
-        assert_eq!(solve_a(&input), 1000);
+        assert_eq!(solve_a(&input), Ok(1000));
     }
 
     #[test]
@@ -230,19 +250,19 @@ r"0/1000
 r"0/1000
 0/100
 100/901";
-        assert_eq!(solve_a(&input), 1101);
+        assert_eq!(solve_a(&input), Ok(1101));
 
         let input =
 r"100/901
 0/1000
 0/100";
-        assert_eq!(solve_a(&input), 1101);
+        assert_eq!(solve_a(&input), Ok(1101));
 
         let input =
 r"100/901
 0/100
 0/1000";
-        assert_eq!(solve_a(&input), 1101);
+        assert_eq!(solve_a(&input), Ok(1101));
     }
 
     #[test]
@@ -254,7 +274,7 @@ r"0/1
 2/3
 1/5
 2/5";
-        assert_eq!(solve_a(&input), 22);
+        assert_eq!(solve_a(&input), Ok(22));
     }
 
     #[test]
@@ -268,6 +288,12 @@ r"0/2
 0/1
 10/1
 9/10";
-        assert_eq!(solve_b(&input), 19);
+        assert_eq!(solve_b(&input), Ok(19));
+    }
+
+    #[test]
+    fn bad_components() {
+        assert_eq!(solve_a("0/2\n2-2"), Err(ParseError::new(2, 1, "a component like \"0/2\"", "2-2")));
+        assert_eq!(solve_b("0/2\n2/x"), Err(ParseError::new(2, 3, "a port number", "x")));
     }
 }
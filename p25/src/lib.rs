@@ -0,0 +1,406 @@
+#![feature(nll)]
+
+use std::fmt;
+use std::collections::HashMap;
+
+#[macro_use] extern crate lazy_static;
+extern crate regex;
+use regex::Regex;
+
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::{ParseError, column_of, parse_field};
+use aoclib::simulation::Simulation;
+use aoclib::solution::{Solution, SolveError};
+
+#[derive(PartialEq)]
+enum Direction {
+    Left,
+    Right,
+}
+
+struct Action {
+    condition_val : bool,
+    write_val : bool,
+    dir : Direction,
+    next_state : char,
+
+    // The line and column of next_state, to report it if there's no such state.
+    next_state_at : (usize, usize),
+}
+
+struct State {
+    actions : [Action ; 2],
+}
+
+struct Machine {
+    initial_state : char,
+    checksum_after : usize,
+    states : HashMap<char, State>,
+}
+
+struct Execution<'m> {
+    machine : &'m Machine,
+    state : char,
+    pos : usize,
+    steps_taken : usize,
+    num_ones : usize,
+    tape : Vec<bool>,
+}
+
+impl Direction {
+    fn parse(input : &str) -> Result<Direction, ParseError> {
+        if input == "left" {
+            Ok(Direction::Left)
+        } else if input == "right" {
+            Ok(Direction::Right)
+        } else {
+            Err(ParseError::in_line(input, input, "left or right"))
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self == &Direction::Left {
+            write!(f, "left")
+        } else {
+            write!(f, "right")
+        }
+    }
+}
+
+impl Action {
+    // lines yields each line along with its 1-based line number, for error reporting. taken is the
+    // current value that the state's other action is for, if it's been loaded already.
+    fn load<'t>(lines : &mut impl Iterator<Item = (usize, &'t str)>, taken : Option<bool>) -> Result<Action, ParseError> {
+        lazy_static! {
+            static ref RE_CONDITION : regex::Regex = Regex::new(r"^\s*If the current value is (\d+):$").expect("failed to compile regex");
+            static ref RE_WRITE : regex::Regex = Regex::new(r"^\s*- Write the value (\d+)\.$").expect("failed to compile regex");
+            static ref RE_MOVE : regex::Regex = Regex::new(r"^\s*- Move one slot to the (\w+)\.$").expect("failed to compile regex");
+            static ref RE_NEXT_STATE : regex::Regex = Regex::new(r"^\s*- Continue with state (\w)\.$").expect("failed to compile regex");
+        }
+
+        let mut condition_val = false;
+        let mut write_val = false;
+        let mut dir = Direction::Left;
+        let mut last_line_number = 0;
+
+        for (line_number, line) in lines {
+            last_line_number = line_number;
+            if let Some(captures) = RE_CONDITION.captures_iter(line).next() {
+                let field = captures.get(1).unwrap().as_str();
+                condition_val = parse_field::<u8>(line, field, "a tape value").map_err(|e| e.on_line(line_number))? != 0;
+                if taken == Some(condition_val) {
+                    return Err(ParseError::in_line(line, field, &format!("the other action's value, {}", if condition_val { 0 } else { 1 })).on_line(line_number));
+                }
+            } else if let Some(captures) = RE_WRITE.captures_iter(line).next() {
+                write_val = parse_field::<u8>(line, captures.get(1).unwrap().as_str(), "a tape value").map_err(|e| e.on_line(line_number))? != 0;
+            } else if let Some(captures) = RE_MOVE.captures_iter(line).next() {
+                let dir_name = captures.get(1).unwrap().as_str();
+                dir = Direction::parse(dir_name).map_err(|e| e.within(line, dir_name).on_line(line_number))?;
+            } else if let Some(captures) = RE_NEXT_STATE.captures_iter(line).next() {
+                let state_name = captures.get(1).unwrap();
+                return Ok(Action {
+                    condition_val,
+                    write_val,
+                    dir,
+                    next_state : state_name.as_str().chars().nth(0).unwrap(),
+                    next_state_at : (line_number, column_of(line, state_name.as_str())),
+                });
+            }
+        }
+
+        Err(ParseError::new(last_line_number + 1, 1, "- Continue with state X.", ""))
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+r"  If the current value is {}:
+    - Write the value {}.
+    - Move one slot to the {}.
+    - Continue with state {}.
+",
+            if self.condition_val { 1 } else { 0 },
+            if self.write_val { 1 } else { 0 },
+            self.dir,
+            self.next_state)
+    }
+}
+
+impl State {
+    fn load<'t>(lines : &mut impl Iterator<Item = (usize, &'t str)>) -> Result<State, ParseError> {
+        let action0 = Action::load(lines, None)?;
+        let action1 = Action::load(lines, Some(action0.condition_val))?;
+
+        Ok(State {
+            actions : [action0, action1],
+        })
+    }
+
+    fn get_action_for_condition(&self, condition_val : bool) -> &Action {
+        self.actions.iter().find(|action| {
+            action.condition_val == condition_val
+        }).unwrap()
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ret = write!(f, "");
+        for action in self.actions.iter() {
+            ret = write!(f, "{}", action)
+        }
+        ret
+    }
+}
+
+impl Machine {
+    fn load(input : &str) -> Result<Machine, ParseError> {
+        lazy_static! {
+            static ref RE_BEGIN : regex::Regex = Regex::new(r"^Begin in state (\w)\.$").expect("failed to compile regex");
+            static ref RE_CHECKSUM : regex::Regex = Regex::new(r"^Perform a diagnostic checksum after (\d+) steps\.$").expect("failed to compile regex");
+            static ref RE_START_STATE : regex::Regex = Regex::new(r"^In state (\w):$").expect("failed to compile regex");
+        }
+
+        let mut initial_state = None;
+        let mut checksum_after = 0;
+        let mut states : HashMap<char, State> = HashMap::new();
+
+        let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
+        while let Some((line_number, line)) = lines.next() {
+            if let Some(captures) = RE_BEGIN.captures_iter(line).next() {
+                let state_name = captures.get(1).unwrap().as_str();
+                initial_state = Some((state_name.chars().nth(0).unwrap(), (line_number, column_of(line, state_name))));
+            } else if let Some(captures) = RE_CHECKSUM.captures_iter(line).next() {
+                checksum_after = parse_field(line, captures.get(1).unwrap().as_str(), "a step count").map_err(|e| e.on_line(line_number))?;
+            } else if let Some(captures) = RE_START_STATE.captures_iter(line).next() {
+                let state_name = captures.get(1).unwrap().as_str().chars().nth(0).unwrap();
+                states.insert(state_name, State::load(&mut lines)?);
+            }
+        }
+
+        let (initial_state, initial_state_at) = initial_state.ok_or_else(|| {
+            ParseError::new(1, 1, "a line like \"Begin in state A.\"", input.lines().next().unwrap_or(""))
+        })?;
+
+        // Every state that's started in or moved to has to be defined. The first such reference
+        // in the input is reported, whatever order the states are stored in.
+        let dangling = states.values().flat_map(|state| state.actions.iter()).map(|action| (action.next_state, action.next_state_at))
+            .chain(Some((initial_state, initial_state_at)))
+            .filter(|&(name, _)| !states.contains_key(&name))
+            .min_by_key(|&(_, at)| at);
+
+        if let Some((name, (line, column))) = dangling {
+            return Err(ParseError::new(line, column, "a state with an \"In state\" section", &name.to_string()));
+        }
+
+        Ok(Machine {
+            initial_state,
+            checksum_after,
+            states,
+        })
+    }
+}
+
+impl fmt::Display for Machine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ret;
+        ret = write!(f,
+r"Begin in state {}.
+Perform a diagnostic checksum after {} steps.
+
+",
+                  self.initial_state, self.checksum_after);
+
+        for (name, state) in self.states.iter() {
+            ret = write!(f, "In state {}:\n{}\n", name, state);
+        }
+        ret
+    }
+}
+
+impl<'m> Execution<'m> {
+    fn new(machine : &'m Machine) -> Execution<'m> {
+        Execution {
+            machine,
+            state : machine.initial_state,
+            pos : machine.checksum_after,
+            steps_taken : 0,
+            num_ones : 0,
+            tape : vec![false ; machine.checksum_after * 2 + 1],
+        }
+    }
+}
+
+impl<'m> Simulation for Execution<'m> {
+    type State = usize;
+
+    fn step(&mut self) -> bool {
+        if self.steps_taken < self.machine.checksum_after {
+            self.steps_taken += 1;
+
+            let state = self.machine.states.get(&self.state).unwrap();
+            let cursor_val = self.tape.get_mut(self.pos).unwrap();
+
+            let action = state.get_action_for_condition(*cursor_val);
+
+            if *cursor_val != action.write_val {
+                if action.write_val == true {
+                    self.num_ones += 1;
+                } else {
+                    self.num_ones -= 1;
+                }
+            }
+
+            *cursor_val = action.write_val;
+
+            if action.dir == Direction::Left {
+                self.pos -= 1;
+            } else {
+                self.pos += 1;
+            }
+
+            self.state = action.next_state;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn state(&self) -> usize {
+        self.num_ones
+    }
+}
+
+pub fn solve_a(input : &str) -> Result<usize, ParseError> {
+    let machine = Machine::load(input)?;
+    debug!("machine:\n{}", machine);
+    let mut execution = Execution::new(&machine);
+    execution.run_to_end();
+    Ok(execution.state())
+}
+
+pub fn solve_b(input : &str) -> u32 {
+    0
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type AnswerA = usize;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        25
+    }
+
+    fn solve_a(&self, input : &str) -> Result<usize, SolveError> {
+        Ok(solve_a(input)?)
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, SolveError> {
+        Ok(solve_b(input))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn a_given() {
+        let input =
+r"Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.";
+
+        assert_eq!(solve_a(&input), Ok(3));
+    }
+
+    #[test]
+    fn bad_blueprint() {
+        let input =
+r"Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the up.
+    - Continue with state A.";
+
+        assert_eq!(solve_a(&input), Err(ParseError::new(7, 28, "left or right", "up")));
+        assert_eq!(solve_a("Perform a diagnostic checksum after 99999999999999999999 steps."), Err(ParseError::new(1, 37, "a step count", "99999999999999999999")));
+    }
+
+    #[test]
+    fn bad_states() {
+        let input =
+r"Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state C.";
+
+        let error = "a state with an \"In state\" section";
+        assert_eq!(solve_a(&input), Err(ParseError::new(12, 27, error, "C")));
+        assert_eq!(solve_a(&input.replace("Begin in state A", "Begin in state B")), Err(ParseError::new(1, 16, error, "B")));
+        assert_eq!(solve_a(&input.replace("Begin in state A.", "")), Err(ParseError::new(1, 1, "a line like \"Begin in state A.\"", "")));
+        assert_eq!(solve_a(&input.replace("value is 1", "value is 0")), Err(ParseError::new(9, 27, "the other action's value, 1", "0")));
+        assert_eq!(solve_a(&input.replace("\n    - Continue with state C.", "")), Err(ParseError::new(12, 1, "- Continue with state X.", "")));
+    }
+
+    #[test]
+    fn a_always_left() {
+        let input =
+r"Begin in state A.
+Perform a diagnostic checksum after 3 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.";
+
+        assert_eq!(solve_a(&input), Ok(3));
+    }
+
+    #[test]
+    fn b_1() {
+        let input = "blah";
+        assert_eq!(solve_b(&input), 0);
+    }
+}
@@ -1,7 +1,8 @@
 #![feature(nll)]
 
-extern crate aoclib;
-use aoclib::*;
+#[macro_use] extern crate aoclib;
+use aoclib::parse_error::ParseError;
+use aoclib::solution::Solution;
 
 /*
 impl Iterator for Thing {
@@ -12,22 +13,30 @@ impl Iterator for Thing {
 }
 */
 
-fn solve_a(input : &str) -> u32 {
+pub fn solve_a(input : &str) -> u32 {
     0
 }
 
-fn solve_b(input : &str) -> u32 {
+pub fn solve_b(input : &str) -> u32 {
     0
 }
 
-fn main() {
-    let input = read_all_stdin();
-    //eprintln!("input: {}", input);
+pub struct Solver;
 
-    if aoclib::should_solve_puzzle_a() {
-        println!("answer: {}", solve_a(&input));
-    } else {
-        println!("answer: {}", solve_b(&input));
+impl Solution for Solver {
+    type AnswerA = u32;
+    type AnswerB = u32;
+
+    fn day(&self) -> u32 {
+        0
+    }
+
+    fn solve_a(&self, input : &str) -> Result<u32, ParseError> {
+        Ok(solve_a(input))
+    }
+
+    fn solve_b(&self, input : &str) -> Result<u32, ParseError> {
+        Ok(solve_b(input))
     }
 }
 